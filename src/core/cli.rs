@@ -1,4 +1,6 @@
-use clap::{Args, Parser, Subcommand};
+use crate::core::users::Role;
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use serde::Deserialize;
 
 pub const DEFAULT_CONFIG_NAME: &str = "config.yaml";
@@ -30,8 +32,58 @@ impl ConfigFileProvider for ConfigArgs {
     }
 }
 
+impl From<&ConfigArgs> for ServeArgs {
+    fn from(config_args: &ConfigArgs) -> Self {
+        Self {
+            config: config_args.config.clone(),
+            exit_after: None,
+            port: None,
+        }
+    }
+}
+
 #[derive(Args, Clone, Debug, Deserialize, PartialEq)]
-pub struct RootPasswordArgs;
+pub struct ExportArgs {
+    #[command(flatten)]
+    pub(crate) config_args: ConfigArgs,
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = ExportFormat::Json)]
+    pub(crate) format: ExportFormat,
+    /// File to write to instead of stdout
+    #[arg(short, long)]
+    pub(crate) output: Option<String>,
+    /// Only export entities updated since this date, or RFC 3339 time
+    #[arg(long, value_parser = parse_since)]
+    pub(crate) since: Option<DateTime<Utc>>,
+    /// Source whose stored entities to export
+    #[arg(short, long)]
+    pub(crate) source: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    Yaml,
+}
+
+fn parse_since(since: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(since)
+        .map(|time| time.to_utc())
+        .or_else(|_| {
+            NaiveDate::parse_from_str(since, "%Y-%m-%d")
+                .map(|date| date.and_time(Default::default()).and_utc())
+        })
+        .map_err(|_| {
+            format!(
+                "{} is not a date like 2024-06-01 or an RFC 3339 time",
+                since
+            )
+        })
+}
 
 #[derive(Args, Clone, Debug, Deserialize, PartialEq)]
 pub struct ServeArgs {
@@ -68,6 +120,35 @@ impl ServeArgs {
 #[derive(Clone, Debug, Deserialize, PartialEq, Subcommand)]
 pub enum Command {
     Config(ConfigArgs),
-    RootPassword(RootPasswordArgs),
+    /// Write stored entities out for other tools
+    Export(ExportArgs),
+    /// Set the password of the root admin
+    RootPassword(ConfigArgs),
     Serve(ServeArgs),
+    /// Sync every source once, then exit
+    Sync(ConfigArgs),
+    /// Manage who can log in to the web UI
+    Users(UsersArgs),
+}
+
+#[derive(Args, Clone, Debug, Deserialize, PartialEq)]
+pub struct UsersArgs {
+    #[command(subcommand)]
+    pub(crate) command: UsersCommand,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Subcommand)]
+pub enum UsersCommand {
+    /// Add a user, or change an existing user's password and role
+    Add {
+        #[command(flatten)]
+        config_args: ConfigArgs,
+        name: String,
+        #[arg(short, long, value_enum, default_value_t = Role::Viewer)]
+        role: Role,
+    },
+    /// List who can log in, and their roles
+    List,
+    /// Stop a user logging in. The last admin can't be removed
+    Remove { name: String },
 }
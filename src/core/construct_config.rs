@@ -193,7 +193,7 @@ fn read_boolean(message: &str, default_yes: bool) -> bool {
         } else if input == "n" || input == "no" {
             result = false;
             break;
-        } else if input == "" {
+        } else if input.is_empty() {
             result = default_yes;
             break;
         } else {
@@ -225,10 +225,5 @@ fn read_optional_u16(message: &str) -> Option<u16> {
     let mut input = String::new();
     stdin().read_line(&mut input).expect("Error reading input");
 
-    let result = input.trim().parse::<u16>();
-
-    match result {
-        Ok(value) => Some(value),
-        Err(_) => None,
-    }
+    input.trim().parse::<u16>().ok()
 }
@@ -1,30 +1,30 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use log::trace;
+use log::{info, trace};
 use tokio::task::{AbortHandle, JoinSet};
 use tokio::time::sleep;
 
 use crate::core::node_handles::NodeHandles;
+use crate::core::node_registry::NodeRegistry;
 use crate::domain::config::Config;
 use crate::domain::mpsc_handle::{one_shot, OneshotMpscSenderHandle};
-use crate::domain::node::{LifecycleChannelHandle, Manager};
-use crate::integration::google::Source as GoogleSource;
-use crate::integration::log::Sink as LogSink;
-use crate::integration::stub::Source as StubSource;
+use crate::domain::node::{LifecycleChannelHandle, Manager, NodeKind, NodeState};
+use crate::integration;
 use crate::server::WebServer;
 use core::time::Duration;
 #[cfg(test)]
 use mockall::automock;
-use std::sync::atomic::AtomicBool;
-use std::sync::atomic::Ordering::SeqCst;
-use tokio::sync::Semaphore;
-use tokio::{join, task};
+use std::future::pending;
+use tokio::signal::ctrl_c;
+use tokio::sync::{Notify, Semaphore};
+use tokio::{join, select, task};
 
 #[async_trait]
 #[cfg_attr(test, automock)]
 pub trait Engine {
-    async fn start(&self);
+    async fn start(&self) -> Result<(), String>;
+    async fn sync(&self) -> Result<(), String>;
 }
 
 pub struct EngineImpl<ServerType>
@@ -33,7 +33,7 @@ where
 {
     manager: Manager,
     server: ServerType,
-    stopped: Arc<AtomicBool>,
+    stopped: Arc<Notify>,
 }
 
 #[async_trait]
@@ -41,8 +41,12 @@ impl<ServerType> Engine for EngineImpl<ServerType>
 where
     ServerType: WebServer,
 {
-    async fn start(&self) {
-        self.run().await;
+    async fn start(&self) -> Result<(), String> {
+        self.run().await
+    }
+
+    async fn sync(&self) -> Result<(), String> {
+        self.run_once().await
     }
 }
 
@@ -52,52 +56,47 @@ where
 {
     pub(crate) fn new(config: &Arc<Config>, server: ServerType) -> Self {
         Self {
-            manager: Manager::new(config, LifecycleChannelHandle::new()),
+            manager: Manager::new(
+                config,
+                LifecycleChannelHandle::with_capacity(config.lifecycle_channel_capacity()),
+            ),
             server,
-            stopped: Arc::new(AtomicBool::new(false)),
+            stopped: Arc::new(Notify::new()),
         }
     }
 
-    async fn run(&self) {
+    async fn run(&self) -> Result<(), String> {
         trace!("run");
         let mut join_set = JoinSet::new();
         let mut abort_handles = Vec::new();
 
         let wait_duration = self.manager.core_config().exit_after();
 
-        let mut stub_source = StubSource::new(&self.manager);
-        let google_source = GoogleSource::new(&self.manager, self.server.get_web_channel_handle());
-        let mut log_sink = LogSink::new(&self.manager, &stub_source.get_readonly_channel_handle());
-
         let node_handles = NodeHandles::new(&self.manager, self.server.get_web_channel_handle());
 
-        const NODE_COUNT: usize = 4;
-        let semaphore = Arc::new(Semaphore::new(NODE_COUNT));
+        let mut registry = NodeRegistry::new();
+        integration::register(&mut registry);
+        let nodes = registry.build(&node_handles)?;
+        let init_count = nodes.len();
+
+        // One permit per node, plus one for the server.
+        let permit_count = nodes.len() + 1;
+        let semaphore = Arc::new(Semaphore::new(permit_count));
+
+        for node in nodes {
+            let permit = semaphore
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("Could not acquire semaphore");
+            abort_handles.push(join_set.spawn(async move { node.run(permit).await }));
+        }
 
-        let log_permit = semaphore
-            .clone()
-            .acquire_owned()
-            .await
-            .expect("Could not acquire semaphore");
-        let stub_permit = semaphore
-            .clone()
-            .acquire_owned()
-            .await
-            .expect("Could not acquire semaphore");
-        let google_permit = semaphore
-            .clone()
-            .acquire_owned()
-            .await
-            .expect("Could not acquire semaphore");
         let server_permit = semaphore
             .clone()
             .acquire_owned()
             .await
             .expect("Could not acquire semaphore");
-        abort_handles.push(join_set.spawn(async move { log_sink.run(log_permit).await }));
-        abort_handles.push(join_set.spawn(async move { stub_source.run(stub_permit).await }));
-        abort_handles.push(join_set.spawn(async move { google_source.run(google_permit).await }));
-
         let server = self.server.clone();
         abort_handles.push(join_set.spawn(async move {
             trace!("server starting");
@@ -107,18 +106,85 @@ where
         }));
 
         let _permit = semaphore
-            .acquire_many(NODE_COUNT as u32)
+            .acquire_many(permit_count as u32)
             .await
             .expect("Could not acquire semaphore");
 
-        let (sender, join_set, abort_handle) = self.wait_for_init_responses(&mut join_set).await;
+        let (sender, join_set, abort_handle) = self
+            .wait_for_init_responses(&mut join_set, init_count)
+            .await;
         abort_handles.push(abort_handle);
         let join_set = self.send_init(join_set, sender);
 
-        let join_set = self.wait_for_timeout(join_set, wait_duration).await;
+        let join_set = self.wait_for_stop(join_set, wait_duration).await;
 
-        join!(Self::join(join_set), self.stop_checker(abort_handles));
+        select! {
+            _ = Self::join(join_set) => {}
+            _ = self.stop_checker(abort_handles) => Self::join(join_set).await,
+        }
         trace!("run completed");
+        Ok(())
+    }
+
+    /// Syncs every source once through its sinks without starting the server.
+    async fn run_once(&self) -> Result<(), String> {
+        trace!("run_once");
+        let mut join_set = JoinSet::new();
+        let manager = self.manager.clone().with_one_shot();
+        let node_handles = NodeHandles::new(&manager, self.server.get_web_channel_handle());
+
+        let mut registry = NodeRegistry::new();
+        integration::register(&mut registry);
+        let nodes = registry.build(&node_handles)?;
+        let init_count = nodes.len();
+
+        let semaphore = Arc::new(Semaphore::new(init_count));
+        for node in nodes {
+            let permit = semaphore
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("Could not acquire semaphore");
+            join_set.spawn(async move { node.run(permit).await });
+        }
+
+        let _permit = semaphore
+            .acquire_many(init_count as u32)
+            .await
+            .expect("Could not acquire semaphore");
+
+        let (sender, join_set, _abort_handle) = self
+            .wait_for_init_responses(&mut join_set, init_count)
+            .await;
+        let join_set = self.send_init(join_set, sender);
+
+        // Each source stops by itself after one sync, closing its channel so its sinks drain.
+        let status = manager.status();
+        status
+            .wait_until(|nodes| {
+                nodes.values().all(|node| {
+                    *node.kind() != NodeKind::Source || *node.state() == NodeState::Stopped
+                })
+            })
+            .await;
+        manager
+            .clone()
+            .send_stop()
+            .map_err(|e| format!("Lifecycle error while sending stop signal: {:?}", e))?;
+
+        Self::join(join_set).await;
+        trace!("run_once completed");
+
+        let errors = status.errors();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors
+                .iter()
+                .map(|(node, error)| format!("{} failed: {}", node, error))
+                .collect::<Vec<_>>()
+                .join(", "))
+        }
     }
 
     async fn join(join_set: &mut JoinSet<()>) {
@@ -132,9 +198,11 @@ where
         }
     }
 
+    /// Waits for every node to reply to the init signal.
     async fn wait_for_init_responses<'a>(
         &'a self,
         join_set: &'a mut JoinSet<()>,
+        node_count: usize,
     ) -> (
         OneshotMpscSenderHandle<()>,
         &'a mut JoinSet<()>,
@@ -142,7 +210,8 @@ where
     ) {
         let readonly_manager = self.manager.readonly();
         let (sender, mut receiver) = one_shot();
-        let expected = join_set.len();
+        // abort_on_stop below replies to the init signal too.
+        let expected = node_count + 1;
         let semaphore = Arc::new(Semaphore::new(1));
         let permit = semaphore
             .clone()
@@ -180,7 +249,8 @@ where
         (sender, join_set, abort_handle)
     }
 
-    async fn wait_for_timeout<'a>(
+    /// Sends the stop signal after the configured wait, or when the process is told to stop.
+    async fn wait_for_stop<'a>(
         &'a self,
         join_set: &'a mut JoinSet<()>,
         wait: Option<Duration>,
@@ -196,23 +266,29 @@ where
 
         join_set.spawn(async move {
             drop(permit);
-            match wait {
-                Some(duration) => {
-                    trace!("timeout starting sleep");
-                    sleep(duration).await;
-                    trace!("timeout sleep over");
-                    match wait_manager.send_stop() {
-                        Ok(_) => {
-                            trace!("Lifecycle sent stop signal after {:?}", duration);
-                        }
-                        Err(e) => {
-                            panic!("Lifecycle error while sending stop signal: {:?}", e);
-                        }
+            let timeout = async {
+                match wait {
+                    Some(duration) => {
+                        trace!("timeout starting sleep");
+                        sleep(duration).await;
+                        trace!("timeout sleep over");
                     }
-                    stopped.store(true, SeqCst);
+                    None => pending::<()>().await,
+                }
+            };
+            select! {
+                _ = timeout => {}
+                signal = shutdown_signal() => info!("Received {}, shutting down.", signal),
+            }
+            match wait_manager.send_stop() {
+                Ok(_) => {
+                    trace!("Lifecycle sent stop signal");
+                }
+                Err(e) => {
+                    panic!("Lifecycle error while sending stop signal: {:?}", e);
                 }
-                None => {}
             }
+            stopped.notify_one();
         });
 
         let _permit = semaphore
@@ -236,25 +312,48 @@ where
         join_set
     }
 
-    /// Stop all tasks when the stop signal is received.
+    /// Stop all tasks when the stop signal is received, once they've had the grace period to
+    /// finish. Tasks that finish sooner end the wait, as the run joins them alongside this.
     /// Used as a backup in case a task handles the stop signal incorrectly.
     async fn stop_checker(&self, abort_handles: Vec<AbortHandle>) {
-        loop {
-            if self.stopped.load(SeqCst) {
-                trace!("Stopping all tasks.");
-                abort_handles.iter().for_each(|handle| {
-                    handle.abort();
-                });
-                break;
+        self.stopped.notified().await;
+        sleep(self.manager.core_config().shutdown_grace_period()).await;
+        trace!("Stopping all tasks.");
+        abort_handles.iter().for_each(|handle| {
+            handle.abort();
+        });
+    }
+}
+
+/// Resolves with the name of the signal once the process is asked to stop.
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => select! {
+                _ = ctrl_c() => "SIGINT",
+                _ = terminate.recv() => "SIGTERM",
+            },
+            Err(e) => {
+                log::error!("Could not listen for SIGTERM: {}", e);
+                let _ = ctrl_c().await;
+                "SIGINT"
             }
-            sleep(Duration::from_millis(10)).await;
         }
     }
+    #[cfg(not(unix))]
+    {
+        let _ = ctrl_c().await;
+        "SIGINT"
+    }
 }
 
 #[cfg(test)]
 pub(crate) mod tests {
     use crate::block_on;
+    use crate::integration::google::auth::web::tests::make_config_file_and_lock;
     use crate::server::{MockWebServer, WebEventChannelHandle};
 
     use super::*;
@@ -285,6 +384,42 @@ pub(crate) mod tests {
             .expect_get_web_channel_handle()
             .return_const(cloned_web_channel_handle.clone());
 
-        block_on!(EngineImpl::new(&stub_config(), mock_web_server).start());
+        block_on!(EngineImpl::new(&stub_config(), mock_web_server).start()).unwrap();
+    }
+
+    fn sync_engine(yaml: &str) -> EngineImpl<MockWebServer> {
+        let config: Config = serde_yaml::from_str(yaml).expect("Could not parse config");
+        let mut mock_web_server = MockWebServer::new();
+        mock_web_server.expect_clone().never();
+        mock_web_server
+            .expect_get_web_channel_handle()
+            .return_const(WebEventChannelHandle::new());
+        EngineImpl::new(&Arc::new(config), mock_web_server)
+    }
+
+    #[test]
+    fn test_engine_sync() {
+        let engine = sync_engine(
+            "site_state_folder: ./stub_site_folder\nsinks:\n  log: {}\nsources:\n  stub: {}\n",
+        );
+
+        block_on!(engine.sync()).unwrap();
+
+        let nodes = engine.manager.status().nodes();
+        assert_eq!(nodes["stub"].state(), &NodeState::Stopped);
+        assert!(nodes["stub"].last_sync().is_some());
+    }
+
+    #[test]
+    fn test_engine_sync_fails_without_authorization() {
+        // Setting up the app isn't enough without someone authorizing it.
+        let _config = block_on!(make_config_file_and_lock());
+        let engine = sync_engine(
+            "site_state_folder: ./stub_site_folder\nsinks:\n  log: {}\nsources:\n  google: {}\n",
+        );
+
+        let result = block_on!(engine.sync());
+
+        assert_eq!(result, Err("google failed: Not authorized".to_string()));
     }
 }
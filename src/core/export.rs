@@ -0,0 +1,169 @@
+use crate::core::cli::{ExportArgs, ExportFormat};
+use crate::domain::config::Config;
+use crate::domain::entity::Entity;
+use crate::integration::store::EntityStore;
+use serde_yaml::Value;
+use std::io::{stdout, Write};
+use tokio::fs;
+
+/// Writes the source's stored entities, with their metadata, to the output file or stdout.
+pub async fn export(config: &Config, args: &ExportArgs) -> Result<(), String> {
+    let mut entities: Vec<Entity<Value>> = EntityStore::new(config)
+        .entities(&args.source)
+        .await
+        .map_err(|e| format!("Could not read stored entities because of {}", e))?;
+    if let Some(since) = args.since {
+        entities.retain(|entity| entity.updated_at() >= since);
+    }
+    entities.sort_by(|a, b| {
+        a.updated_at()
+            .cmp(&b.updated_at())
+            .then_with(|| a.id().cmp(b.id()))
+    });
+
+    let text = format(&entities, args.format)?;
+    match &args.output {
+        Some(path) => fs::write(path, text)
+            .await
+            .map_err(|e| format!("Could not write {} because of {}", path, e)),
+        None => stdout()
+            .write_all(text.as_bytes())
+            .map_err(|e| format!("Could not write to stdout because of {}", e)),
+    }
+}
+
+fn format(entities: &[Entity<Value>], format: ExportFormat) -> Result<String, String> {
+    match format {
+        ExportFormat::Csv => to_csv(entities),
+        ExportFormat::Json => serde_json::to_string_pretty(entities)
+            .map(|json| json + "\n")
+            .map_err(|e| e.to_string()),
+        ExportFormat::Ndjson => entities
+            .iter()
+            .map(|entity| serde_json::to_string(entity).map(|json| json + "\n"))
+            .collect::<Result<String, _>>()
+            .map_err(|e| e.to_string()),
+        ExportFormat::Yaml => serde_yaml::to_string(entities).map_err(|e| e.to_string()),
+    }
+}
+
+/// Strings go in the data column as they are. Anything else is written as JSON.
+fn to_csv(entities: &[Entity<Value>]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(["id", "created_at", "updated_at", "data"])
+        .map_err(|e| e.to_string())?;
+    for entity in entities {
+        let data = match entity.data() {
+            Value::String(data) => data.clone(),
+            data => serde_json::to_string(data).map_err(|e| e.to_string())?,
+        };
+        writer
+            .write_record([
+                entity.id(),
+                &entity.created_at().to_rfc3339(),
+                &entity.updated_at().to_rfc3339(),
+                &data,
+            ])
+            .map_err(|e| e.to_string())?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::cli::ConfigArgs;
+    use chrono::{TimeZone, Utc};
+    use std::env::temp_dir;
+    use uuid::Uuid;
+
+    fn entities() -> Vec<Entity<Value>> {
+        let at = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        vec![
+            Entity::new(at, Value::String("a, \"quoted\" note".to_string()), "1", at),
+            Entity::new(
+                at,
+                serde_yaml::from_str("title: Buy milk\ndone: false").unwrap(),
+                "2",
+                at,
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_json_includes_the_metadata() {
+        let json = format(&entities(), ExportFormat::Json).unwrap();
+        let parsed: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed[1]["id"], "2");
+        assert_eq!(parsed[1]["created_at"], "2024-06-01T12:00:00Z");
+        assert_eq!(parsed[1]["updated_at"], "2024-06-01T12:00:00Z");
+        assert_eq!(parsed[1]["data"]["title"], "Buy milk");
+    }
+
+    #[test]
+    fn test_ndjson_is_one_entity_per_line() {
+        let ndjson = format(&entities(), ExportFormat::Ndjson).unwrap();
+
+        let lines: Vec<_> = ndjson.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let second: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(second["data"]["done"], false);
+    }
+
+    #[test]
+    fn test_csv_quotes_and_flattens_data() {
+        let csv = format(&entities(), ExportFormat::Csv).unwrap();
+
+        assert_eq!(
+            csv,
+            "id,created_at,updated_at,data\n\
+            1,2024-06-01T12:00:00+00:00,2024-06-01T12:00:00+00:00,\"a, \"\"quoted\"\" note\"\n\
+            2,2024-06-01T12:00:00+00:00,2024-06-01T12:00:00+00:00,\
+            \"{\"\"title\"\":\"\"Buy milk\"\",\"\"done\"\":false}\"\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_since_to_a_file() {
+        let site = temp_dir().join(format!("cloud-scraper-{}", Uuid::new_v4()));
+        let config =
+            Config::with_all_properties(None, None, None, Some(site.display().to_string()));
+        let store = EntityStore::new(&config);
+        let old_at = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        let new_at = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        let old = Entity::new(old_at, "old".to_string(), "old", old_at);
+        let new = Entity::new(old_at, "new".to_string(), "new", new_at);
+        store.put("source", &old).await.unwrap();
+        store.put("source", &new).await.unwrap();
+        let output = site.join("export.yaml");
+
+        export(
+            &config,
+            &ExportArgs {
+                config_args: ConfigArgs { config: None },
+                format: ExportFormat::Yaml,
+                output: Some(output.display().to_string()),
+                since: Some(new_at),
+                source: "source".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let exported: Vec<Entity<String>> =
+            serde_yaml::from_str(&fs::read_to_string(&output).await.unwrap()).unwrap();
+        let _ = std::fs::remove_dir_all(&site);
+        assert_eq!(exported, vec![new]);
+    }
+
+    #[test]
+    fn test_yaml_reads_back() {
+        let yaml = format(&entities(), ExportFormat::Yaml).unwrap();
+
+        let parsed: Vec<Entity<Value>> = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(parsed, entities());
+    }
+}
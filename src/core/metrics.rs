@@ -0,0 +1,214 @@
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+lazy_static! {
+    static ref METRICS: Mutex<Metrics> = Mutex::new(Metrics::new());
+}
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub(crate) enum Metric {
+    AcmeRenewals,
+    ChannelLagged,
+    EntitiesEmitted,
+    EntitiesWritten,
+    GoogleApiErrors,
+    LoginFailures,
+    OauthTokenRefreshes,
+    SyncDurationSeconds,
+}
+
+impl Metric {
+    const ALL: [Metric; 8] = [
+        Metric::AcmeRenewals,
+        Metric::ChannelLagged,
+        Metric::EntitiesEmitted,
+        Metric::EntitiesWritten,
+        Metric::GoogleApiErrors,
+        Metric::LoginFailures,
+        Metric::OauthTokenRefreshes,
+        Metric::SyncDurationSeconds,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Metric::AcmeRenewals => "cloud_scraper_acme_renewals_total",
+            Metric::ChannelLagged => "cloud_scraper_channel_lagged_total",
+            Metric::EntitiesEmitted => "cloud_scraper_entities_emitted_total",
+            Metric::EntitiesWritten => "cloud_scraper_entities_written_total",
+            Metric::GoogleApiErrors => "cloud_scraper_google_api_errors_total",
+            Metric::LoginFailures => "cloud_scraper_login_failures_total",
+            Metric::OauthTokenRefreshes => "cloud_scraper_oauth_token_refreshes_total",
+            Metric::SyncDurationSeconds => "cloud_scraper_sync_duration_seconds",
+        }
+    }
+
+    fn help(&self) -> &'static str {
+        match self {
+            Metric::AcmeRenewals => "ACME certificate renewals by result.",
+            Metric::ChannelLagged => "Entities or events dropped by lagging channel receivers.",
+            Metric::EntitiesEmitted => "Entities emitted by each source.",
+            Metric::EntitiesWritten => "Entities written by each sink.",
+            Metric::GoogleApiErrors => "Errors returned by Google APIs.",
+            Metric::LoginFailures => "Failed logins.",
+            Metric::OauthTokenRefreshes => "OAuth token refreshes by result.",
+            Metric::SyncDurationSeconds => "How long each source's last sync took.",
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Metric::SyncDurationSeconds => "gauge",
+            _ => "counter",
+        }
+    }
+}
+
+type Labels = Vec<(&'static str, String)>;
+
+/// Counters and gauges, rendered in the Prometheus text format.
+#[derive(Debug)]
+struct Metrics {
+    values: BTreeMap<Metric, BTreeMap<Labels, f64>>,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Self {
+            values: BTreeMap::new(),
+        }
+    }
+
+    fn add(&mut self, metric: Metric, labels: &[(&'static str, &str)], amount: f64) {
+        *self.value(metric, labels) += amount;
+    }
+
+    fn set(&mut self, metric: Metric, labels: &[(&'static str, &str)], value: f64) {
+        *self.value(metric, labels) = value;
+    }
+
+    fn value(&mut self, metric: Metric, labels: &[(&'static str, &str)]) -> &mut f64 {
+        let labels = labels
+            .iter()
+            .map(|(name, value)| (*name, value.to_string()))
+            .collect();
+        self.values
+            .entry(metric)
+            .or_default()
+            .entry(labels)
+            .or_insert(0.0)
+    }
+
+    fn render(&self) -> String {
+        let mut text = String::new();
+        for metric in Metric::ALL {
+            let _ = writeln!(text, "# HELP {} {}", metric.name(), metric.help());
+            let _ = writeln!(text, "# TYPE {} {}", metric.name(), metric.kind());
+            match self.values.get(&metric) {
+                Some(samples) => {
+                    for (labels, value) in samples {
+                        let _ =
+                            writeln!(text, "{}{} {}", metric.name(), format_labels(labels), value);
+                    }
+                }
+                // Unlabelled counters start at zero, so rates work from the first scrape.
+                None if !is_labelled(metric) => {
+                    let _ = writeln!(text, "{} 0", metric.name());
+                }
+                None => {}
+            }
+        }
+        text
+    }
+}
+
+fn is_labelled(metric: Metric) -> bool {
+    !matches!(metric, Metric::GoogleApiErrors | Metric::LoginFailures)
+}
+
+fn format_labels(labels: &Labels) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+
+    let labels: Vec<_> = labels
+        .iter()
+        .map(|(name, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", name, value)
+        })
+        .collect();
+    format!("{{{}}}", labels.join(","))
+}
+
+pub(crate) fn add(metric: Metric, labels: &[(&'static str, &str)], amount: u64) {
+    METRICS.lock().add(metric, labels, amount as f64);
+}
+
+pub(crate) fn increment(metric: Metric, labels: &[(&'static str, &str)]) {
+    add(metric, labels, 1);
+}
+
+pub(crate) fn render() -> String {
+    METRICS.lock().render()
+}
+
+pub(crate) fn set(metric: Metric, labels: &[(&'static str, &str)], value: f64) {
+    METRICS.lock().set(metric, labels, value);
+}
+
+/// Labels a result for the counters that track successes and failures.
+pub(crate) fn result<T, E>(result: &Result<T, E>) -> (&'static str, &'static str) {
+    match result {
+        Ok(_) => ("result", "success"),
+        Err(_) => ("result", "failure"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counters_and_gauges() {
+        let mut metrics = Metrics::new();
+
+        metrics.add(Metric::EntitiesEmitted, &[("source", "google")], 2.0);
+        metrics.add(Metric::EntitiesEmitted, &[("source", "google")], 3.0);
+        metrics.set(Metric::SyncDurationSeconds, &[("source", "google")], 1.5);
+        metrics.set(Metric::SyncDurationSeconds, &[("source", "google")], 0.25);
+
+        let text = metrics.render();
+
+        assert!(text.contains("# TYPE cloud_scraper_entities_emitted_total counter\n"));
+        assert!(text.contains("cloud_scraper_entities_emitted_total{source=\"google\"} 5\n"));
+        assert!(text.contains("# TYPE cloud_scraper_sync_duration_seconds gauge\n"));
+        assert!(text.contains("cloud_scraper_sync_duration_seconds{source=\"google\"} 0.25\n"));
+    }
+
+    #[test]
+    fn test_unlabelled_counters_start_at_zero() {
+        let text = Metrics::new().render();
+
+        assert!(text.contains("cloud_scraper_login_failures_total 0\n"));
+        assert!(text.contains("cloud_scraper_google_api_errors_total 0\n"));
+        assert!(!text
+            .lines()
+            .any(|line| line.starts_with("cloud_scraper_entities_written_total")));
+    }
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        let mut metrics = Metrics::new();
+
+        metrics.add(Metric::ChannelLagged, &[("channel", "a\"b\\c")], 1.0);
+
+        assert!(metrics
+            .render()
+            .contains("cloud_scraper_channel_lagged_total{channel=\"a\\\"b\\\\c\"} 1\n"));
+    }
+}
@@ -2,11 +2,14 @@ pub mod cli;
 mod construct_config;
 pub mod engine;
 mod error;
+mod export;
 mod hash;
+pub(crate) mod metrics;
 pub mod module;
 pub mod node_handles;
+pub(crate) mod node_registry;
 pub mod password;
-pub mod root_password;
-pub(crate) mod serde_yaml;
+pub mod users;
 
 pub use construct_config::construct_config;
+pub(crate) use export::export;
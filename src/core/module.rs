@@ -1,23 +1,45 @@
 use crate::domain::module_state::ModuleState;
 use async_trait::async_trait;
+#[cfg(test)]
+use lazy_static::lazy_static;
+
+#[cfg(test)]
+lazy_static! {
+    // Tests keep their state out of the working copy, where a real service keeps its own.
+    static ref TEST_PATH: String = std::env::temp_dir()
+        .join(format!("cloud-scraper-state-{}", uuid::Uuid::new_v4()))
+        .to_string_lossy()
+        .to_string();
+}
 
 pub struct State {}
 
 #[async_trait]
 impl ModuleState for State {
+    #[cfg(not(test))]
     fn path() -> &'static str {
         "state"
     }
+
+    #[cfg(test)]
+    fn path() -> &'static str {
+        &TEST_PATH
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::integration::google::Source;
+    use std::env::temp_dir;
+    use std::path::Path;
 
     #[tokio::test]
     async fn test_path_for() {
-        assert_eq!(State::path(), "state");
-        assert_eq!(State::path_for::<Source>().await.unwrap(), "state/google")
+        assert!(Path::new(State::path()).starts_with(temp_dir()));
+        assert_eq!(
+            State::path_for::<Source>().await.unwrap(),
+            format!("{}/google", State::path())
+        )
     }
 }
@@ -0,0 +1,394 @@
+use crate::core::node_handles::NodeHandles;
+use crate::domain::channel_handle::DEFAULT_CAPACITY;
+use crate::domain::config::Config;
+use crate::domain::entity_data::EntityData;
+use crate::domain::entity_translator::EntityTranslator;
+use crate::domain::node::{
+    ConsumerNode, Manager, Node, ProducerNode, Schedule, SinkNode, SourceNode,
+};
+use crate::domain::sink::Sink;
+use crate::domain::source::Source;
+use crate::domain::translator_registry::TranslatorRegistry;
+use log::trace;
+use std::time::Duration;
+
+type SinkConstructor =
+    Box<dyn Fn(&NodeHandles) -> Result<Box<dyn ConsumerNode>, String> + Send + Sync>;
+type SourceConstructor =
+    Box<dyn Fn(&NodeHandles) -> Result<Box<dyn ProducerNode>, String> + Send + Sync>;
+
+/// The sources, sinks and translators that integrations register with the engine.
+pub(crate) struct NodeRegistry {
+    opt_in_sinks: Vec<&'static str>,
+    sinks: Vec<(&'static str, SinkConstructor)>,
+    sources: Vec<(&'static str, SourceConstructor)>,
+    translators: TranslatorRegistry,
+}
+
+impl NodeRegistry {
+    pub(crate) fn new() -> Self {
+        Self {
+            opt_in_sinks: Vec::new(),
+            sinks: Vec::new(),
+            sources: Vec::new(),
+            translators: TranslatorRegistry::new(),
+        }
+    }
+
+    /// Sinks may be registered under the same name for several kinds of entity data.
+    pub(crate) fn register_sink<SinkType, DataType, Constructor>(
+        &mut self,
+        constructor: Constructor,
+    ) where
+        SinkType: Sink<DataType>,
+        DataType: EntityData + 'static,
+        Constructor: Fn(&NodeHandles) -> SinkType + Send + Sync + 'static,
+    {
+        self.register_fallible_sink(move |handles| Ok(constructor(handles)));
+    }
+
+    /// Registers a sink that only runs when the config's `sinks` section names it. Its
+    /// constructor may refuse settings it can't use.
+    pub(crate) fn register_opt_in_sink<SinkType, DataType, Constructor>(
+        &mut self,
+        constructor: Constructor,
+    ) where
+        SinkType: Sink<DataType>,
+        DataType: EntityData + 'static,
+        Constructor: Fn(&NodeHandles) -> Result<SinkType, String> + Send + Sync + 'static,
+    {
+        self.opt_in_sinks.push(SinkType::name());
+        self.register_fallible_sink(constructor);
+    }
+
+    fn register_fallible_sink<SinkType, DataType, Constructor>(&mut self, constructor: Constructor)
+    where
+        SinkType: Sink<DataType>,
+        DataType: EntityData + 'static,
+        Constructor: Fn(&NodeHandles) -> Result<SinkType, String> + Send + Sync + 'static,
+    {
+        self.sinks.push((
+            SinkType::name(),
+            Box::new(move |handles| {
+                let manager = handles.lifecycle_manager();
+                manager.register_node::<SinkType>();
+                let sink = constructor(handles)
+                    .map_err(|e| format!("{} in sink {}", e, SinkType::name()))?;
+                Ok(Box::new(SinkNode::new(manager, sink)))
+            }),
+        ));
+    }
+
+    pub(crate) fn register_source<SourceType, DataType, Constructor>(
+        &mut self,
+        constructor: Constructor,
+        poll_interval: Duration,
+    ) where
+        SourceType: Source<DataType>,
+        DataType: EntityData + 'static,
+        Constructor: Fn(&NodeHandles) -> SourceType + Send + Sync + 'static,
+    {
+        self.sources.push((
+            SourceType::name(),
+            Box::new(move |handles| {
+                let manager = handles.lifecycle_manager();
+                manager.register_node::<SourceType>();
+                let core_config = manager.core_config();
+                let config = core_config.source(SourceType::name());
+                let schedule = Schedule::from_config(config, poll_interval)
+                    .map_err(|e| format!("{} in source {}", e, SourceType::name()))?;
+                let capacity = config
+                    .and_then(|config| *config.channel_capacity())
+                    .unwrap_or(DEFAULT_CAPACITY);
+                let policy = config
+                    .and_then(|config| *config.on_lag())
+                    .unwrap_or_default();
+                Ok(Box::new(
+                    SourceNode::new(manager, constructor(handles), poll_interval)
+                        .with_channel(capacity, policy)
+                        .with_schedule(schedule),
+                ))
+            }),
+        ));
+    }
+
+    pub(crate) fn register_translator<InputType, OutputType, TranslatorType>(
+        &mut self,
+        translator: TranslatorType,
+    ) where
+        InputType: EntityData + 'static,
+        OutputType: EntityData + 'static,
+        TranslatorType: EntityTranslator<InputType, OutputType>,
+    {
+        self.translators.register(translator);
+    }
+
+    /// Constructs every enabled node and connects them.
+    ///
+    /// Without a `routes` section in the config, each sink is connected to every source whose
+    /// entities it can take.
+    pub(crate) fn build(&self, handles: &NodeHandles) -> Result<Vec<Box<dyn Node>>, String> {
+        let manager = handles.lifecycle_manager();
+        let config = manager.core_config();
+        self.check_module_names(&config)?;
+
+        let sources: Vec<_> = self
+            .sources
+            .iter()
+            .filter(|(name, _)| config.source_enabled(name))
+            .map(|(name, constructor)| Ok((*name, constructor(handles)?)))
+            .collect::<Result<_, String>>()?;
+        let mut sinks: Vec<_> = self
+            .sinks
+            .iter()
+            .filter(|(name, _)| {
+                config.sink_enabled(name)
+                    && (config.sink(name).is_some() || !self.opt_in_sinks.contains(name))
+            })
+            .map(|(name, constructor)| Ok((*name, constructor(handles)?)))
+            .collect::<Result<_, String>>()?;
+        let mut nodes: Vec<Box<dyn Node>> = Vec::new();
+
+        match config.routes() {
+            Some(routes) => {
+                for route in routes {
+                    let (_, source) = sources
+                        .iter()
+                        .find(|(name, _)| name == route.source())
+                        .ok_or_else(|| format!("{} is not an enabled source", route.source()))?;
+                    if let Some(translator) =
+                        self.connect(manager, &mut sinks, route.sink(), source.as_ref())?
+                    {
+                        nodes.push(translator);
+                    }
+                }
+            }
+            None => {
+                let mut sink_names: Vec<_> = sinks.iter().map(|(name, _)| *name).collect();
+                sink_names.sort();
+                sink_names.dedup();
+                for sink_name in sink_names {
+                    for (_, source) in &sources {
+                        match self.connect(manager, &mut sinks, sink_name, source.as_ref()) {
+                            Ok(Some(translator)) => nodes.push(translator),
+                            Ok(None) => {}
+                            Err(e) => trace!("Not connecting {}: {}", sink_name, e),
+                        }
+                    }
+                }
+            }
+        }
+
+        nodes.extend(sinks.into_iter().map(|(_, sink)| sink as Box<dyn Node>));
+        nodes.extend(
+            sources
+                .into_iter()
+                .map(|(_, source)| source as Box<dyn Node>),
+        );
+        Ok(nodes)
+    }
+
+    /// Connects the source to one of the sinks registered under `sink_name`, preferring one that
+    /// takes its entities without translation.
+    fn connect(
+        &self,
+        manager: &Manager,
+        sinks: &mut [(&'static str, Box<dyn ConsumerNode>)],
+        sink_name: &str,
+        source: &dyn ProducerNode,
+    ) -> Result<Option<Box<dyn Node>>, String> {
+        let input = source.entity_channel();
+        let mut candidates: Vec<_> = sinks
+            .iter_mut()
+            .filter(|(name, _)| *name == sink_name)
+            .map(|(_, sink)| sink)
+            .collect();
+        if candidates.is_empty() {
+            return Err(format!("{} is not an enabled sink", sink_name));
+        }
+        candidates.sort_by_key(|sink| sink.data_type() != input.data_type());
+
+        let mut errors = Vec::new();
+        for sink in candidates {
+            match sink.connect(manager, &self.translators, &input) {
+                Ok(translator) => return Ok(translator),
+                Err(e) => errors.push(e),
+            }
+        }
+        Err(errors.join(", "))
+    }
+
+    fn check_module_names(&self, config: &Config) -> Result<(), String> {
+        let unknown_sources = config
+            .sources()
+            .iter()
+            .flat_map(|sources| sources.keys())
+            .filter(|name| !self.sources.iter().any(|(known, _)| known == name))
+            .map(|name| format!("Unknown source {}", name));
+        let unknown_sinks = config
+            .sinks()
+            .iter()
+            .flat_map(|sinks| sinks.keys())
+            .filter(|name| !self.sinks.iter().any(|(known, _)| known == name))
+            .map(|name| format!("Unknown sink {}", name));
+        let errors: Vec<_> = unknown_sources.chain(unknown_sinks).collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::node_handles::tests::get_test_node_handles;
+    use crate::domain::node::get_test_manager;
+    use crate::integration;
+    use crate::server::WebEventChannelHandle;
+    use std::sync::Arc;
+
+    fn handles_for(config_yaml: &str) -> NodeHandles {
+        let config: Config = serde_yaml::from_str(config_yaml).expect("Could not parse config");
+        let manager = get_test_manager(&Arc::new(config));
+        NodeHandles::new(&manager, &WebEventChannelHandle::new())
+    }
+
+    fn integration_registry() -> NodeRegistry {
+        let mut registry = NodeRegistry::new();
+        integration::register(&mut registry);
+        registry
+    }
+
+    mod build {
+        use super::*;
+
+        #[test]
+        fn test_everything_runs_without_pipeline_config() {
+            let nodes = integration_registry()
+                .build(&get_test_node_handles())
+                .unwrap();
+
+            // Two sources, three sinks, and a translator from the stub to each sink taking strings
+            // and from google to the log.
+            assert_eq!(nodes.len(), 8);
+        }
+
+        #[test]
+        fn test_disabled_modules_do_not_run() {
+            let handles = handles_for(
+                r#"
+sources:
+  google:
+    enabled: false
+  stub: {}
+"#,
+            );
+
+            let nodes = integration_registry().build(&handles).unwrap();
+
+            assert_eq!(nodes.len(), 6);
+        }
+
+        #[test]
+        fn test_only_routed_modules_are_connected() {
+            let handles = handles_for(
+                r#"
+routes:
+  - source: google
+    sink: log
+"#,
+            );
+
+            let nodes = integration_registry().build(&handles).unwrap();
+
+            // Only the google to log translator. Unrouted sinks run without inputs.
+            assert_eq!(nodes.len(), 6);
+        }
+
+        #[test]
+        fn test_route_prefers_a_sink_without_translation() {
+            let handles = handles_for(
+                r#"
+routes:
+  - source: google
+    sink: store
+"#,
+            );
+
+            let nodes = integration_registry().build(&handles).unwrap();
+
+            // Google tasks go straight into the store, so there is no translator.
+            assert_eq!(nodes.len(), 5);
+        }
+
+        #[test]
+        fn test_opt_in_sinks_only_run_when_named() {
+            let handles = handles_for(
+                r#"
+sinks:
+  git: {}
+"#,
+            );
+
+            let nodes = integration_registry().build(&handles).unwrap();
+
+            // Two sources, a git sink for each, and a translator from the stub.
+            assert_eq!(nodes.len(), 5);
+        }
+
+        #[test]
+        fn test_route_to_a_disabled_sink_is_an_error() {
+            let handles = handles_for(
+                r#"
+routes:
+  - source: stub
+    sink: log
+sinks:
+  log:
+    enabled: false
+"#,
+            );
+
+            let result = integration_registry().build(&handles);
+
+            assert_eq!(result.err().unwrap(), "log is not an enabled sink");
+        }
+
+        #[test]
+        fn test_invalid_schedule_is_an_error() {
+            let handles = handles_for(
+                r#"
+sources:
+  google:
+    cron: every night
+"#,
+            );
+
+            let result = integration_registry().build(&handles);
+
+            assert!(result.err().unwrap().ends_with("in source google"));
+        }
+
+        #[test]
+        fn test_unknown_modules_are_an_error() {
+            let handles = handles_for(
+                r#"
+sinks:
+  nowhere: {}
+sources:
+  nothing: {}
+"#,
+            );
+
+            let result = integration_registry().build(&handles);
+
+            assert_eq!(
+                result.err().unwrap(),
+                "Unknown source nothing, Unknown sink nowhere"
+            );
+        }
+    }
+}
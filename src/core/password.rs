@@ -1,49 +1,135 @@
 use crate::core::hash::hash_sha256;
+use crate::domain::password_hashing::PasswordHashing;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::SaltString;
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
 use hex;
-use rand::distributions::Alphanumeric;
-use rand::{thread_rng, Rng};
 use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
 
+fn argon2(hashing: &PasswordHashing) -> Result<Argon2<'static>, String> {
+    let params = Params::new(
+        hashing.memory_kib(),
+        hashing.iterations(),
+        hashing.parallelism(),
+        None,
+    )
+    .map_err(|e| format!("Invalid password hashing costs because of {}", e))?;
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+/// A password hash in the PHC string format, which records the algorithm and its costs.
+///
+/// Older hashes were hex encoded PBKDF2 with a separate salt. They still verify, but need
+/// rehashing.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Password {
     hash: String,
-    salt: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    salt: Option<String>,
 }
 
 impl Password {
-    pub fn new(password: &str, salt_length: u16) -> Self {
-        let salt = make_salt(salt_length);
-        let hash = hash_password(password, &salt);
-        Self { hash, salt }
+    pub fn new(password: &str, hashing: &PasswordHashing) -> Result<Self, String> {
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = argon2(hashing)?
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| format!("Could not hash password because of {}", e))?;
+        Ok(Self {
+            hash: hash.to_string(),
+            salt: None,
+        })
     }
 
-    pub fn verify(&self, password: &str) -> bool {
-        self.hash == hash_password(password, &self.salt)
+    /// Whether the hash should be replaced with one using the current algorithm and costs.
+    pub fn needs_rehash(&self, hashing: &PasswordHashing) -> bool {
+        if self.salt.is_some() {
+            return true;
+        }
+        let Ok(hash) = PasswordHash::new(&self.hash) else {
+            return true;
+        };
+        match Params::try_from(&hash) {
+            Ok(params) => {
+                hash.algorithm != Algorithm::Argon2id.ident()
+                    || params.m_cost() != hashing.memory_kib()
+                    || params.t_cost() != hashing.iterations()
+                    || params.p_cost() != hashing.parallelism()
+            }
+            Err(_) => true,
+        }
     }
-}
 
-fn hash_password(password: &str, salt: &str) -> String {
-    hex::encode(hash_sha256(password, salt))
-}
-
-fn make_salt(length: u16) -> String {
-    let rng = thread_rng();
-    rng.sample_iter(&Alphanumeric)
-        .take(length as usize)
-        .map(char::from)
-        .collect()
+    /// Compares in constant time.
+    pub fn verify(&self, password: &str) -> bool {
+        match &self.salt {
+            Some(salt) => hex::decode(&self.hash).is_ok_and(|hash| {
+                hash_sha256(password, salt)
+                    .as_slice()
+                    .ct_eq(hash.as_slice())
+                    .into()
+            }),
+            None => PasswordHash::new(&self.hash).is_ok_and(|hash| {
+                Argon2::default()
+                    .verify_password(password.as_bytes(), &hash)
+                    .is_ok()
+            }),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn legacy(password: &str) -> Password {
+        let salt = "eyF8Ak6G48ZrRBs0".to_string();
+        Password {
+            hash: hex::encode(hash_sha256(password, &salt)),
+            salt: Some(salt),
+        }
+    }
+
     #[test]
     fn test_password() {
-        let password = "password";
-        let salt_length = 16;
-        let password = Password::new(password, salt_length);
+        let password = Password::new("password", &PasswordHashing::default()).unwrap();
+        assert!(password.verify("password"));
+        assert!(!password.verify("wrong_password"));
+    }
+
+    #[test]
+    fn test_hash_records_the_algorithm_and_costs() {
+        let password = Password::new("password", &PasswordHashing::default()).unwrap();
+        assert!(password.hash.starts_with("$argon2id$v=19$m=64,t=2,p=1$"));
+        assert!(password.salt.is_none());
+    }
+
+    #[test]
+    fn test_legacy_hashes_verify_and_need_rehashing() {
+        let password = legacy("password");
         assert!(password.verify("password"));
         assert!(!password.verify("wrong_password"));
+        assert!(password.needs_rehash(&PasswordHashing::default()));
+    }
+
+    #[test]
+    fn test_changed_costs_need_rehashing() {
+        let hashing = PasswordHashing::default();
+        let password = Password::new("password", &hashing).unwrap();
+        assert!(!password.needs_rehash(&hashing));
+
+        let stronger = hashing.with_iterations(3);
+        assert!(password.needs_rehash(&stronger));
+        assert!(password.verify("password"));
+    }
+
+    #[test]
+    fn test_yaml_round_trip() {
+        let password = Password::new("password", &PasswordHashing::default()).unwrap();
+        let yaml = serde_yaml::to_string(&password).unwrap();
+        assert!(!yaml.contains("salt"));
+
+        let password: Password = serde_yaml::from_str(&yaml).unwrap();
+        assert!(password.verify("password"));
     }
 }
@@ -0,0 +1,374 @@
+use crate::core::password::Password;
+use crate::domain::password_hashing::PasswordHashing;
+use clap::ValueEnum;
+use derive_getters::Getters;
+use log::{error, info, trace};
+use rpassword::prompt_password;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use tokio::fs;
+use uuid::Uuid;
+
+static USERS_FILE: &str = "users.yaml";
+/// Where the single root password lived before there were users.
+static LEGACY_ROOT_PASSWORD_FILE: &str = "root_password.yaml";
+pub static ROOT_USER: &str = "root";
+
+/// What a user may do in the web UI.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Can change the config and OAuth credentials
+    Admin,
+    /// Can browse backups
+    Viewer,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Admin => write!(f, "admin"),
+            Role::Viewer => write!(f, "viewer"),
+        }
+    }
+}
+
+/// Who is logged in.
+#[derive(Clone, Debug, Deserialize, Getters, PartialEq, Serialize)]
+pub struct Identity {
+    name: String,
+    role: Role,
+}
+
+impl Identity {
+    pub fn is_admin(&self) -> bool {
+        self.role == Role::Admin
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct User {
+    password: Password,
+    role: Role,
+}
+
+type Users = BTreeMap<String, User>;
+
+/// Prompts for the user's password, replacing any they had along with their role.
+pub async fn add_user(name: &str, role: Role, hashing: &PasswordHashing) -> Result<(), String> {
+    let password = prompt_password(format!("Input password for {}: ", name))
+        .map_err(|e| format!("Could not read password because of {:?}", e))?;
+
+    save_user(name, &password, role, hashing).await
+}
+
+/// Rehashes the user's password with the current costs after it's checked, if they've changed.
+pub async fn check_login(
+    name: &str,
+    password: &str,
+    hashing: &PasswordHashing,
+) -> Result<Option<Identity>, String> {
+    let mut users = load_users().await?;
+    let Some(user) = users.get_mut(name) else {
+        // Hash it anyway, so an unknown name takes as long as a known one to refuse.
+        let _ = Password::new(password, hashing);
+        return Ok(None);
+    };
+    if !user.password.verify(password) {
+        return Ok(None);
+    }
+
+    let identity = Identity {
+        name: name.to_string(),
+        role: user.role,
+    };
+    if user.password.needs_rehash(hashing) {
+        user.password = Password::new(password, hashing)?;
+        match save_users(&users).await {
+            Ok(()) => info!("Rehashed the password for {}.", name),
+            Err(e) => error!("Could not rehash the password for {}: {}", name, e),
+        }
+    }
+    Ok(Some(identity))
+}
+
+/// Who the named user is now, if they still exist.
+pub async fn find_user(name: &str) -> Result<Option<Identity>, String> {
+    Ok(load_users().await?.remove(name).map(|user| Identity {
+        name: name.to_string(),
+        role: user.role,
+    }))
+}
+
+pub async fn list_users() -> Result<Vec<Identity>, String> {
+    Ok(load_users()
+        .await?
+        .into_iter()
+        .map(|(name, user)| Identity {
+            name,
+            role: user.role,
+        })
+        .collect())
+}
+
+/// Refuses to remove the last admin, who'd be needed to add anyone back.
+pub async fn remove_user(name: &str) -> Result<(), String> {
+    let mut users = load_users().await?;
+    match users.get(name) {
+        None => return Err(format!("There is no user named {}.", name)),
+        Some(user)
+            if user.role == Role::Admin
+                && users
+                    .values()
+                    .filter(|user| user.role == Role::Admin)
+                    .count()
+                    == 1 =>
+        {
+            return Err(format!("{} is the last admin, so can't be removed.", name))
+        }
+        Some(_) => {}
+    }
+
+    users.remove(name);
+    save_users(&users).await
+}
+
+pub async fn users_exist() -> bool {
+    fs::metadata(USERS_FILE).await.is_ok() || fs::metadata(LEGACY_ROOT_PASSWORD_FILE).await.is_ok()
+}
+
+/// Moves a legacy root password in as the root admin, the first time the users are needed.
+async fn load_users() -> Result<Users, String> {
+    if fs::metadata(USERS_FILE).await.is_err() {
+        if fs::metadata(LEGACY_ROOT_PASSWORD_FILE).await.is_ok() {
+            return migrate_root_password().await;
+        }
+        return Ok(Users::new());
+    }
+
+    let users = fs::read_to_string(USERS_FILE)
+        .await
+        .map_err(|e| format!("Could not read users because of {:?}", e))?;
+    if users.trim().is_empty() {
+        return Ok(Users::new());
+    }
+    serde_yaml::from_str(&users)
+        .map_err(|e| format!("Could not deserialize users because of {:?}", e))
+}
+
+async fn migrate_root_password() -> Result<Users, String> {
+    let root_password = fs::read_to_string(LEGACY_ROOT_PASSWORD_FILE)
+        .await
+        .map_err(|e| format!("Could not read password because of {:?}", e))?;
+    let password: Password = serde_yaml::from_str(&root_password)
+        .map_err(|e| format!("Could not deserialize password because of {:?}", e))?;
+
+    let mut users = Users::new();
+    users.insert(
+        ROOT_USER.to_string(),
+        User {
+            password,
+            role: Role::Admin,
+        },
+    );
+    save_users(&users).await?;
+    fs::remove_file(LEGACY_ROOT_PASSWORD_FILE)
+        .await
+        .map_err(|e| format!("Could not remove the old root password because of {:?}", e))?;
+    info!(
+        "Moved the root password into {} as the {} admin.",
+        USERS_FILE, ROOT_USER
+    );
+    Ok(users)
+}
+
+async fn save_user(
+    name: &str,
+    password: &str,
+    role: Role,
+    hashing: &PasswordHashing,
+) -> Result<(), String> {
+    let mut users = load_users().await?;
+
+    trace!("Hashing password for {}.", name);
+    let password = Password::new(password, hashing)?;
+    users.insert(name.to_string(), User { password, role });
+
+    save_users(&users).await
+}
+
+/// Writes then renames, so a crash or a concurrent save never leaves the file half written.
+async fn save_users(users: &Users) -> Result<(), String> {
+    trace!("Writing users to file.");
+    let partial = format!("{}.{}.partial", USERS_FILE, Uuid::new_v4());
+    fs::write(
+        &partial,
+        serde_yaml::to_string(users)
+            .map_err(|e| format!("Could not serialize users because of {:?}", e))?,
+    )
+    .await
+    .map_err(|e| format!("Could not write users because of {:?}", e))?;
+    fs::rename(&partial, USERS_FILE)
+        .await
+        .map_err(|e| format!("Could not replace users because of {:?}", e))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use lazy_static::lazy_static;
+    use std::sync::{Mutex, MutexGuard};
+
+    pub static TEST_PASSWORD: &str = "test";
+    pub static TEST_VIEWER: &str = "viewer";
+    lazy_static! {
+        pub static ref TEST_USERS_MUTEX: Mutex<()> = Mutex::new(());
+    }
+
+    pub fn test_admin() -> Identity {
+        Identity {
+            name: ROOT_USER.to_string(),
+            role: Role::Admin,
+        }
+    }
+
+    pub fn test_viewer() -> Identity {
+        Identity {
+            name: TEST_VIEWER.to_string(),
+            role: Role::Viewer,
+        }
+    }
+
+    pub struct CleanableTestFile<'a> {
+        _guard: MutexGuard<'a, ()>,
+    }
+
+    impl CleanableTestFile<'_> {
+        #[allow(clippy::await_holding_lock)]
+        async fn new() -> Self {
+            let lock = TEST_USERS_MUTEX.lock().expect("Could not lock mutex.");
+            let _ = fs::remove_file(LEGACY_ROOT_PASSWORD_FILE).await;
+            let _ = fs::remove_file(USERS_FILE).await;
+            let hashing = PasswordHashing::default();
+            save_user(ROOT_USER, TEST_PASSWORD, Role::Admin, &hashing)
+                .await
+                .expect("Could not save password.");
+            save_user(TEST_VIEWER, TEST_PASSWORD, Role::Viewer, &hashing)
+                .await
+                .expect("Could not save password.");
+            Self { _guard: lock }
+        }
+    }
+
+    impl Drop for CleanableTestFile<'_> {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(USERS_FILE);
+        }
+    }
+
+    /// Adds or replaces a user with the test password.
+    pub async fn save_test_user(name: &str, role: Role) {
+        save_user(name, TEST_PASSWORD, role, &PasswordHashing::default())
+            .await
+            .expect("Could not save password.");
+    }
+
+    /// An admin named root and a viewer named viewer, both with the test password.
+    pub async fn with_test_users_scope<'a>() -> CleanableTestFile<'a> {
+        CleanableTestFile::new().await
+    }
+
+    mod check_login {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_the_identity() {
+            let _scope = with_test_users_scope().await;
+            let hashing = PasswordHashing::default();
+
+            assert_eq!(
+                check_login(ROOT_USER, TEST_PASSWORD, &hashing).await,
+                Ok(Some(test_admin()))
+            );
+            assert_eq!(
+                check_login(TEST_VIEWER, TEST_PASSWORD, &hashing).await,
+                Ok(Some(test_viewer()))
+            );
+        }
+
+        #[tokio::test]
+        async fn fails_for_a_bad_password_or_unknown_user() {
+            let _scope = with_test_users_scope().await;
+            let hashing = PasswordHashing::default();
+
+            assert_eq!(check_login(ROOT_USER, "bad", &hashing).await, Ok(None));
+            assert_eq!(
+                check_login("nobody", TEST_PASSWORD, &hashing).await,
+                Ok(None)
+            );
+        }
+
+        #[tokio::test]
+        #[allow(clippy::await_holding_lock)]
+        async fn migrates_and_rehashes_a_legacy_root_password() {
+            let _lock = TEST_USERS_MUTEX.lock().expect("Could not lock mutex.");
+            let _ = fs::remove_file(USERS_FILE).await;
+            let salt = "eyF8Ak6G48ZrRBs0";
+            fs::write(
+                LEGACY_ROOT_PASSWORD_FILE,
+                format!(
+                    "hash: {}\nsalt: {}\n",
+                    hex::encode(crate::core::hash::hash_sha256("test", salt)),
+                    salt
+                ),
+            )
+            .await
+            .expect("Could not write password.");
+            let hashing = PasswordHashing::default();
+
+            let checked = check_login(ROOT_USER, "test", &hashing).await;
+
+            let legacy_exists = fs::metadata(LEGACY_ROOT_PASSWORD_FILE).await.is_ok();
+            let users: Users = serde_yaml::from_str(
+                &fs::read_to_string(USERS_FILE)
+                    .await
+                    .expect("Could not read users."),
+            )
+            .expect("Could not deserialize users.");
+            fs::remove_file(USERS_FILE)
+                .await
+                .expect("Could not remove file.");
+            assert_eq!(checked, Ok(Some(test_admin())));
+            assert!(!legacy_exists);
+            let root = users.get(ROOT_USER).unwrap();
+            assert_eq!(root.role, Role::Admin);
+            assert!(!root.password.needs_rehash(&hashing));
+            assert!(root.password.verify("test"));
+        }
+    }
+
+    mod remove_user {
+        use super::*;
+
+        #[tokio::test]
+        async fn removes_the_user() {
+            let _scope = with_test_users_scope().await;
+
+            remove_user(TEST_VIEWER).await.unwrap();
+
+            assert_eq!(list_users().await, Ok(vec![test_admin()]));
+        }
+
+        #[tokio::test]
+        async fn keeps_the_last_admin() {
+            let _scope = with_test_users_scope().await;
+
+            assert!(remove_user(ROOT_USER).await.is_err());
+            assert!(remove_user("nobody").await.is_err());
+            assert_eq!(list_users().await, Ok(vec![test_admin(), test_viewer()]));
+        }
+    }
+}
@@ -1,49 +1,199 @@
-use tokio::sync::broadcast::error::SendError;
-use tokio::sync::broadcast::{channel, Receiver, Sender};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::{RecvError, SendError};
+use tokio::sync::broadcast::{channel, Receiver, Sender, WeakSender};
+use tokio::sync::Notify;
+
+pub const DEFAULT_CAPACITY: usize = 12;
+
+/// What a producer does when the slowest receiver of its channel falls `capacity` values behind.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LagPolicy {
+    /// Wait until there is room.
+    #[default]
+    Block,
+    /// Send anyway, so the slowest receivers lag and lose the oldest values.
+    Drop,
+    /// Keep values on disk until there is room. Producers that can't spill block instead.
+    Spill,
+}
 
 #[derive(Clone, Debug)]
 pub struct ChannelHandle<T> {
+    capacity: usize,
+    lagged: Arc<AtomicU64>,
+    policy: LagPolicy,
+    room: Arc<Notify>,
     sender: Sender<T>,
 }
 
 impl<T: Clone> ChannelHandle<T> {
     pub fn new() -> ChannelHandle<T> {
-        let (sender, _receiver) = channel::<T>(12);
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> ChannelHandle<T> {
+        Self::with_policy(capacity, LagPolicy::default())
+    }
+
+    pub fn with_policy(capacity: usize, policy: LagPolicy) -> ChannelHandle<T> {
+        let (sender, _receiver) = channel::<T>(capacity);
+
+        ChannelHandle {
+            capacity,
+            lagged: Arc::new(AtomicU64::new(0)),
+            policy,
+            room: Arc::new(Notify::new()),
+            sender,
+        }
+    }
 
-        ChannelHandle { sender }
+    pub fn capacity(&self) -> usize {
+        self.capacity
     }
 
+    /// Receives without making room for [ChannelHandle::send_or_wait], which only hears from
+    /// receivers subscribed through [Readonly].
     pub fn get_receiver(&self) -> Receiver<T> {
         self.sender.subscribe()
     }
 
+    /// Whether the slowest receiver is `capacity` values behind.
+    pub fn is_full(&self) -> bool {
+        self.sender.len() >= self.capacity
+    }
+
+    pub fn policy(&self) -> LagPolicy {
+        self.policy
+    }
+
     pub fn read_only(&self) -> Readonly<T> {
-        Readonly::new(self.sender.clone())
+        Readonly::new(
+            self.sender.downgrade(),
+            self.capacity,
+            self.policy,
+            &self.lagged,
+            &self.room,
+        )
     }
 
     pub fn send(&mut self, value: T) -> Result<usize, SendError<T>> {
         self.sender.send(value)
     }
+
+    /// Sends the value, first waiting for room unless the policy is to drop.
+    pub async fn send_or_wait(&mut self, value: T) -> Result<usize, SendError<T>> {
+        if self.policy != LagPolicy::Drop {
+            self.wait_for_room().await;
+        }
+        self.send(value)
+    }
+
+    /// Waits until the slowest receiver is less than `capacity` values behind.
+    pub async fn wait_for_room(&self) {
+        loop {
+            let room = self.room.notified();
+            tokio::pin!(room);
+            // Listening before looking, so a receiver can't make room in between unheard.
+            room.as_mut().enable();
+            if !self.is_full() {
+                return;
+            }
+            room.await;
+        }
+    }
 }
 
+/// Subscribes to a channel without keeping it open, so receivers see it close once every
+/// [ChannelHandle] for it is dropped.
 #[derive(Clone, Debug)]
 pub struct Readonly<T> {
-    sender: Sender<T>,
+    capacity: usize,
+    lagged: Arc<AtomicU64>,
+    policy: LagPolicy,
+    room: Arc<Notify>,
+    sender: WeakSender<T>,
 }
 
 impl<T: Clone> Readonly<T> {
-    pub fn new(sender: Sender<T>) -> Self {
-        Readonly { sender }
+    pub fn new(
+        sender: WeakSender<T>,
+        capacity: usize,
+        policy: LagPolicy,
+        lagged: &Arc<AtomicU64>,
+        room: &Arc<Notify>,
+    ) -> Self {
+        Readonly {
+            capacity,
+            lagged: lagged.clone(),
+            policy,
+            room: room.clone(),
+            sender,
+        }
     }
 
-    pub fn get_receiver(&self) -> Receiver<T> {
-        self.sender.subscribe()
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Receives from the channel, or is already closed if the channel is.
+    pub fn get_receiver(&self) -> RoomMakingReceiver<T> {
+        let receiver = match self.sender.upgrade() {
+            Some(sender) => sender.subscribe(),
+            None => channel(1).1,
+        };
+        RoomMakingReceiver {
+            receiver: Some(receiver),
+            room: self.room.clone(),
+        }
+    }
+
+    pub fn policy(&self) -> LagPolicy {
+        self.policy
+    }
+
+    /// Counts values a receiver lost by lagging, returning the total lost on this channel.
+    pub fn report_lagged(&self, amount: u64) -> u64 {
+        self.lagged.fetch_add(amount, Ordering::Relaxed) + amount
+    }
+}
+
+/// Receives from a channel, waking a sender waiting for room whenever it may have made some.
+#[derive(Debug)]
+pub struct RoomMakingReceiver<T> {
+    // Only taken when dropped.
+    receiver: Option<Receiver<T>>,
+    room: Arc<Notify>,
+}
+
+impl<T: Clone> RoomMakingReceiver<T> {
+    pub async fn recv(&mut self) -> Result<T, RecvError> {
+        let result = self
+            .receiver
+            .as_mut()
+            .expect("Receiver is only taken when dropped")
+            .recv()
+            .await;
+        self.room.notify_waiters();
+        result
+    }
+}
+
+impl<T> Drop for RoomMakingReceiver<T> {
+    fn drop(&mut self) {
+        // The values it hadn't read only stop counting once the receiver is gone.
+        drop(self.receiver.take());
+        self.room.notify_waiters();
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
+    use tokio::time::timeout;
 
     #[test]
     fn send() {
@@ -52,4 +202,88 @@ mod tests {
         let _ = handle.send(true);
         let _ = handle.send(false);
     }
+
+    #[test]
+    fn read_only_shares_settings() {
+        let handle: ChannelHandle<bool> = ChannelHandle::with_policy(3, LagPolicy::Spill);
+        let readonly = handle.read_only();
+
+        assert_eq!(readonly.capacity(), 3);
+        assert_eq!(readonly.policy(), LagPolicy::Spill);
+    }
+
+    #[test]
+    fn report_lagged_accumulates() {
+        let handle: ChannelHandle<bool> = ChannelHandle::new();
+
+        assert_eq!(handle.read_only().report_lagged(2), 2);
+        assert_eq!(handle.read_only().report_lagged(3), 5);
+    }
+
+    #[tokio::test]
+    async fn read_only_does_not_keep_the_channel_open() {
+        let mut handle = ChannelHandle::new();
+        let readonly = handle.read_only();
+        let mut receiver = readonly.get_receiver();
+
+        handle.send(1).unwrap();
+        drop(handle);
+
+        assert_eq!(receiver.recv().await, Ok(1));
+        assert!(receiver.recv().await.is_err());
+        assert!(readonly.get_receiver().recv().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn send_or_wait_blocks_until_there_is_room() {
+        let mut handle = ChannelHandle::with_policy(2, LagPolicy::Block);
+        let mut receiver = handle.read_only().get_receiver();
+        handle.send_or_wait(1).await.unwrap();
+        handle.send_or_wait(2).await.unwrap();
+        assert!(handle.is_full());
+
+        let blocked = timeout(Duration::from_millis(50), handle.send_or_wait(3)).await;
+        assert!(blocked.is_err());
+
+        assert_eq!(receiver.recv().await.unwrap(), 1);
+        handle.send_or_wait(3).await.unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), 2);
+        assert_eq!(receiver.recv().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn send_or_wait_goes_on_once_the_slowest_receiver_is_dropped() {
+        let mut handle = ChannelHandle::with_policy(1, LagPolicy::Block);
+        let receiver = handle.read_only().get_receiver();
+        handle.send_or_wait(1).await.unwrap();
+
+        let blocked = timeout(Duration::from_millis(50), handle.send_or_wait(2)).await;
+        assert!(blocked.is_err());
+
+        drop(receiver);
+        timeout(Duration::from_millis(50), handle.send_or_wait(2))
+            .await
+            .unwrap()
+            .unwrap_err();
+    }
+
+    #[test]
+    fn new_uses_the_default_policy() {
+        let handle: ChannelHandle<bool> = ChannelHandle::new();
+
+        assert_eq!(handle.policy(), LagPolicy::default());
+    }
+
+    #[tokio::test]
+    async fn send_or_wait_drops_when_asked() {
+        let mut handle = ChannelHandle::with_policy(2, LagPolicy::Drop);
+        let _receiver = handle.get_receiver();
+
+        for value in 0..4 {
+            timeout(Duration::from_millis(50), handle.send_or_wait(value))
+                .await
+                .unwrap()
+                .unwrap();
+        }
+    }
 }
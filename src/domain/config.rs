@@ -1,8 +1,15 @@
 use crate::core::cli::{ServeArgs, DEFAULT_CONFIG_NAME};
+use crate::domain::channel_handle::{LagPolicy, DEFAULT_CAPACITY};
+use crate::domain::login_throttling::LoginThrottling;
+use crate::domain::password_hashing::PasswordHashing;
+use crate::domain::retry::RetryPolicy;
+use chrono::NaiveTime;
 use derive_builder::Builder;
 use derive_getters::Getters;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
 use std::{fs, vec};
@@ -11,14 +18,21 @@ use url::Url;
 pub(crate) const HTTP_PORT: u16 = 80;
 pub const TLS_PORT: u16 = 443;
 pub const DEFAULT_SITE_FOLDER: &str = ".site";
+const DEFAULT_AUTHORIZATION_TIMEOUT_SECONDS: u64 = 60 * 60;
+const DEFAULT_SHUTDOWN_GRACE_SECONDS: u64 = 30;
 const LOCALHOST: &str = "http://localhost";
 
 lazy_static! {
     static ref DEFAULT_DOMAIN_CONFIG: DomainConfig = Default::default();
 }
 
-#[derive(Builder, Clone, Debug, Deserialize, Getters, PartialEq, Serialize)]
+#[derive(Builder, Clone, Debug, Default, Deserialize, Getters, PartialEq, Serialize)]
 pub struct Config {
+    /// How long a source waits for someone to finish authorizing it at the provider.
+    #[builder(default)]
+    #[getter(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    authorization_timeout_seconds: Option<u64>,
     #[getter(skip)]
     #[serde(skip_serializing_if = "Option::is_none")]
     domain_config: Option<DomainConfig>,
@@ -27,37 +41,96 @@ pub struct Config {
     #[getter(skip)]
     #[serde(skip_serializing_if = "Option::is_none")]
     exit_after: Option<u64>,
+    /// How many start, stop and other lifecycle events may wait for the slowest node.
+    #[builder(default)]
+    #[getter(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lifecycle_channel_capacity: Option<usize>,
+    #[builder(default)]
+    #[getter(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    login_throttling: Option<LoginThrottling>,
+    #[builder(default)]
+    #[getter(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    password_hashing: Option<PasswordHashing>,
+    #[builder(default)]
+    #[getter(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry: Option<RetryPolicy>,
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    routes: Option<Vec<RouteConfig>>,
+    /// What the command line asked for, to read the config the same way on reload.
+    #[builder(default)]
+    #[getter(skip)]
+    #[serde(skip)]
+    serve_args: Option<ServeArgs>,
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sinks: Option<BTreeMap<String, SinkConfig>>,
+    /// How long sources and sinks get to finish up when the service stops.
+    #[builder(default)]
+    #[getter(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shutdown_grace_seconds: Option<u64>,
     #[getter(skip)]
     #[serde(skip_serializing_if = "Option::is_none")]
     site_state_folder: Option<String>,
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sources: Option<BTreeMap<String, SourceConfig>>,
+    /// How many web events, like OAuth codes and redirects, may wait for the slowest listener.
+    #[builder(default)]
+    #[getter(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    web_channel_capacity: Option<usize>,
 }
 
 impl Config {
     pub(crate) fn new(serve_args: &ServeArgs) -> Arc<Self> {
-        Arc::new(match serve_args.config.as_ref() {
-            Some(config_file) => Self::open_config(config_file, serve_args),
+        Arc::new(Self::read(serve_args).unwrap_or_else(|e| panic!("{}", e)))
+    }
+
+    fn read(serve_args: &ServeArgs) -> Result<Self, String> {
+        let config = match serve_args.config.as_ref() {
+            Some(config_file) => Self::open_config(config_file, serve_args)?,
             None => {
                 if fs::exists(DEFAULT_CONFIG_NAME).unwrap_or(false) {
-                    Self::open_config(DEFAULT_CONFIG_NAME, serve_args)
+                    Self::open_config(DEFAULT_CONFIG_NAME, serve_args)?
                 } else {
                     Self {
-                        domain_config: None,
-                        email: None,
                         exit_after: serve_args.exit_after,
-                        site_state_folder: None,
+                        ..Default::default()
                     }
                     .merge_port(serve_args.port)
                 }
             }
+        };
+        Ok(Self {
+            serve_args: Some(serve_args.clone()),
+            ..config
         })
     }
 
-    fn open_config(config_path: &str, serve_args: &ServeArgs) -> Self {
-        let config_file = fs::read_to_string(config_path).expect("Could not open config file");
-        serde_yaml::from_str::<Self>(&config_file)
-            .expect("Could not parse config")
+    fn open_config(config_path: &str, serve_args: &ServeArgs) -> Result<Self, String> {
+        let config_file = fs::read_to_string(config_path)
+            .map_err(|e| format!("Could not open config file {}: {}", config_path, e))?;
+        Ok(serde_yaml::from_str::<Self>(&config_file)
+            .map_err(|e| format!("Could not parse config {}: {}", config_path, e))?
             .merge_exit_after(serve_args.exit_after)
-            .merge_port(serve_args.port)
+            .merge_port(serve_args.port))
+    }
+
+    /// Reads the config file again, with the same command line overrides as at startup.
+    pub(crate) fn reload(&self) -> Result<Self, String> {
+        let serve_args = self
+            .serve_args
+            .as_ref()
+            .ok_or("The config was not read from a file")?;
+        let config = Self::read(serve_args)?;
+        config.sanity_check()?;
+        Ok(config)
     }
 
     pub fn with_all_properties(
@@ -71,9 +144,17 @@ impl Config {
             email,
             exit_after,
             site_state_folder,
+            ..Default::default()
         }
     }
 
+    pub(crate) fn authorization_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.authorization_timeout_seconds
+                .unwrap_or(DEFAULT_AUTHORIZATION_TIMEOUT_SECONDS),
+        )
+    }
+
     pub(crate) fn domain_config(&self) -> &DomainConfig {
         self.domain_config
             .as_ref()
@@ -149,6 +230,44 @@ impl Config {
         }
     }
 
+    pub(crate) fn lifecycle_channel_capacity(&self) -> usize {
+        self.lifecycle_channel_capacity.unwrap_or(DEFAULT_CAPACITY)
+    }
+
+    /// How failed logins are throttled, with the defaults for anything left out.
+    pub(crate) fn login_throttling(&self) -> LoginThrottling {
+        self.login_throttling.clone().unwrap_or_default()
+    }
+
+    /// The costs for new password hashes, with the defaults for anything left out.
+    pub(crate) fn password_hashing(&self) -> PasswordHashing {
+        self.password_hashing.clone().unwrap_or_default()
+    }
+
+    /// How outbound operations retry, with the defaults for anything the `retry` section leaves out.
+    pub(crate) fn retry_policy(&self) -> RetryPolicy {
+        self.retry.clone().unwrap_or_default()
+    }
+
+    pub(crate) fn shutdown_grace_period(&self) -> Duration {
+        Duration::from_secs(
+            self.shutdown_grace_seconds
+                .unwrap_or(DEFAULT_SHUTDOWN_GRACE_SECONDS),
+        )
+    }
+
+    pub(crate) fn sink(&self, name: &str) -> Option<&SinkConfig> {
+        self.sinks.as_ref().and_then(|sinks| sinks.get(name))
+    }
+
+    /// Whether the named sink should run. All sinks run when there is no `sinks` section.
+    pub(crate) fn sink_enabled(&self, name: &str) -> bool {
+        match &self.sinks {
+            Some(sinks) => sinks.get(name).is_some_and(|sink| sink.enabled),
+            None => true,
+        }
+    }
+
     pub(crate) fn site_folder(&self) -> &str {
         match self.site_state_folder {
             Some(ref folder) => folder.as_str(),
@@ -156,10 +275,26 @@ impl Config {
         }
     }
 
+    pub(crate) fn source(&self, name: &str) -> Option<&SourceConfig> {
+        self.sources.as_ref().and_then(|sources| sources.get(name))
+    }
+
+    /// Whether the named source should run. All sources run when there is no `sources` section.
+    pub(crate) fn source_enabled(&self, name: &str) -> bool {
+        match &self.sources {
+            Some(sources) => sources.get(name).is_some_and(|source| source.enabled),
+            None => true,
+        }
+    }
+
     pub(crate) fn uses_tls(&self) -> bool {
         self.domain_config().url_in_use().scheme() == "https"
     }
 
+    pub(crate) fn web_channel_capacity(&self) -> usize {
+        self.web_channel_capacity.unwrap_or(DEFAULT_CAPACITY)
+    }
+
     pub(crate) fn websocket_url(&self) -> Url {
         let mut url = self
             .domain_config()
@@ -293,6 +428,102 @@ impl TlsConfig {
     }
 }
 
+/// Connects a source to a sink, by module name.
+#[derive(Clone, Debug, Deserialize, Getters, PartialEq, Serialize)]
+pub struct RouteConfig {
+    sink: String,
+    source: String,
+}
+
+impl RouteConfig {
+    pub fn new(source: &str, sink: &str) -> Self {
+        Self {
+            sink: sink.to_string(),
+            source: source.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Getters, PartialEq, Serialize)]
+pub struct SinkConfig {
+    #[serde(default = "enabled_by_default", skip_serializing_if = "is_enabled")]
+    enabled: bool,
+    /// Settings specific to the sink module.
+    #[serde(flatten)]
+    settings: BTreeMap<String, Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Getters, PartialEq, Serialize)]
+pub struct SourceConfig {
+    /// How many entities may wait for the slowest sink before the lag policy applies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channel_capacity: Option<usize>,
+    /// Syncs on a cron schedule, in local time, instead of every poll interval.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cron: Option<String>,
+    #[serde(default = "enabled_by_default", skip_serializing_if = "is_enabled")]
+    enabled: bool,
+    /// Delays each sync by up to this many seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    jitter_seconds: Option<u64>,
+    /// Limits the source's outbound requests. They aren't limited without it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_requests_per_second: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    on_lag: Option<LagPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    poll_interval_seconds: Option<u64>,
+    /// Syncs due in these local hours wait until they end.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quiet_hours: Option<QuietHours>,
+    /// How many requests may go out at once before the limit applies. 1 by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_burst: Option<u32>,
+    /// Settings specific to the source module.
+    #[serde(flatten)]
+    settings: BTreeMap<String, Value>,
+}
+
+impl SourceConfig {
+    pub(crate) fn jitter(&self) -> Option<Duration> {
+        self.jitter_seconds.map(Duration::from_secs)
+    }
+
+    pub(crate) fn poll_interval(&self) -> Option<Duration> {
+        self.poll_interval_seconds.map(Duration::from_secs)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Getters, PartialEq, Serialize)]
+pub struct QuietHours {
+    end: NaiveTime,
+    start: NaiveTime,
+}
+
+impl QuietHours {
+    #[cfg(test)]
+    pub(crate) fn new(start: NaiveTime, end: NaiveTime) -> Self {
+        Self { end, start }
+    }
+
+    /// Whether the time is in the quiet hours, which may run past midnight.
+    pub(crate) fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+fn is_enabled(enabled: &bool) -> bool {
+    *enabled
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use super::*;
@@ -300,9 +531,7 @@ pub(crate) mod tests {
     pub(crate) fn test_config() -> Arc<Config> {
         Arc::new(Config {
             domain_config: Default::default(),
-            email: None,
-            exit_after: None,
-            site_state_folder: None,
+            ..Default::default()
         })
     }
 
@@ -311,10 +540,9 @@ pub(crate) mod tests {
         email: Option<String>,
     ) -> Arc<Config> {
         Arc::new(Config {
-            domain_config: Some(domain_config.unwrap_or(Default::default())),
+            domain_config: Some(domain_config.unwrap_or_default()),
             email,
-            exit_after: None,
-            site_state_folder: None,
+            ..Default::default()
         })
     }
 
@@ -325,9 +553,8 @@ pub(crate) mod tests {
         fn test_instantiate() {
             let config = Config {
                 domain_config: Default::default(),
-                email: None,
-                exit_after: None,
                 site_state_folder: Some("test_site_folder".to_string()),
+                ..Default::default()
             };
 
             assert!(config.sanity_check().is_ok());
@@ -356,6 +583,150 @@ pub(crate) mod tests {
             assert!(config.sanity_check().is_ok());
         }
 
+        #[test]
+        fn test_login_throttling() {
+            let config = Config::with_all_properties(None, None, None, None);
+            assert_eq!(config.login_throttling(), LoginThrottling::default());
+
+            let config: Config =
+                serde_yaml::from_str("login_throttling:\n  ip_lockout_failures: 3").unwrap();
+            let expected: LoginThrottling = serde_yaml::from_str("ip_lockout_failures: 3").unwrap();
+            assert_eq!(config.login_throttling(), expected);
+            assert_ne!(expected, LoginThrottling::default());
+        }
+
+        #[test]
+        fn test_authorization_timeout() {
+            let config = Config::with_all_properties(None, None, None, None);
+            assert_eq!(config.authorization_timeout(), Duration::from_secs(3600));
+
+            let config: Config =
+                serde_yaml::from_str("authorization_timeout_seconds: 600").unwrap();
+            assert_eq!(config.authorization_timeout(), Duration::from_secs(600));
+        }
+
+        #[test]
+        fn test_channel_capacities() {
+            let config = Config::with_all_properties(None, None, None, None);
+            assert_eq!(config.lifecycle_channel_capacity(), DEFAULT_CAPACITY);
+            assert_eq!(config.web_channel_capacity(), DEFAULT_CAPACITY);
+
+            let config: Config =
+                serde_yaml::from_str("lifecycle_channel_capacity: 50\nweb_channel_capacity: 30")
+                    .unwrap();
+            assert_eq!(config.lifecycle_channel_capacity(), 50);
+            assert_eq!(config.web_channel_capacity(), 30);
+        }
+
+        #[test]
+        fn test_shutdown_grace_period() {
+            let config = Config::with_all_properties(None, None, None, None);
+            assert_eq!(config.shutdown_grace_period(), Duration::from_secs(30));
+
+            let config: Config = serde_yaml::from_str("shutdown_grace_seconds: 5").unwrap();
+            assert_eq!(config.shutdown_grace_period(), Duration::from_secs(5));
+        }
+
+        mod pipeline {
+            use super::*;
+
+            const PIPELINE_YAML: &str = r#"
+routes:
+  - sink: log
+    source: google
+sinks:
+  log: {}
+sources:
+  google:
+    channel_capacity: 100
+    cron: 0 3 * * *
+    jitter_seconds: 300
+    max_requests_per_second: 2.5
+    on_lag: spill
+    poll_interval_seconds: 600
+    quiet_hours:
+      start: "23:00"
+      end: "06:30"
+    request_burst: 10
+    tasklist: backup
+  stub:
+    enabled: false
+"#;
+
+            #[test]
+            fn without_sections_everything_is_enabled() {
+                let config = Config::with_all_properties(None, None, None, None);
+
+                assert!(config.sink_enabled("log"));
+                assert!(config.source_enabled("google"));
+                assert!(config.routes().is_none());
+            }
+
+            #[test]
+            fn only_listed_and_enabled_modules_are_enabled() {
+                let config: Config = serde_yaml::from_str(PIPELINE_YAML).unwrap();
+
+                assert!(config.sink_enabled("log"));
+                assert!(!config.sink_enabled("other"));
+                assert!(config.source_enabled("google"));
+                assert!(!config.source_enabled("stub"));
+                assert_eq!(
+                    config.routes(),
+                    &Some(vec![RouteConfig::new("google", "log")])
+                );
+            }
+
+            #[test]
+            fn source_settings_are_kept() {
+                let config: Config = serde_yaml::from_str(PIPELINE_YAML).unwrap();
+                let google = config.source("google").unwrap();
+
+                assert_eq!(google.channel_capacity(), &Some(100));
+                assert_eq!(google.on_lag(), &Some(LagPolicy::Spill));
+                assert_eq!(google.poll_interval(), Some(Duration::from_secs(600)));
+                assert_eq!(google.cron(), &Some("0 3 * * *".to_string()));
+                assert_eq!(google.jitter(), Some(Duration::from_secs(300)));
+                assert_eq!(google.max_requests_per_second(), &Some(2.5));
+                assert_eq!(google.request_burst(), &Some(10));
+                let quiet_hours = google.quiet_hours().unwrap();
+                assert!(quiet_hours.contains(NaiveTime::from_hms_opt(6, 0, 0).unwrap()));
+                assert!(!quiet_hours.contains(NaiveTime::from_hms_opt(6, 30, 0).unwrap()));
+                assert_eq!(
+                    google.settings().get("tasklist"),
+                    Some(&Value::String("backup".to_string()))
+                );
+            }
+
+            #[test]
+            fn round_trips() {
+                let config: Config = serde_yaml::from_str(PIPELINE_YAML).unwrap();
+                let serialized = serde_yaml::to_string(&config).unwrap();
+
+                assert_eq!(serde_yaml::from_str::<Config>(&serialized).unwrap(), config);
+            }
+        }
+
+        mod retry_policy {
+            use super::*;
+
+            #[test]
+            fn defaults_without_a_retry_section() {
+                let config = Config::with_all_properties(None, None, None, None);
+
+                assert_eq!(config.retry_policy(), RetryPolicy::default());
+            }
+
+            #[test]
+            fn defaults_fill_in_missing_settings() {
+                let config: Config = serde_yaml::from_str("retry:\n  max_retries: 3\n").unwrap();
+
+                assert_eq!(
+                    config.retry_policy(),
+                    RetryPolicy::default().with_max_retries(3)
+                );
+            }
+        }
+
         mod redirect_uri {
             use super::*;
 
@@ -5,6 +5,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, Hash, PartialEq, PartialOrd, Serialize)]
+#[serde(bound = "")]
 pub(crate) struct Entity<DataType>
 where
     DataType: EntityData,
@@ -26,7 +27,7 @@ where
         updated_at: DateTime<Utc>,
     ) -> Self {
         Self {
-            created_at: created_at.clone(),
+            created_at,
             data,
             id: id.to_string(),
             updated_at,
@@ -37,17 +38,14 @@ where
         Self::new(Utc::now(), data, id, Utc::now())
     }
 
-    #[cfg(test)]
     pub(crate) fn created_at(&self) -> DateTime<Utc> {
         self.created_at
     }
 
-    #[cfg(test)]
     pub(crate) fn updated_at(&self) -> DateTime<Utc> {
         self.updated_at
     }
 
-    #[cfg(test)]
     pub(crate) fn data(&self) -> &DataType {
         &self.data
     }
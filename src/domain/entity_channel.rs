@@ -0,0 +1,65 @@
+use crate::domain::channel_handle::Readonly;
+use crate::domain::entity_data::EntityData;
+use crate::domain::published::Published;
+use std::any::{Any, TypeId};
+use std::sync::Arc;
+
+/// A source's entity channel with its entity data type erased, so that it can be routed at
+/// runtime.
+#[derive(Clone)]
+pub(crate) struct EntityChannel {
+    data_type: TypeId,
+    handle: Arc<dyn Any + Send + Sync>,
+    source: &'static str,
+}
+
+impl EntityChannel {
+    pub(crate) fn new<DataType>(
+        source: &'static str,
+        handle: &Readonly<Published<DataType>>,
+    ) -> Self
+    where
+        DataType: EntityData + 'static,
+    {
+        Self {
+            data_type: TypeId::of::<DataType>(),
+            handle: Arc::new(handle.clone()),
+            source,
+        }
+    }
+
+    pub(crate) fn data_type(&self) -> TypeId {
+        self.data_type
+    }
+
+    pub(crate) fn downcast<DataType>(&self) -> Option<Readonly<Published<DataType>>>
+    where
+        DataType: EntityData + 'static,
+    {
+        self.handle
+            .downcast_ref::<Readonly<Published<DataType>>>()
+            .cloned()
+    }
+
+    pub(crate) fn source(&self) -> &'static str {
+        self.source
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::channel_handle::ChannelHandle;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_downcast() {
+        let handle: ChannelHandle<Published<Uuid>> = ChannelHandle::new();
+        let channel = EntityChannel::new("test", &handle.read_only());
+
+        assert_eq!(channel.data_type(), TypeId::of::<Uuid>());
+        assert_eq!(channel.source(), "test");
+        assert!(channel.downcast::<Uuid>().is_some());
+        assert!(channel.downcast::<String>().is_none());
+    }
+}
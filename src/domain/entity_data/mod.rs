@@ -1,8 +1,17 @@
 use std::fmt::Debug;
 
+use serde::de::DeserializeOwned;
 use serde::Serialize;
 
 pub(crate) mod string_entity_data;
 pub(crate) mod uuid_entity_data;
+pub(crate) mod value_entity_data;
 
-pub(crate) trait EntityData: Clone + Debug + Serialize + Send + Sync {}
+pub(crate) trait EntityData:
+    Clone + Debug + DeserializeOwned + Serialize + Send + Sync
+{
+    /// Whether the entity has been deleted from the cloud service it came from.
+    fn is_deleted(&self) -> bool {
+        false
+    }
+}
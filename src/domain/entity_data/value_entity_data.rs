@@ -0,0 +1,5 @@
+use crate::domain::entity_data::EntityData;
+use serde_yaml::Value;
+
+/// Reads back stored entities of any kind, for when only their shape matters.
+impl EntityData for Value {}
@@ -0,0 +1,19 @@
+use crate::domain::entity::Entity;
+use crate::domain::entity_data::EntityData;
+use std::any::TypeId;
+
+pub(crate) trait EntityTranslator<InputType, OutputType>: Send + Sync + 'static
+where
+    InputType: EntityData + 'static,
+    OutputType: EntityData + 'static,
+{
+    fn input_entity(&self) -> TypeId {
+        TypeId::of::<InputType>()
+    }
+
+    fn output_entity(&self) -> TypeId {
+        TypeId::of::<OutputType>()
+    }
+
+    fn translate(&self, entity: &Entity<InputType>) -> Entity<OutputType>;
+}
@@ -0,0 +1,5 @@
+use std::any::TypeId;
+
+pub(crate) trait EntityUser {
+    fn supported_entity_data() -> Vec<TypeId>;
+}
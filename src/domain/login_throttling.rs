@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How failed logins slow down and lock out further attempts, from one address or from
+/// everywhere.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct LoginThrottling {
+    /// Failures older than this are forgotten.
+    failure_window_seconds: u64,
+    /// Locks out every address once this many logins have failed in the window.
+    global_lockout_failures: u32,
+    global_lockout_seconds: u64,
+    /// The wait after an address's first failure, doubling after each further one.
+    initial_delay_millis: u64,
+    /// Locks out an address once it has failed this many times in the window.
+    ip_lockout_failures: u32,
+    ip_lockout_seconds: u64,
+    max_delay_seconds: u64,
+}
+
+impl Default for LoginThrottling {
+    fn default() -> Self {
+        Self {
+            failure_window_seconds: 15 * 60,
+            global_lockout_failures: 50,
+            global_lockout_seconds: 5 * 60,
+            initial_delay_millis: 500,
+            ip_lockout_failures: 5,
+            ip_lockout_seconds: 15 * 60,
+            max_delay_seconds: 30,
+        }
+    }
+}
+
+impl LoginThrottling {
+    /// The wait after an address's latest failure, short of a lockout.
+    pub(crate) fn delay(&self, failures: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+        Duration::from_millis(self.initial_delay_millis)
+            .saturating_mul(factor)
+            .min(Duration::from_secs(self.max_delay_seconds))
+    }
+
+    pub(crate) fn failure_window(&self) -> Duration {
+        Duration::from_secs(self.failure_window_seconds)
+    }
+
+    pub(crate) fn global_lockout(&self) -> Duration {
+        Duration::from_secs(self.global_lockout_seconds)
+    }
+
+    pub(crate) fn global_lockout_failures(&self) -> u32 {
+        self.global_lockout_failures
+    }
+
+    pub(crate) fn ip_lockout(&self) -> Duration {
+        Duration::from_secs(self.ip_lockout_seconds)
+    }
+
+    pub(crate) fn ip_lockout_failures(&self) -> u32 {
+        self.ip_lockout_failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delays_grow_exponentially_up_to_the_max() {
+        let settings: LoginThrottling =
+            serde_yaml::from_str("initial_delay_millis: 1000\nmax_delay_seconds: 5").unwrap();
+
+        assert_eq!(settings.delay(1), Duration::from_secs(1));
+        assert_eq!(settings.delay(2), Duration::from_secs(2));
+        assert_eq!(settings.delay(3), Duration::from_secs(4));
+        assert_eq!(settings.delay(4), Duration::from_secs(5));
+        assert_eq!(settings.delay(1000), Duration::from_secs(5));
+    }
+}
@@ -1,10 +1,22 @@
 pub(crate) mod channel_handle;
 pub(crate) mod config;
 pub(crate) mod entity;
+pub(crate) mod entity_channel;
 pub(crate) mod entity_data;
+pub(crate) mod entity_translator;
+pub(crate) mod entity_user;
+pub(crate) mod login_throttling;
 pub(crate) mod module_state;
 pub(crate) mod mpsc_handle;
 pub(crate) mod node;
 pub(crate) mod oauth2;
+pub(crate) mod password_hashing;
+pub(crate) mod published;
+pub(crate) mod rate_limiter;
+pub(crate) mod request_layer;
+pub(crate) mod retry;
+pub(crate) mod sink;
+pub(crate) mod source;
+pub(crate) mod translator_registry;
 
 pub use config::{Config, DomainConfig};
@@ -99,9 +99,9 @@ mod tests {
     async fn send_and_recv() {
         let (sender, mut receiver) = one_shot::<bool>();
 
-        let _ = sender.send(true).await.unwrap();
+        sender.send(true).await.unwrap();
         let value = receiver.recv().await.unwrap();
 
-        assert_eq!(value, true);
+        assert!(value);
     }
 }
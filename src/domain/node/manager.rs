@@ -1,39 +1,74 @@
 use crate::domain::config::Config;
+use crate::domain::module_state::NamedModule;
 use crate::domain::mpsc_handle::OneshotMpscSenderHandle;
 use crate::domain::node::InitReplier;
-use crate::domain::node::Lifecycle::{Init, ReadConfig, Stop};
-use crate::domain::node::{Lifecycle, LifecycleChannelHandle};
+use crate::domain::node::Lifecycle::{Init, Pause, ReadConfig, ReloadConfig, Resume, Stop};
+use crate::domain::node::{Lifecycle, LifecycleChannelHandle, StatusBoard};
 use log::{debug, error};
+use parking_lot::{Mutex, RwLock};
 use std::any::TypeId;
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::broadcast::error::{RecvError, SendError};
 use tokio::sync::broadcast::Receiver;
 use tokio::sync::Semaphore;
 use tokio::task;
 use tokio::task::JoinHandle;
+use tokio::time::{sleep, Instant};
+
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 #[derive(Clone, Debug)]
 pub struct Manager {
-    config: Arc<Config>,
+    /// Replaced when the config is reloaded.
+    config: Arc<RwLock<Arc<Config>>>,
     lifecycle_channel_handle: LifecycleChannelHandle,
+    /// The running sources and sinks by module name, so they can be paused and resumed by name.
+    nodes: Arc<Mutex<BTreeMap<&'static str, Vec<TypeId>>>>,
+    /// Sources sync once and stop, and nothing waits for a user.
+    one_shot: bool,
+    status: StatusBoard,
 }
 
 impl Manager {
     pub fn new(config: &Arc<Config>, lifecycle_channel_handle: LifecycleChannelHandle) -> Self {
         Self {
-            config: config.clone(),
+            config: Arc::new(RwLock::new(config.clone())),
             lifecycle_channel_handle,
+            nodes: Arc::new(Mutex::new(BTreeMap::new())),
+            one_shot: false,
+            status: StatusBoard::new(),
         }
     }
 
-    pub fn core_config(&self) -> &Config {
-        self.config.as_ref()
+    /// The config as last read, which a reload may since have replaced.
+    pub fn core_config(&self) -> Arc<Config> {
+        self.config.read().clone()
+    }
+
+    pub(crate) fn is_one_shot(&self) -> bool {
+        self.one_shot
+    }
+
+    /// The module names of the running sources and sinks.
+    pub(crate) fn node_names(&self) -> Vec<&'static str> {
+        self.nodes.lock().keys().copied().collect()
     }
 
     pub fn readonly(&self) -> ReadonlyManager {
         ReadonlyManager::new(self)
     }
 
+    /// Makes the node pausable by its module name.
+    pub(crate) fn register_node<T: NamedModule + 'static>(&self) {
+        let mut nodes = self.nodes.lock();
+        let type_ids = nodes.entry(T::name()).or_default();
+        if !type_ids.contains(&TypeId::of::<T>()) {
+            type_ids.push(TypeId::of::<T>());
+        }
+    }
+
     pub fn send_init(
         &mut self,
         sender: OneshotMpscSenderHandle<()>,
@@ -42,14 +77,64 @@ impl Manager {
         Ok(())
     }
 
+    pub fn send_pause<T: 'static>(&mut self) -> Result<usize, SendError<Lifecycle>> {
+        self.lifecycle_channel_handle.send(Pause(TypeId::of::<T>()))
+    }
+
+    /// Pauses the running source or sink with the module name, returning whether there is one.
+    pub(crate) fn send_pause_to(&mut self, name: &str) -> Result<bool, SendError<Lifecycle>> {
+        self.send_to(name, Pause)
+    }
+
     pub fn send_read_config<T: 'static>(&mut self) -> Result<usize, SendError<Lifecycle>> {
         self.lifecycle_channel_handle
             .send(ReadConfig(TypeId::of::<T>()))
     }
 
+    /// Reads the config file again, then tells the nodes so they can pick up their settings.
+    /// Keeps the config as it was if the file can't be read.
+    pub fn send_reload_config(&mut self) -> Result<usize, String> {
+        let config = self.core_config().reload()?;
+        *self.config.write() = Arc::new(config);
+        self.lifecycle_channel_handle
+            .send(ReloadConfig)
+            .map_err(|e| format!("Lifecycle error while sending reload signal: {:?}", e))
+    }
+
+    pub fn send_resume<T: 'static>(&mut self) -> Result<usize, SendError<Lifecycle>> {
+        self.lifecycle_channel_handle
+            .send(Resume(TypeId::of::<T>()))
+    }
+
+    /// Resumes the paused source or sink with the module name, returning whether there is one.
+    pub(crate) fn send_resume_to(&mut self, name: &str) -> Result<bool, SendError<Lifecycle>> {
+        self.send_to(name, Resume)
+    }
+
     pub fn send_stop(&mut self) -> Result<usize, SendError<Lifecycle>> {
         self.lifecycle_channel_handle.send(Stop)
     }
+
+    pub fn status(&self) -> &StatusBoard {
+        &self.status
+    }
+
+    fn send_to(
+        &mut self,
+        name: &str,
+        event: impl Fn(TypeId) -> Lifecycle,
+    ) -> Result<bool, SendError<Lifecycle>> {
+        let type_ids = self.nodes.lock().get(name).cloned().unwrap_or_default();
+        for type_id in &type_ids {
+            self.lifecycle_channel_handle.send(event(*type_id))?;
+        }
+        Ok(!type_ids.is_empty())
+    }
+
+    pub(crate) fn with_one_shot(mut self) -> Self {
+        self.one_shot = true;
+        self
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -64,18 +149,50 @@ impl ReadonlyManager {
         }
     }
 
+    pub(crate) fn core_config(&self) -> Arc<Config> {
+        self.manager.core_config()
+    }
+
     pub(crate) async fn abort_on_stop<T>(&self, task: &JoinHandle<T>) -> JoinHandle<()> {
-        abort_on_stop::<T>(self.manager.lifecycle_channel_handle.get_receiver(), task).await
+        abort_on_stop::<T>(
+            self.manager.lifecycle_channel_handle.get_receiver(),
+            task,
+            Duration::ZERO,
+        )
+        .await
+    }
+
+    /// Like [Self::abort_on_stop], but lets the task finish by itself within the shutdown grace
+    /// period first.
+    pub(crate) async fn abort_after_grace_on_stop<T>(
+        &self,
+        task: &JoinHandle<T>,
+    ) -> JoinHandle<()> {
+        abort_on_stop::<T>(
+            self.manager.lifecycle_channel_handle.get_receiver(),
+            task,
+            self.manager.core_config().shutdown_grace_period(),
+        )
+        .await
     }
 
     pub(crate) fn get_receiver(&self) -> Receiver<Lifecycle> {
         self.manager.lifecycle_channel_handle.get_receiver()
     }
+
+    pub(crate) fn is_one_shot(&self) -> bool {
+        self.manager.is_one_shot()
+    }
+
+    pub(crate) fn status(&self) -> &StatusBoard {
+        self.manager.status()
+    }
 }
 
 pub(crate) async fn abort_on_stop<T>(
     mut event_receiver: Receiver<Lifecycle>,
     task: &JoinHandle<T>,
+    grace: Duration,
 ) -> JoinHandle<()> {
     let task_abort_handle = task.abort_handle();
     let semaphore = Arc::new(Semaphore::new(1));
@@ -91,6 +208,10 @@ pub(crate) async fn abort_on_stop<T>(
                 Ok(event) => match event {
                     Init(event) => event.reply_to_init_with((), "abort_on_stop").await,
                     Stop => {
+                        let deadline = Instant::now() + grace;
+                        while !task_abort_handle.is_finished() && Instant::now() < deadline {
+                            sleep(STOP_POLL_INTERVAL).await;
+                        }
                         task_abort_handle.abort();
                         break;
                     }
@@ -144,4 +265,66 @@ pub mod tests {
             assert_eq!(event, ReadConfig(TypeId::of::<TestNode>()));
         }
     }
+
+    mod send_pause_and_resume {
+        use super::*;
+        use crate::core::cli::ServeArgs;
+        use crate::domain::config::tests::test_config;
+        use crate::domain::node::tests::TestNode;
+        use crate::integration::google::Source;
+        use std::env::temp_dir;
+        use uuid::Uuid;
+
+        #[tokio::test]
+        async fn test_send_pause_reload_and_resume() {
+            let path = temp_dir().join(format!("cloud-scraper-config-{}.yaml", Uuid::new_v4()));
+            std::fs::write(&path, "shutdown_grace_seconds: 5").unwrap();
+            let config = Config::new(&ServeArgs {
+                config: Some(path.to_string_lossy().to_string()),
+                ..ServeArgs::default()
+            });
+            let mut manager = get_test_manager(&config);
+            let mut lifecycle_receiver = manager.readonly().get_receiver();
+
+            std::fs::write(&path, "shutdown_grace_seconds: 7").unwrap();
+            manager.send_pause::<TestNode>().unwrap();
+            manager.send_reload_config().unwrap();
+            manager.send_resume::<TestNode>().unwrap();
+            let _ = std::fs::remove_file(&path);
+
+            let test_node = TypeId::of::<TestNode>();
+            assert_eq!(lifecycle_receiver.recv().await.unwrap(), Pause(test_node));
+            assert_eq!(lifecycle_receiver.recv().await.unwrap(), ReloadConfig);
+            assert_eq!(lifecycle_receiver.recv().await.unwrap(), Resume(test_node));
+            assert_eq!(
+                manager.core_config().shutdown_grace_period(),
+                Duration::from_secs(7)
+            );
+        }
+
+        #[test]
+        fn test_failed_reload_keeps_the_config() {
+            let config = test_config();
+            let mut manager = get_test_manager(&config);
+
+            assert!(manager.send_reload_config().is_err());
+            assert_eq!(manager.core_config(), config);
+        }
+
+        #[tokio::test]
+        async fn test_send_pause_and_resume_by_name() {
+            let mut manager = get_test_manager(&test_config());
+            let mut lifecycle_receiver = manager.readonly().get_receiver();
+            manager.register_node::<Source>();
+
+            assert_eq!(manager.node_names(), vec!["google"]);
+            assert!(manager.send_pause_to("google").unwrap());
+            assert!(manager.send_resume_to("google").unwrap());
+            assert!(!manager.send_pause_to("nothing").unwrap());
+
+            let google = TypeId::of::<Source>();
+            assert_eq!(lifecycle_receiver.recv().await.unwrap(), Pause(google));
+            assert_eq!(lifecycle_receiver.recv().await.unwrap(), Resume(google));
+        }
+    }
 }
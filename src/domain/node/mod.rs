@@ -1,21 +1,44 @@
 mod manager;
+mod schedule;
+mod sink_node;
+mod source_node;
+mod spill;
+mod status;
+mod translator_node;
 
 pub use manager::{Manager, ReadonlyManager};
+pub(crate) use schedule::Schedule;
+pub(crate) use sink_node::SinkNode;
+pub(crate) use source_node::SourceNode;
+pub(crate) use status::short_type_name;
+pub use status::{NodeKind, NodeState, NodeStatus, StatusBoard};
+pub(crate) use translator_node::TranslatorNode;
 
 #[cfg(test)]
 pub use manager::tests::get_test_manager;
 
 use crate::domain::channel_handle::ChannelHandle;
+use crate::domain::entity_channel::EntityChannel;
 use crate::domain::mpsc_handle::OneshotMpscSenderHandle;
 use crate::domain::node::Lifecycle::ReadConfig;
+use crate::domain::translator_registry::TranslatorRegistry;
 use async_trait::async_trait;
 use std::any::TypeId;
+use tokio::sync::OwnedSemaphorePermit;
 use Lifecycle::Stop;
 
+/// Events every node listens for. Those carrying a [TypeId] are for the source or sink of that
+/// type.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Lifecycle {
     Init(OneshotMpscSenderHandle<()>),
+    /// Stop getting or putting entities until resumed. Paused sinks hold on to what they're sent
+    /// meanwhile.
+    Pause(TypeId),
     ReadConfig(TypeId),
+    /// Every source and sink re-reads its configuration.
+    ReloadConfig,
+    Resume(TypeId),
     Stop,
 }
 
@@ -83,6 +106,35 @@ impl<E: std::fmt::Debug> LifecycleAware for Result<Lifecycle, E> {
     }
 }
 
+/// A unit of work the engine runs, dropping its permit once it is listening for lifecycle events.
+///
+/// Every node replies to [Lifecycle::Init] exactly once.
+#[async_trait]
+pub(crate) trait Node: Send {
+    async fn run(self: Box<Self>, permit: OwnedSemaphorePermit);
+}
+
+/// A node publishing entities, whatever their entity data.
+pub(crate) trait ProducerNode: Node {
+    fn entity_channel(&self) -> EntityChannel;
+}
+
+/// A node consuming entities, whatever their entity data.
+pub(crate) trait ConsumerNode: Node {
+    /// The entity data this node takes without translation.
+    fn data_type(&self) -> TypeId;
+
+    /// Subscribes to `input`, translating its entities if needed.
+    ///
+    /// Returns the translator node that must also be run, if any.
+    fn connect(
+        &mut self,
+        manager: &Manager,
+        translators: &TranslatorRegistry,
+        input: &EntityChannel,
+    ) -> Result<Option<Box<dyn Node>>, String>;
+}
+
 #[async_trait]
 pub trait InitReplier<T> {
     async fn reply_to_init_with(&self, value: T, sent_in: &str);
@@ -101,7 +153,10 @@ pub mod tests {
     mod readonly_manager {
         use super::*;
         use crate::domain::mpsc_handle::one_shot;
+        use std::future::pending;
+        use std::time::Duration;
         use tokio::task;
+        use tokio::time::{sleep, Instant};
         use tokio_test::assert_ok;
 
         #[tokio::test]
@@ -131,6 +186,32 @@ pub mod tests {
             assert_ok!(stop_handle.await);
         }
 
+        #[tokio::test(start_paused = true)]
+        async fn test_abort_after_grace_on_stop_lets_the_task_finish() {
+            let config = test_config();
+            let mut manager = get_test_manager(&config);
+            let readonly_manager = manager.readonly();
+            let task = task::spawn(sleep(Duration::from_secs(1)));
+            let stop_handle = readonly_manager.abort_after_grace_on_stop(&task).await;
+            manager.send_stop().expect("Could not send stop signal.");
+            assert_ok!(stop_handle.await);
+            assert_ok!(task.await);
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_abort_after_grace_on_stop_aborts_after_the_grace_period() {
+            let config = test_config();
+            let mut manager = get_test_manager(&config);
+            let readonly_manager = manager.readonly();
+            let task = task::spawn(pending::<()>());
+            let stop_handle = readonly_manager.abort_after_grace_on_stop(&task).await;
+            let start = Instant::now();
+            manager.send_stop().expect("Could not send stop signal.");
+            assert_ok!(stop_handle.await);
+            assert!(task.await.unwrap_err().is_cancelled());
+            assert!(start.elapsed() >= config.shutdown_grace_period());
+        }
+
         #[tokio::test]
         async fn test_get_receiver() {
             let config = test_config();
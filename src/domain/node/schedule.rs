@@ -0,0 +1,238 @@
+use crate::domain::config::{QuietHours, SourceConfig};
+use chrono::{DateTime, Days, Local, TimeZone, Utc};
+use rand::{thread_rng, Rng};
+use std::str::FromStr;
+use std::time::Duration;
+
+#[derive(Clone, Debug)]
+enum Timing {
+    Cron(Box<cron::Schedule>),
+    Interval(Duration),
+}
+
+/// When a source syncs: on a cron schedule or every interval, with jitter and quiet hours.
+#[derive(Clone, Debug)]
+pub(crate) struct Schedule {
+    jitter: Duration,
+    quiet_hours: Option<QuietHours>,
+    timing: Timing,
+}
+
+impl Schedule {
+    pub(crate) fn every(interval: Duration) -> Self {
+        Self {
+            jitter: Duration::ZERO,
+            quiet_hours: None,
+            timing: Timing::Interval(interval),
+        }
+    }
+
+    /// Reads the schedule from the source's config, syncing every `poll_interval` by default.
+    pub(crate) fn from_config(
+        config: Option<&SourceConfig>,
+        poll_interval: Duration,
+    ) -> Result<Self, String> {
+        let Some(config) = config else {
+            return Ok(Self::every(poll_interval));
+        };
+        let timing = match config.cron() {
+            Some(expression) => Timing::Cron(Box::new(parse_cron(expression)?)),
+            None => Timing::Interval(config.poll_interval().unwrap_or(poll_interval)),
+        };
+        Ok(Self {
+            jitter: config.jitter().unwrap_or_default(),
+            quiet_hours: *config.quiet_hours(),
+            timing,
+        })
+    }
+
+    /// How long to wait from now until the next sync.
+    pub(crate) fn delay(&self) -> Duration {
+        self.delay_after(Some(Utc::now()))
+    }
+
+    /// How long to wait from now until the sync due after the one at `last`, which is no time at
+    /// all if it's overdue. Without a last sync, one is due straight away unless the schedule is
+    /// cron.
+    pub(crate) fn delay_after(&self, last: Option<DateTime<Utc>>) -> Duration {
+        let jitter = self.jitter.mul_f64(thread_rng().gen_range(0.0..=1.0));
+        let now = Local::now();
+        self.delay_from(now, last.map(|last| last.with_timezone(&Local)), jitter)
+    }
+
+    fn delay_from<Tz: TimeZone>(
+        &self,
+        now: DateTime<Tz>,
+        last: Option<DateTime<Tz>>,
+        jitter: Duration,
+    ) -> Duration {
+        let next = match (&self.timing, last) {
+            (Timing::Cron(schedule), last) => {
+                match schedule.after(&last.unwrap_or(now.clone())).next() {
+                    Some(next) => next,
+                    None => return Duration::MAX,
+                }
+            }
+            (Timing::Interval(interval), Some(last)) => last + *interval,
+            (Timing::Interval(_), None) => now.clone(),
+        } + jitter;
+        let next = match &self.quiet_hours {
+            Some(quiet_hours) => after_quiet_hours(quiet_hours, next),
+            None => next,
+        };
+        (next - now).to_std().unwrap_or_default()
+    }
+}
+
+/// Accepts the usual five field expressions as well as the six and seven field ones with seconds
+/// and years.
+fn parse_cron(expression: &str) -> Result<cron::Schedule, String> {
+    let expression = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+    cron::Schedule::from_str(&expression)
+        .map_err(|e| format!("Invalid cron expression {}: {}", expression, e))
+}
+
+fn after_quiet_hours<Tz: TimeZone>(quiet_hours: &QuietHours, time: DateTime<Tz>) -> DateTime<Tz> {
+    if !quiet_hours.contains(time.time()) {
+        return time;
+    }
+
+    let timezone = time.timezone();
+    let mut date = time.date_naive();
+    if time.time() >= *quiet_hours.end() {
+        date = date + Days::new(1);
+    }
+    timezone
+        .from_local_datetime(&date.and_time(*quiet_hours.end()))
+        .earliest()
+        .unwrap_or(time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveTime;
+
+    fn config(yaml: &str) -> SourceConfig {
+        serde_yaml::from_str(yaml).expect("Could not parse source config")
+    }
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 1, hour, minute, 0).unwrap()
+    }
+
+    fn time(hour: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_interval_by_default() {
+        let schedule = Schedule::from_config(None, Duration::from_secs(10)).unwrap();
+
+        assert_eq!(
+            schedule.delay_from(at(12, 0), Some(at(12, 0)), Duration::ZERO),
+            Duration::from_secs(10)
+        );
+    }
+
+    #[test]
+    fn test_cron_every_night() {
+        let schedule =
+            Schedule::from_config(Some(&config("cron: 0 3 * * *")), Duration::from_secs(10))
+                .unwrap();
+
+        assert_eq!(
+            schedule.delay_from(at(2, 30), Some(at(2, 30)), Duration::ZERO),
+            Duration::from_secs(30 * 60)
+        );
+        assert_eq!(
+            schedule.delay_from(at(3, 0), Some(at(3, 0)), Duration::ZERO),
+            Duration::from_secs(24 * 60 * 60)
+        );
+    }
+
+    #[test]
+    fn test_invalid_cron_is_an_error() {
+        let result = Schedule::from_config(Some(&config("cron: every night")), Duration::ZERO);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_jitter_delays_the_sync() {
+        let schedule = Schedule::from_config(
+            Some(&config("jitter_seconds: 60\npoll_interval_seconds: 600")),
+            Duration::from_secs(10),
+        )
+        .unwrap();
+
+        assert_eq!(
+            schedule.delay_from(at(12, 0), Some(at(12, 0)), Duration::from_secs(45)),
+            Duration::from_secs(645)
+        );
+        let delay = schedule.delay();
+        assert!(delay >= Duration::from_secs(599) && delay <= Duration::from_secs(660));
+    }
+
+    #[test]
+    fn test_quiet_hours_past_midnight() {
+        let mut schedule = Schedule::every(Duration::from_secs(60 * 60));
+        schedule.quiet_hours = Some(QuietHours::new(time(23), time(6)));
+
+        // Due at 23:30, so it waits until 6am.
+        assert_eq!(
+            schedule.delay_from(at(22, 30), Some(at(22, 30)), Duration::ZERO),
+            Duration::from_secs(7 * 60 * 60 + 30 * 60)
+        );
+        // Due at 3am, so it waits until 6am.
+        assert_eq!(
+            schedule.delay_from(at(2, 0), Some(at(2, 0)), Duration::ZERO),
+            Duration::from_secs(4 * 60 * 60)
+        );
+        // Due at 7am, outside the quiet hours.
+        assert_eq!(
+            schedule.delay_from(at(6, 0), Some(at(6, 0)), Duration::ZERO),
+            Duration::from_secs(60 * 60)
+        );
+    }
+
+    #[test]
+    fn test_delay_after_the_last_sync() {
+        let schedule = Schedule::every(Duration::from_secs(60 * 60));
+
+        assert_eq!(
+            schedule.delay_from(at(12, 0), Some(at(11, 30)), Duration::ZERO),
+            Duration::from_secs(30 * 60)
+        );
+        // Overdue, or never synced, so it's due now.
+        assert_eq!(
+            schedule.delay_from(at(12, 0), Some(at(9, 0)), Duration::ZERO),
+            Duration::ZERO
+        );
+        assert_eq!(
+            schedule.delay_from(at(12, 0), None, Duration::ZERO),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_cron_waits_for_its_time_without_a_last_sync() {
+        let schedule =
+            Schedule::from_config(Some(&config("cron: 0 3 * * *")), Duration::from_secs(10))
+                .unwrap();
+
+        assert_eq!(
+            schedule.delay_from(at(2, 30), None, Duration::ZERO),
+            Duration::from_secs(30 * 60)
+        );
+        // Missed this morning's sync, so it's due now.
+        assert_eq!(
+            schedule.delay_from(at(12, 0), Some(at(2, 0) - Days::new(1)), Duration::ZERO),
+            Duration::ZERO
+        );
+    }
+}
@@ -0,0 +1,511 @@
+use crate::core::metrics;
+use crate::core::metrics::Metric;
+use crate::domain::channel_handle::Readonly;
+use crate::domain::entity::Entity;
+use crate::domain::entity_channel::EntityChannel;
+use crate::domain::entity_data::EntityData;
+use crate::domain::node::Lifecycle::{Pause, ReloadConfig, Resume, Stop};
+use crate::domain::node::{
+    short_type_name, ConsumerNode, Lifecycle, LifecycleAware, Manager, Node, NodeKind, NodeState,
+    ReadonlyManager, StatusBoard,
+};
+use crate::domain::published::{Acknowledgement, Published};
+use crate::domain::sink::Sink;
+use crate::domain::translator_registry::TranslatorRegistry;
+use async_trait::async_trait;
+use log::error;
+use std::any::TypeId;
+use std::collections::HashSet;
+use std::marker::PhantomData;
+use std::mem;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, OwnedSemaphorePermit};
+use tokio::task::JoinSet;
+use tokio::{join, select, task};
+
+const BATCH_SIZE: usize = 64;
+
+/// Runs a [Sink], putting everything published by the sources it subscribes to.
+pub(crate) struct SinkNode<SinkType, DataType>
+where
+    SinkType: Sink<DataType>,
+    DataType: EntityData,
+{
+    inputs: Vec<(&'static str, Readonly<Published<DataType>>)>,
+    lifecycle_manager: ReadonlyManager,
+    sink: SinkType,
+    phantom: PhantomData<DataType>,
+}
+
+impl<SinkType, DataType> SinkNode<SinkType, DataType>
+where
+    SinkType: Sink<DataType>,
+    DataType: EntityData + 'static,
+{
+    pub(crate) fn new(manager: &Manager, sink: SinkType) -> Self {
+        debug_assert!(
+            SinkType::supported_entity_data().contains(&TypeId::of::<DataType>()),
+            "{} does not declare the entity data it accepts",
+            SinkType::name()
+        );
+        Self {
+            inputs: Vec::new(),
+            lifecycle_manager: manager.readonly(),
+            sink,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The name on the status board, since a sink can run once for each kind of entity data.
+    fn status_name() -> String {
+        format!("{} ({})", SinkType::name(), short_type_name::<DataType>())
+    }
+
+    /// Puts the pending entities, acknowledging the end of each sync once it's flushed.
+    ///
+    /// `failed` holds the sources with entities that couldn't be put since their last sync ended.
+    async fn put(
+        sink: &mut SinkType,
+        status: &StatusBoard,
+        name: &str,
+        failed: &mut HashSet<&'static str>,
+        pending: Vec<(&'static str, Published<DataType>)>,
+    ) {
+        for batch in batch_by_source(pending) {
+            match batch {
+                Batch::Put(source, entities) => match sink.put(source, &entities).await {
+                    Ok(()) => {
+                        metrics::add(
+                            Metric::EntitiesWritten,
+                            &[("sink", SinkType::name()), ("source", source)],
+                            entities.len() as u64,
+                        );
+                        status.set(name, NodeKind::Sink, NodeState::Started);
+                    }
+                    Err(e) => {
+                        error!(
+                            "Error while putting entities from {} into {}: {}",
+                            source,
+                            sink.sink_identifier(),
+                            e
+                        );
+                        status.errored(name, NodeKind::Sink, &e.to_string());
+                        failed.insert(source);
+                    }
+                },
+                Batch::Synced(source, acknowledgement) => {
+                    let put = !failed.remove(source);
+                    let flushed = match sink.flush(source).await {
+                        Ok(()) => true,
+                        Err(e) => {
+                            error!(
+                                "Error while flushing entities from {} into {}: {}",
+                                source,
+                                sink.sink_identifier(),
+                                e
+                            );
+                            status.errored(name, NodeKind::Sink, &e.to_string());
+                            false
+                        }
+                    };
+                    acknowledgement.acknowledge(put && flushed);
+                }
+            }
+        }
+    }
+
+    /// Returns whether the sink should keep running.
+    fn handle(
+        sink: &mut SinkType,
+        paused: &mut bool,
+        status: &StatusBoard,
+        event: Result<Lifecycle, RecvError>,
+    ) -> bool {
+        let this = TypeId::of::<SinkType>();
+        match event {
+            Ok(Pause(type_id)) if type_id == this => {
+                *paused = true;
+                status.set(&Self::status_name(), NodeKind::Sink, NodeState::Paused);
+            }
+            Ok(Resume(type_id)) if type_id == this => {
+                *paused = false;
+                status.set(&Self::status_name(), NodeKind::Sink, NodeState::Started);
+            }
+            Ok(event) if event.is_this::<SinkType>() => sink.config_changed(),
+            Ok(ReloadConfig) => sink.config_changed(),
+            Ok(Stop) | Err(RecvError::Closed) => return false,
+            Ok(_) => {}
+            Err(RecvError::Lagged(amount)) => {
+                metrics::add(Metric::ChannelLagged, &[("channel", "lifecycle")], amount);
+                error!(
+                    "Lagged amount of {} in {} sink node.",
+                    amount,
+                    sink.sink_identifier()
+                );
+            }
+        }
+        true
+    }
+}
+
+#[async_trait]
+impl<SinkType, DataType> Node for SinkNode<SinkType, DataType>
+where
+    SinkType: Sink<DataType>,
+    DataType: EntityData + 'static,
+{
+    async fn run(self: Box<Self>, permit: OwnedSemaphorePermit) {
+        let Self {
+            inputs,
+            lifecycle_manager,
+            mut sink,
+            ..
+        } = *self;
+        let stop_manager = lifecycle_manager.clone();
+        let name = Self::status_name();
+        let status = lifecycle_manager.status().clone();
+        status.set(&name, NodeKind::Sink, NodeState::Started);
+        let task_status = status.clone();
+        let task_name = name.clone();
+        let receivers: Vec<_> = inputs
+            .iter()
+            .map(|(source, input)| (*source, input.clone(), input.get_receiver()))
+            .collect();
+
+        let task = task::spawn(async move {
+            let (status, name) = (task_status, task_name);
+            let (sender, mut receiver) = mpsc::channel(BATCH_SIZE);
+            let mut forwarders = JoinSet::new();
+            for (source, input, mut receiver) in receivers {
+                let sender = sender.clone();
+                forwarders.spawn(async move {
+                    loop {
+                        match receiver.recv().await {
+                            Ok(published) => {
+                                if sender.send((source, published)).await.is_err() {
+                                    break;
+                                }
+                            }
+                            Err(RecvError::Closed) => break,
+                            Err(RecvError::Lagged(amount)) => {
+                                metrics::add(Metric::ChannelLagged, &[("channel", source)], amount);
+                                error!(
+                                    "Lagged amount of {} from {} in {} sink node, {} in total.",
+                                    amount,
+                                    source,
+                                    SinkType::name(),
+                                    input.report_lagged(amount)
+                                );
+                            }
+                        }
+                    }
+                });
+            }
+            drop(sender);
+
+            let mut lifecycle_receiver = lifecycle_manager.get_receiver();
+            let mut paused = false;
+            // Once stopping, the sink writes whatever is left until its sources close.
+            let mut stopping = false;
+            // Paused sinks keep reading, so their sources never wait on them.
+            let mut pending = Vec::new();
+            let mut failed = HashSet::new();
+            loop {
+                select! {
+                    event = lifecycle_receiver.recv(), if !stopping => {
+                        stopping = !Self::handle(&mut sink, &mut paused, &status, event);
+                    }
+                    next = receiver.recv() => match next {
+                        Some(next) => pending.push(next),
+                        None => break,
+                    },
+                };
+                if paused && !stopping {
+                    continue;
+                }
+                while pending.len() < BATCH_SIZE {
+                    match receiver.try_recv() {
+                        Ok(next) => pending.push(next),
+                        Err(_) => break,
+                    }
+                }
+                Self::put(
+                    &mut sink,
+                    &status,
+                    &name,
+                    &mut failed,
+                    mem::take(&mut pending),
+                )
+                .await;
+            }
+            Self::put(&mut sink, &status, &name, &mut failed, pending).await;
+        });
+
+        let stop_task = stop_manager.abort_after_grace_on_stop(&task).await;
+
+        drop(permit);
+
+        let (_task_result, _stop_result) = join!(task, stop_task);
+        status.set(&name, NodeKind::Sink, NodeState::Stopped);
+    }
+}
+
+impl<SinkType, DataType> ConsumerNode for SinkNode<SinkType, DataType>
+where
+    SinkType: Sink<DataType>,
+    DataType: EntityData + 'static,
+{
+    fn data_type(&self) -> TypeId {
+        TypeId::of::<DataType>()
+    }
+
+    fn connect(
+        &mut self,
+        manager: &Manager,
+        translators: &TranslatorRegistry,
+        input: &EntityChannel,
+    ) -> Result<Option<Box<dyn Node>>, String> {
+        let (channel, node) = translators
+            .route(manager, input, TypeId::of::<DataType>())
+            .ok_or_else(|| {
+                format!(
+                    "No translator for entities from {} to {}",
+                    input.source(),
+                    SinkType::name()
+                )
+            })?;
+        let readonly = channel
+            .downcast::<DataType>()
+            .ok_or_else(|| format!("{} received the wrong entity data", SinkType::name()))?;
+        self.inputs.push((channel.source(), readonly));
+        Ok(node)
+    }
+}
+
+enum Batch<T> {
+    Put(&'static str, Vec<T>),
+    /// The end of a sync of the source.
+    Synced(&'static str, Acknowledgement),
+}
+
+/// Groups consecutive entities from the same source, keeping their order and where each sync
+/// ends.
+fn batch_by_source<DataType>(
+    pending: Vec<(&'static str, Published<DataType>)>,
+) -> Vec<Batch<Entity<DataType>>>
+where
+    DataType: EntityData,
+{
+    let mut batches = Vec::new();
+    for (source, published) in pending {
+        match (published, batches.last_mut()) {
+            (Published::Synced(acknowledgement), _) => {
+                batches.push(Batch::Synced(source, acknowledgement))
+            }
+            (Published::Entity(entity), Some(Batch::Put(last_source, entities)))
+                if *last_source == source =>
+            {
+                entities.push(entity)
+            }
+            (Published::Entity(entity), _) => batches.push(Batch::Put(source, vec![entity])),
+        }
+    }
+    batches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::channel_handle::{ChannelHandle, LagPolicy};
+    use crate::domain::config::tests::test_config;
+    use crate::domain::entity_user::EntityUser;
+    use crate::domain::module_state::NamedModule;
+    use crate::domain::mpsc_handle::one_shot;
+    use crate::domain::node::get_test_manager;
+    use crate::static_init::error::Error;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::sync::Semaphore;
+    use tokio::time::{sleep, timeout};
+
+    struct RecordingSink {
+        ids: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl NamedModule for RecordingSink {
+        fn name() -> &'static str {
+            "recording"
+        }
+    }
+
+    impl EntityUser for RecordingSink {
+        fn supported_entity_data() -> Vec<TypeId> {
+            vec![TypeId::of::<String>()]
+        }
+    }
+
+    #[async_trait]
+    impl Sink<String> for RecordingSink {
+        async fn put(&mut self, _source: &str, entities: &[Entity<String>]) -> Result<(), Error> {
+            if entities.iter().any(|entity| entity.data() == "unstorable") {
+                return Err(Error::Io("Could not store".to_string()));
+            }
+            let mut ids = self.ids.lock().unwrap();
+            ids.extend(entities.iter().map(|entity| entity.id().to_string()));
+            Ok(())
+        }
+
+        async fn flush(&mut self, source: &str) -> Result<(), Error> {
+            self.ids.lock().unwrap().push(format!("flushed {}", source));
+            Ok(())
+        }
+    }
+
+    /// Runs a recording sink reading from `source_handle` until it's listening for lifecycle
+    /// events, then initializes it.
+    async fn start(
+        manager: &mut Manager,
+        source_handle: &ChannelHandle<Published<String>>,
+        ids: &Arc<Mutex<Vec<String>>>,
+    ) -> task::JoinHandle<()> {
+        let mut node = SinkNode::new(manager, RecordingSink { ids: ids.clone() });
+        node.connect(
+            manager,
+            &TranslatorRegistry::new(),
+            &EntityChannel::new("test", &source_handle.read_only()),
+        )
+        .unwrap();
+        let semaphore = Arc::new(Semaphore::new(1));
+        let permit = semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("Could not acquire semaphore");
+        let run = task::spawn(Box::new(node).run(permit));
+        let _permit = semaphore
+            .acquire()
+            .await
+            .expect("Could not acquire semaphore");
+        let (sender, _receiver) = one_shot();
+        manager.send_init(sender).unwrap();
+        run
+    }
+
+    #[tokio::test]
+    async fn test_failed_puts_are_not_acknowledged() {
+        let config = test_config();
+        let mut manager = get_test_manager(&config);
+        let ids = Arc::new(Mutex::new(Vec::new()));
+        let mut source_handle = ChannelHandle::with_policy(4, LagPolicy::Block);
+        let run = start(&mut manager, &source_handle, &ids).await;
+
+        let mut acknowledged = Vec::new();
+        for data in ["unstorable", "stored"] {
+            let (acknowledgement, stored) = Acknowledgement::new();
+            source_handle
+                .send_or_wait(Published::Entity(Entity::new_now(data.to_string(), data)))
+                .await
+                .unwrap();
+            source_handle
+                .send_or_wait(Published::Synced(acknowledgement))
+                .await
+                .unwrap();
+            acknowledged.push(stored.await.unwrap());
+        }
+
+        assert_eq!(acknowledged, vec![false, true]);
+        drop(source_handle);
+        manager.send_stop().unwrap();
+        run.await.expect("Error while running the sink node");
+    }
+
+    #[tokio::test]
+    async fn test_paused_sinks_do_not_hold_up_their_sources() {
+        let config = test_config();
+        let mut manager = get_test_manager(&config);
+        let ids = Arc::new(Mutex::new(Vec::new()));
+        let mut source_handle = ChannelHandle::with_policy(1, LagPolicy::Block);
+        let run = start(&mut manager, &source_handle, &ids).await;
+        manager.send_pause::<RecordingSink>().unwrap();
+        sleep(Duration::from_millis(20)).await;
+
+        // Well past what the channels between the source and the sink can hold.
+        let mut sent: Vec<String> = (0..BATCH_SIZE * 2).map(|id| id.to_string()).collect();
+        for id in &sent {
+            timeout(
+                Duration::from_secs(1),
+                source_handle
+                    .send_or_wait(Published::Entity(Entity::new_now("data".to_string(), id))),
+            )
+            .await
+            .expect("The paused sink should keep reading")
+            .unwrap();
+        }
+        let (acknowledgement, stored) = Acknowledgement::new();
+        source_handle
+            .send_or_wait(Published::Synced(acknowledgement))
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(20)).await;
+        assert!(ids.lock().unwrap().is_empty());
+
+        manager.send_resume::<RecordingSink>().unwrap();
+        sleep(Duration::from_millis(20)).await;
+        sent.push("flushed test".to_string());
+        assert_eq!(*ids.lock().unwrap(), sent);
+        assert!(stored.await.unwrap());
+
+        drop(source_handle);
+        manager.send_stop().unwrap();
+        run.await.expect("Error while running the sink node");
+    }
+
+    mod batch_by_source {
+        use super::*;
+
+        fn published(id: &str) -> Published<String> {
+            Published::Entity(Entity::new_now(id.to_string(), id))
+        }
+
+        #[test]
+        fn test_consecutive_entities_are_batched() {
+            let pending = vec![
+                ("a", published("1")),
+                ("a", published("2")),
+                ("b", published("3")),
+                ("a", published("4")),
+            ];
+
+            let batches = batch_by_source(pending);
+
+            assert_eq!(
+                batches.iter().map(batch_ids).collect::<Vec<_>>(),
+                vec![("a", vec!["1", "2"]), ("b", vec!["3"]), ("a", vec!["4"])]
+            );
+        }
+
+        #[test]
+        fn test_the_end_of_a_sync_splits_batches() {
+            let pending = vec![
+                ("a", published("1")),
+                ("a", Published::Synced(Acknowledgement::new().0)),
+                ("a", published("2")),
+            ];
+
+            let batches = batch_by_source(pending);
+
+            assert_eq!(batches.len(), 3);
+            assert!(matches!(batches[1], Batch::Synced("a", _)));
+            assert_eq!(batch_ids(&batches[2]), ("a", vec!["2"]));
+        }
+
+        fn batch_ids(batch: &Batch<Entity<String>>) -> (&'static str, Vec<&str>) {
+            match batch {
+                Batch::Put(source, entities) => {
+                    (*source, entities.iter().map(Entity::id).collect())
+                }
+                Batch::Synced(source, _) => (*source, vec![]),
+            }
+        }
+    }
+}
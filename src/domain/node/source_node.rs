@@ -0,0 +1,814 @@
+use crate::core::metrics;
+use crate::core::metrics::Metric;
+use crate::core::module::State;
+use crate::domain::channel_handle::{ChannelHandle, LagPolicy, Readonly};
+use crate::domain::entity_channel::EntityChannel;
+use crate::domain::entity_data::EntityData;
+use crate::domain::module_state::ModuleState;
+use crate::domain::node::spill::Spill;
+use crate::domain::node::Lifecycle::{Init, Pause, ReloadConfig, Resume, Stop};
+use crate::domain::node::{
+    Lifecycle, LifecycleAware, Manager, Node, NodeKind, NodeState, ProducerNode, ReadonlyManager,
+    Schedule, StatusBoard,
+};
+use crate::domain::published::{Acknowledgement, Published};
+use crate::domain::source::Source;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::error;
+use std::any::TypeId;
+use std::future::Future;
+use std::io::ErrorKind;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+use tokio::fs;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::OwnedSemaphorePermit;
+use tokio::time::sleep;
+use tokio::{join, select, task};
+
+/// Runs a [Source], publishing everything it gets on a channel that sinks can subscribe to.
+pub(crate) struct SourceNode<SourceType, DataType>
+where
+    SourceType: Source<DataType>,
+    DataType: EntityData,
+{
+    channel_handle: ChannelHandle<Published<DataType>>,
+    lifecycle_manager: ReadonlyManager,
+    /// How often the source syncs when its config doesn't say.
+    poll_interval: Duration,
+    schedule: Schedule,
+    source: SourceType,
+    phantom: PhantomData<DataType>,
+}
+
+const SINCE_FILE: &str = "since.yaml";
+const SPILL_FILE: &str = "spill.yaml";
+
+/// Whether the sinks stored everything up to the end of the latest sync, and when that sync
+/// started if it's known.
+type Acknowledged = Pin<Box<dyn Future<Output = (bool, Option<DateTime<Utc>>)> + Send>>;
+
+enum Wake {
+    Continue,
+    /// Work out again when the next sync is due.
+    Reschedule,
+    Stop,
+}
+
+impl<SourceType, DataType> SourceNode<SourceType, DataType>
+where
+    SourceType: Source<DataType>,
+    DataType: EntityData + 'static,
+{
+    pub(crate) fn new(manager: &Manager, source: SourceType, poll_interval: Duration) -> Self {
+        debug_assert!(
+            SourceType::supported_entity_data().contains(&TypeId::of::<DataType>()),
+            "{} does not declare the entity data it produces",
+            SourceType::name()
+        );
+        Self {
+            channel_handle: ChannelHandle::new(),
+            lifecycle_manager: manager.readonly(),
+            poll_interval,
+            schedule: Schedule::every(poll_interval),
+            source,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Publishes on a channel with the given capacity and lag policy.
+    pub(crate) fn with_channel(mut self, capacity: usize, policy: LagPolicy) -> Self {
+        self.channel_handle = ChannelHandle::with_policy(capacity, policy);
+        self
+    }
+
+    pub(crate) fn with_schedule(mut self, schedule: Schedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    pub(crate) fn get_readonly_channel_handle(&self) -> Readonly<Published<DataType>> {
+        self.channel_handle.read_only()
+    }
+
+    async fn get(
+        source: &mut SourceType,
+        channel_handle: &mut ChannelHandle<Published<DataType>>,
+        spill: &mut Option<Spill<DataType>>,
+        unacknowledged: &mut Option<Acknowledged>,
+        status: &StatusBoard,
+        since: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        let name = source.source_identifier();
+        let started_at = Utc::now();
+        status.set(name, NodeKind::Source, NodeState::Syncing);
+        if let Some(spill) = spill.as_mut() {
+            spill.drain(channel_handle);
+        }
+
+        let timer = Instant::now();
+        let result = source.get(since).await;
+        metrics::set(
+            Metric::SyncDurationSeconds,
+            &[("source", name)],
+            timer.elapsed().as_secs_f64(),
+        );
+
+        let synced_at = match result {
+            Ok(entities) => {
+                metrics::add(
+                    Metric::EntitiesEmitted,
+                    &[("source", name)],
+                    entities.len() as u64,
+                );
+                for entity in entities {
+                    if let Some(spill) = spill.as_mut() {
+                        // Once anything has spilled, the rest follows to keep the order.
+                        if channel_handle.is_full() || !spill.is_empty() {
+                            spill.push(entity);
+                            continue;
+                        }
+                    }
+                    Self::publish(name, channel_handle, Published::Entity(entity)).await;
+                }
+                // Spilled entities go first, so the end of the sync is published after them.
+                if spill.as_ref().is_none_or(Spill::is_empty) {
+                    Self::publish_synced(name, channel_handle, unacknowledged, Some(started_at))
+                        .await;
+                }
+                if source.is_waiting_for_authorization() {
+                    status.set(name, NodeKind::Source, NodeState::WaitingForOauth);
+                } else {
+                    status.set(name, NodeKind::Source, NodeState::Started);
+                    status.synced(name, NodeKind::Source, started_at);
+                }
+                Some(started_at)
+            }
+            Err(e) => {
+                error!(
+                    "Error while getting entities from {}: {}",
+                    source.source_identifier(),
+                    e
+                );
+                status.errored(name, NodeKind::Source, &e.to_string());
+                None
+            }
+        };
+
+        if let Some(spill) = spill {
+            Self::save_spill(source.source_identifier(), spill).await;
+        }
+        synced_at
+    }
+
+    /// Publishes what it can of the spill between syncs, then the end of the sync it held up,
+    /// which started at `since`.
+    ///
+    /// Returns whether more can go once there's room.
+    async fn drain_spill(
+        source_identifier: &str,
+        channel_handle: &mut ChannelHandle<Published<DataType>>,
+        spill: &mut Spill<DataType>,
+        unacknowledged: &mut Option<Acknowledged>,
+        since: Option<DateTime<Utc>>,
+    ) -> bool {
+        // Receivers can make room while the spill is saved, so what's left is decided first.
+        let waiting_for_room = spill.drain(channel_handle);
+        Self::save_spill(source_identifier, spill).await;
+        if spill.is_empty() {
+            Self::publish_synced(source_identifier, channel_handle, unacknowledged, since).await;
+        }
+        waiting_for_room
+    }
+
+    /// Publishes the end of a sync, then waits on the sinks' answer after any earlier answers
+    /// still to come, so it only says stored if they stored everything.
+    async fn publish_synced(
+        source_identifier: &str,
+        channel_handle: &mut ChannelHandle<Published<DataType>>,
+        unacknowledged: &mut Option<Acknowledged>,
+        started_at: Option<DateTime<Utc>>,
+    ) {
+        let (acknowledgement, stored) = Acknowledgement::new();
+        Self::publish(
+            source_identifier,
+            channel_handle,
+            Published::Synced(acknowledgement),
+        )
+        .await;
+        let earlier = unacknowledged.take();
+        *unacknowledged = Some(Box::pin(async move {
+            let earlier_stored = match earlier {
+                Some(earlier) => earlier.await.0,
+                None => true,
+            };
+            (earlier_stored && stored.await.unwrap_or(false), started_at)
+        }));
+    }
+
+    async fn publish(
+        source_identifier: &str,
+        channel_handle: &mut ChannelHandle<Published<DataType>>,
+        published: Published<DataType>,
+    ) {
+        if let Err(e) = channel_handle.send_or_wait(published).await {
+            error!("Error while sending from {}: {}", source_identifier, e);
+        }
+    }
+
+    async fn save_spill(source_identifier: &str, spill: &Spill<DataType>) {
+        if let Err(e) = spill.save().await {
+            error!(
+                "Error while saving spilled entities from {}: {}",
+                source_identifier, e
+            );
+        }
+    }
+
+    fn config_changed(source: &mut SourceType) -> Wake {
+        source.config_changed();
+        Wake::Reschedule
+    }
+
+    /// The schedule in the reloaded config, or the current one if the new one is invalid.
+    fn reload_schedule(
+        manager: &ReadonlyManager,
+        poll_interval: Duration,
+        schedule: Schedule,
+    ) -> Schedule {
+        let core_config = manager.core_config();
+        match Schedule::from_config(core_config.source(SourceType::name()), poll_interval) {
+            Ok(reloaded) => reloaded,
+            Err(e) => {
+                error!("Keeping the schedule of {}: {}", SourceType::name(), e);
+                schedule
+            }
+        }
+    }
+
+    async fn state_path(source_identifier: &str, file: &str) -> Option<PathBuf> {
+        match State::path_for::<SourceType>().await {
+            Ok(root) => Some(PathBuf::from(root).join(file)),
+            Err(e) => {
+                error!(
+                    "Could not create the state folder for {}: {}",
+                    source_identifier, e
+                );
+                None
+            }
+        }
+    }
+
+    /// When the sync the sinks last stored everything from started.
+    async fn load_since(source_identifier: &str) -> Option<DateTime<Utc>> {
+        let path = Self::state_path(source_identifier, SINCE_FILE).await?;
+        match fs::read_to_string(&path).await {
+            Ok(yaml) => match serde_yaml::from_str(&yaml) {
+                Ok(since) => Some(since),
+                Err(e) => {
+                    error!(
+                        "Could not read when {} last synced: {}",
+                        source_identifier, e
+                    );
+                    None
+                }
+            },
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => {
+                error!(
+                    "Could not read when {} last synced: {}",
+                    source_identifier, e
+                );
+                None
+            }
+        }
+    }
+
+    async fn save_since(source_identifier: &str, since: DateTime<Utc>) {
+        let Some(path) = Self::state_path(source_identifier, SINCE_FILE).await else {
+            return;
+        };
+        let result = match serde_yaml::to_string(&since) {
+            Ok(yaml) => fs::write(&path, yaml).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = result {
+            error!(
+                "Could not save when {} last synced: {}",
+                source_identifier, e
+            );
+        }
+    }
+
+    /// Remembers the sync the sinks stored everything from, or goes back to it if they didn't
+    /// store the ones after.
+    async fn acknowledged(
+        source: &mut SourceType,
+        (stored, started_at): (bool, Option<DateTime<Utc>>),
+        since: &mut Option<DateTime<Utc>>,
+        stored_since: &mut Option<DateTime<Utc>>,
+    ) {
+        if !stored {
+            *since = *stored_since;
+        } else if let Some(started_at) = started_at {
+            if *stored_since != Some(started_at) {
+                Self::save_since(source.source_identifier(), started_at).await;
+                *stored_since = Some(started_at);
+            }
+        }
+        source.acknowledged(stored).await;
+    }
+
+    async fn load_spill(source_identifier: &str) -> Option<Spill<DataType>> {
+        let path = Self::state_path(source_identifier, SPILL_FILE).await?;
+        match Spill::load(path).await {
+            Ok(spill) => Some(spill),
+            Err(e) => {
+                error!(
+                    "Could not load spilled entities for {}: {}",
+                    source_identifier, e
+                );
+                None
+            }
+        }
+    }
+
+    fn handle(
+        source: &mut SourceType,
+        paused: &mut bool,
+        status: &StatusBoard,
+        event: Result<Lifecycle, RecvError>,
+    ) -> Wake {
+        let this = TypeId::of::<SourceType>();
+        let name = source.source_identifier();
+        match event {
+            Ok(Init(_)) => Wake::Reschedule,
+            Ok(Stop) | Err(RecvError::Closed) => Wake::Stop,
+            Ok(Pause(type_id)) if type_id == this => {
+                *paused = true;
+                status.set(name, NodeKind::Source, NodeState::Paused);
+                Wake::Continue
+            }
+            Ok(Resume(type_id)) if type_id == this && *paused => {
+                *paused = false;
+                status.set(name, NodeKind::Source, NodeState::Started);
+                Wake::Reschedule
+            }
+            Ok(event) if event.is_this::<SourceType>() => Self::config_changed(source),
+            Ok(ReloadConfig) => Self::config_changed(source),
+            Ok(_) => Wake::Continue,
+            Err(RecvError::Lagged(amount)) => {
+                metrics::add(Metric::ChannelLagged, &[("channel", "lifecycle")], amount);
+                error!(
+                    "Lagged amount of {} in {} source node.",
+                    amount,
+                    source.source_identifier()
+                );
+                Wake::Continue
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<SourceType, DataType> Node for SourceNode<SourceType, DataType>
+where
+    SourceType: Source<DataType>,
+    DataType: EntityData + 'static,
+{
+    async fn run(self: Box<Self>, permit: OwnedSemaphorePermit) {
+        let Self {
+            mut channel_handle,
+            lifecycle_manager,
+            poll_interval,
+            mut schedule,
+            mut source,
+            ..
+        } = *self;
+        let mut lifecycle_receiver = lifecycle_manager.get_receiver();
+        let name = source.source_identifier().to_string();
+        let status = lifecycle_manager.status().clone();
+        status.set(&name, NodeKind::Source, NodeState::Started);
+
+        let task_status = status.clone();
+        let one_shot = lifecycle_manager.is_one_shot();
+        let task_manager = lifecycle_manager.clone();
+        let task = task::spawn(async move {
+            let status = task_status;
+            let mut spill = match channel_handle.policy() {
+                LagPolicy::Spill => {
+                    SourceNode::<SourceType, DataType>::load_spill(source.source_identifier()).await
+                }
+                _ => None,
+            };
+
+            let mut paused = false;
+            loop {
+                match SourceNode::<SourceType, DataType>::handle(
+                    &mut source,
+                    &mut paused,
+                    &status,
+                    lifecycle_receiver.recv().await,
+                ) {
+                    Wake::Continue => {}
+                    Wake::Reschedule => break,
+                    Wake::Stop => return,
+                }
+            }
+
+            let mut stored_since =
+                SourceNode::<SourceType, DataType>::load_since(source.source_identifier()).await;
+            let mut since = stored_since;
+            let mut unacknowledged = None;
+            // Running once syncs straight away, whatever the schedule.
+            let mut delay = if one_shot {
+                Duration::ZERO
+            } else {
+                schedule.delay_after(since)
+            };
+            loop {
+                let timeout = sleep(delay);
+                tokio::pin!(timeout);
+                let mut draining = spill.as_ref().is_some_and(|spill| !spill.is_empty());
+                loop {
+                    select! {
+                        _ = &mut timeout => break,
+                        _ = channel_handle.wait_for_room(), if draining => {
+                            if let Some(spill) = spill.as_mut() {
+                                draining = SourceNode::<SourceType, DataType>::drain_spill(
+                                    source.source_identifier(),
+                                    &mut channel_handle,
+                                    spill,
+                                    &mut unacknowledged,
+                                    since,
+                                )
+                                .await;
+                            }
+                        }
+                        acknowledged = async {
+                            unacknowledged.as_mut().expect("Only waited on when some").await
+                        }, if unacknowledged.is_some() => {
+                            unacknowledged = None;
+                            SourceNode::<SourceType, DataType>::acknowledged(
+                                &mut source,
+                                acknowledged,
+                                &mut since,
+                                &mut stored_since,
+                            )
+                            .await;
+                        }
+                        event = lifecycle_receiver.recv() => {
+                            if let Ok(ReloadConfig) = event {
+                                schedule = SourceNode::<SourceType, DataType>::reload_schedule(
+                                    &task_manager,
+                                    poll_interval,
+                                    schedule,
+                                );
+                            }
+                            match SourceNode::<SourceType, DataType>::handle(
+                                &mut source,
+                                &mut paused,
+                                &status,
+                                event,
+                            ) {
+                                Wake::Continue => {}
+                                Wake::Reschedule => timeout.set(sleep(schedule.delay_after(since))),
+                                Wake::Stop => return,
+                            }
+                        }
+                    }
+                }
+
+                if !paused {
+                    if let Some(synced_at) = SourceNode::<SourceType, DataType>::get(
+                        &mut source,
+                        &mut channel_handle,
+                        &mut spill,
+                        &mut unacknowledged,
+                        &status,
+                        since.unwrap_or(DateTime::<Utc>::MIN_UTC),
+                    )
+                    .await
+                    {
+                        since = Some(synced_at);
+                    }
+                }
+
+                if one_shot {
+                    if let Some(unacknowledged) = unacknowledged.take() {
+                        SourceNode::<SourceType, DataType>::acknowledged(
+                            &mut source,
+                            unacknowledged.await,
+                            &mut since,
+                            &mut stored_since,
+                        )
+                        .await;
+                    }
+                    let name = source.source_identifier();
+                    if source.is_waiting_for_authorization() {
+                        status.errored(name, NodeKind::Source, "Not authorized");
+                    }
+                    // Dropping the channel lets the sinks finish what this source sent.
+                    status.set(name, NodeKind::Source, NodeState::Stopped);
+                    return;
+                }
+                delay = schedule.delay();
+            }
+        });
+
+        let stop_task = lifecycle_manager.abort_after_grace_on_stop(&task).await;
+
+        drop(permit);
+
+        let (_task_result, _stop_result) = join!(task, stop_task);
+        status.set(&name, NodeKind::Source, NodeState::Stopped);
+    }
+}
+
+impl<SourceType, DataType> ProducerNode for SourceNode<SourceType, DataType>
+where
+    SourceType: Source<DataType>,
+    DataType: EntityData + 'static,
+{
+    fn entity_channel(&self) -> EntityChannel {
+        EntityChannel::new(
+            self.source.source_identifier(),
+            &self.get_readonly_channel_handle(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::config::tests::test_config;
+    use crate::domain::entity::Entity;
+    use crate::domain::entity_user::EntityUser;
+    use crate::domain::module_state::NamedModule;
+    use crate::domain::mpsc_handle::one_shot;
+    use crate::domain::node::get_test_manager;
+    use crate::static_init::error::Error;
+    use std::marker::PhantomData;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::Semaphore;
+    use tokio::time::timeout;
+
+    struct CountingSource {
+        gets: Arc<AtomicUsize>,
+    }
+
+    impl NamedModule for CountingSource {
+        fn name() -> &'static str {
+            "counting"
+        }
+    }
+
+    impl EntityUser for CountingSource {
+        fn supported_entity_data() -> Vec<TypeId> {
+            vec![TypeId::of::<String>()]
+        }
+    }
+
+    #[async_trait]
+    impl Source<String> for CountingSource {
+        async fn get(&mut self, _since: DateTime<Utc>) -> Result<Vec<Entity<String>>, Error> {
+            self.gets.fetch_add(1, Ordering::SeqCst);
+            Ok(vec![])
+        }
+    }
+
+    /// Gets entities numbered from one the first time, and nothing after that.
+    ///
+    /// Named after `Name`, so tests running side by side keep their state apart.
+    struct BurstSource<Name> {
+        acknowledged: Arc<Mutex<Vec<bool>>>,
+        remaining: usize,
+        name: PhantomData<Name>,
+    }
+
+    impl<Name> BurstSource<Name> {
+        fn new(remaining: usize) -> Self {
+            Self {
+                acknowledged: Arc::new(Mutex::new(Vec::new())),
+                remaining,
+                name: PhantomData,
+            }
+        }
+    }
+
+    impl<Name> NamedModule for BurstSource<Name>
+    where
+        Name: NamedModule,
+    {
+        fn name() -> &'static str {
+            Name::name()
+        }
+    }
+
+    impl<Name> EntityUser for BurstSource<Name> {
+        fn supported_entity_data() -> Vec<TypeId> {
+            vec![TypeId::of::<String>()]
+        }
+    }
+
+    struct SpilledName;
+
+    impl NamedModule for SpilledName {
+        fn name() -> &'static str {
+            "spilled"
+        }
+    }
+
+    struct AcknowledgedName;
+
+    impl NamedModule for AcknowledgedName {
+        fn name() -> &'static str {
+            "acknowledged"
+        }
+    }
+
+    struct RestartedName;
+
+    impl NamedModule for RestartedName {
+        fn name() -> &'static str {
+            "restarted"
+        }
+    }
+
+    async fn remove_state<SourceType>()
+    where
+        SourceType: NamedModule,
+    {
+        let _ = tokio::fs::remove_dir_all(State::path_for::<SourceType>().await.unwrap()).await;
+    }
+
+    #[async_trait]
+    impl<Name> Source<String> for BurstSource<Name>
+    where
+        Name: NamedModule + Send + Sync + 'static,
+    {
+        async fn get(&mut self, _since: DateTime<Utc>) -> Result<Vec<Entity<String>>, Error> {
+            let entities = (1..=self.remaining)
+                .map(|id| Entity::new_now("burst".to_string(), &id.to_string()))
+                .collect();
+            self.remaining = 0;
+            Ok(entities)
+        }
+
+        async fn acknowledged(&mut self, stored: bool) {
+            self.acknowledged.lock().unwrap().push(stored);
+        }
+    }
+
+    /// Runs the node until it's listening for lifecycle events, then initializes it.
+    async fn start<SourceType>(
+        manager: &mut Manager,
+        node: SourceNode<SourceType, String>,
+    ) -> task::JoinHandle<()>
+    where
+        SourceType: Source<String>,
+    {
+        let semaphore = Arc::new(Semaphore::new(1));
+        let permit = semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("Could not acquire semaphore");
+        let run = task::spawn(Box::new(node).run(permit));
+        let _permit = semaphore
+            .acquire()
+            .await
+            .expect("Could not acquire semaphore");
+        let (sender, _receiver) = one_shot();
+        manager.send_init(sender).unwrap();
+        run
+    }
+
+    #[tokio::test]
+    async fn test_pause_and_resume() {
+        let config = test_config();
+        let mut manager = get_test_manager(&config);
+        let gets = Arc::new(AtomicUsize::new(0));
+        let node = SourceNode::new(
+            &manager,
+            CountingSource { gets: gets.clone() },
+            Duration::from_millis(10),
+        );
+        let run = start(&mut manager, node).await;
+        sleep(Duration::from_millis(50)).await;
+        assert!(gets.load(Ordering::SeqCst) > 0);
+
+        manager.send_pause::<CountingSource>().unwrap();
+        sleep(Duration::from_millis(20)).await;
+        let paused_at = gets.load(Ordering::SeqCst);
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(gets.load(Ordering::SeqCst), paused_at);
+
+        manager.send_resume::<CountingSource>().unwrap();
+        sleep(Duration::from_millis(50)).await;
+        assert!(gets.load(Ordering::SeqCst) > paused_at);
+
+        manager.send_stop().unwrap();
+        run.await.expect("Error while running the source node");
+    }
+
+    #[tokio::test]
+    async fn test_spill_drains_between_syncs() {
+        let config = test_config();
+        let mut manager = get_test_manager(&config);
+        let node = SourceNode::new(
+            &manager,
+            BurstSource::<SpilledName>::new(3),
+            Duration::from_secs(3600),
+        )
+        .with_channel(1, LagPolicy::Spill);
+        let mut receiver = node.get_readonly_channel_handle().get_receiver();
+        let run = start(&mut manager, node).await;
+
+        let mut published = Vec::new();
+        for _ in 0..4 {
+            published.push(
+                timeout(Duration::from_secs(1), receiver.recv())
+                    .await
+                    .expect("Spilled entities should be sent before the next sync")
+                    .unwrap(),
+            );
+        }
+        let ids: Vec<_> = published
+            .iter()
+            .map(|published| match published {
+                Published::Entity(entity) => entity.id(),
+                Published::Synced(_) => "synced",
+            })
+            .collect();
+        assert_eq!(ids, vec!["1", "2", "3", "synced"]);
+
+        manager.send_stop().unwrap();
+        run.await.expect("Error while running the source node");
+        remove_state::<BurstSource<SpilledName>>().await;
+    }
+
+    #[tokio::test]
+    async fn test_acknowledges_what_the_sinks_say_about_a_sync() {
+        for stored in [true, false] {
+            let config = test_config();
+            let mut manager = get_test_manager(&config);
+            let source = BurstSource::<AcknowledgedName>::new(1);
+            let acknowledged = source.acknowledged.clone();
+            let node = SourceNode::new(&manager, source, Duration::from_secs(3600));
+            let mut receiver = node.get_readonly_channel_handle().get_receiver();
+            let run = start(&mut manager, node).await;
+
+            assert!(receiver.recv().await.unwrap().entity().is_some());
+            let Published::Synced(acknowledgement) = receiver.recv().await.unwrap() else {
+                panic!("The sync should end after its entity");
+            };
+            sleep(Duration::from_millis(20)).await;
+            assert!(acknowledged.lock().unwrap().is_empty());
+
+            acknowledgement.acknowledge(stored);
+            sleep(Duration::from_millis(20)).await;
+            assert_eq!(*acknowledged.lock().unwrap(), vec![stored]);
+
+            manager.send_stop().unwrap();
+            run.await.expect("Error while running the source node");
+            remove_state::<BurstSource<AcknowledgedName>>().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_waits_for_the_schedule_after_a_stored_sync_when_restarted() {
+        let config = test_config();
+        remove_state::<BurstSource<RestartedName>>().await;
+        // Each start syncs unless the sinks stored the sync before it.
+        for (syncs, stored) in [(true, false), (true, true), (false, true)] {
+            let mut manager = get_test_manager(&config);
+            let node = SourceNode::new(
+                &manager,
+                BurstSource::<RestartedName>::new(1),
+                Duration::from_secs(3600),
+            );
+            let mut receiver = node.get_readonly_channel_handle().get_receiver();
+            let run = start(&mut manager, node).await;
+
+            let synced = timeout(Duration::from_millis(100), receiver.recv()).await;
+            assert_eq!(synced.is_ok(), syncs);
+            if let Ok(published) = synced {
+                assert!(published.unwrap().entity().is_some());
+                let Published::Synced(acknowledgement) = receiver.recv().await.unwrap() else {
+                    panic!("The sync should end after its entity");
+                };
+                acknowledgement.acknowledge(stored);
+                sleep(Duration::from_millis(20)).await;
+            }
+
+            manager.send_stop().unwrap();
+            run.await.expect("Error while running the source node");
+        }
+        remove_state::<BurstSource<RestartedName>>().await;
+    }
+}
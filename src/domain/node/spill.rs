@@ -0,0 +1,139 @@
+use crate::domain::channel_handle::ChannelHandle;
+use crate::domain::entity::Entity;
+use crate::domain::entity_data::EntityData;
+use crate::domain::published::Published;
+use crate::static_init::error::{Error, IoErrorExt, SerdeErrorExt};
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use tokio::fs;
+use tokio::sync::broadcast::error::SendError;
+
+/// Entities a source could not publish because its channel was full, kept on disk until there
+/// is room.
+pub(crate) struct Spill<DataType>
+where
+    DataType: EntityData,
+{
+    entities: VecDeque<Entity<DataType>>,
+    path: PathBuf,
+}
+
+impl<DataType> Spill<DataType>
+where
+    DataType: EntityData,
+{
+    pub(crate) async fn load(path: PathBuf) -> Result<Self, Error> {
+        let entities = match fs::read_to_string(&path).await {
+            Ok(yaml) => serde_yaml::from_str(&yaml).map_err(|e| e.to_yaml_serialization_error())?,
+            Err(e) if e.kind() == ErrorKind::NotFound => VecDeque::new(),
+            Err(e) => return Err(e.to_error()),
+        };
+        Ok(Self { entities, path })
+    }
+
+    /// Publishes spilled entities, oldest first, until the channel is full or nobody is
+    /// listening.
+    ///
+    /// Returns whether any are left for once there's room.
+    pub(crate) fn drain(
+        &mut self,
+        channel_handle: &mut ChannelHandle<Published<DataType>>,
+    ) -> bool {
+        while !channel_handle.is_full() {
+            let Some(entity) = self.entities.pop_front() else {
+                return false;
+            };
+            if let Err(SendError(published)) = channel_handle.send(Published::Entity(entity)) {
+                if let Some(entity) = published.entity() {
+                    self.entities.push_front(entity);
+                }
+                return false;
+            }
+        }
+        !self.entities.is_empty()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub(crate) fn push(&mut self, entity: Entity<DataType>) {
+        self.entities.push_back(entity);
+    }
+
+    pub(crate) async fn save(&self) -> Result<(), Error> {
+        if self.entities.is_empty() {
+            return match fs::remove_file(&self.path).await {
+                Err(e) if e.kind() != ErrorKind::NotFound => Err(e.to_error()),
+                _ => Ok(()),
+            };
+        }
+
+        let yaml =
+            serde_yaml::to_string(&self.entities).map_err(|e| e.to_yaml_serialization_error())?;
+        fs::write(&self.path, yaml).await.map_err(|e| e.to_error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::channel_handle::LagPolicy;
+    use std::env::temp_dir;
+    use uuid::Uuid;
+
+    fn test_path() -> PathBuf {
+        temp_dir().join(format!("spill-{}.yaml", Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn test_survives_reloading() {
+        let path = test_path();
+        let mut spill = Spill::load(path.clone()).await.unwrap();
+        assert!(spill.is_empty());
+        let entity = Entity::new_now("spilled".to_string(), "1");
+
+        spill.push(entity.clone());
+        spill.save().await.unwrap();
+        let mut reloaded = Spill::<String>::load(path.clone()).await.unwrap();
+
+        assert_eq!(reloaded.entities, vec![entity]);
+
+        reloaded.entities.clear();
+        reloaded.save().await.unwrap();
+        assert!(!fs::try_exists(&path).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_drain_stops_when_full() {
+        let mut spill = Spill::load(test_path()).await.unwrap();
+        for id in ["1", "2", "3"] {
+            spill.push(Entity::new_now("spilled".to_string(), id));
+        }
+        let mut channel_handle = ChannelHandle::with_policy(2, LagPolicy::Spill);
+        let mut receiver = channel_handle.get_receiver();
+
+        assert!(spill.drain(&mut channel_handle));
+
+        assert_eq!(spill.entities.len(), 1);
+        assert_eq!(receiver.recv().await.unwrap().entity().unwrap().id(), "1");
+        assert_eq!(receiver.recv().await.unwrap().entity().unwrap().id(), "2");
+
+        assert!(!spill.drain(&mut channel_handle));
+
+        assert!(spill.is_empty());
+        assert_eq!(receiver.recv().await.unwrap().entity().unwrap().id(), "3");
+    }
+
+    #[tokio::test]
+    async fn test_drain_keeps_what_nobody_received() {
+        let mut spill = Spill::load(test_path()).await.unwrap();
+        spill.push(Entity::new_now("spilled".to_string(), "1"));
+        let mut channel_handle = ChannelHandle::with_policy(2, LagPolicy::Spill);
+
+        assert!(!spill.drain(&mut channel_handle));
+
+        assert_eq!(spill.entities.len(), 1);
+    }
+}
@@ -0,0 +1,191 @@
+use chrono::{DateTime, Utc};
+use derive_getters::Getters;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeState {
+    /// Running, and not in the middle of a sync.
+    Started,
+    WaitingForOauth,
+    Syncing,
+    Errored,
+    Paused,
+    Stopped,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    Sink,
+    Source,
+}
+
+#[derive(Clone, Debug, Getters, PartialEq, Serialize)]
+pub struct NodeStatus {
+    /// The last error, while the node is errored.
+    error: Option<String>,
+    kind: NodeKind,
+    /// When the node last synced successfully, for sources.
+    last_sync: Option<DateTime<Utc>>,
+    state: NodeState,
+}
+
+/// What each source and sink is doing, shared by everything holding the [Manager].
+///
+/// [Manager]: crate::domain::node::Manager
+#[derive(Clone, Debug)]
+pub struct StatusBoard {
+    changed: Arc<Notify>,
+    /// The last error from each node that has errored, kept after the node recovers.
+    errors: Arc<Mutex<BTreeMap<String, String>>>,
+    nodes: Arc<Mutex<BTreeMap<String, NodeStatus>>>,
+    started_at: DateTime<Utc>,
+}
+
+impl StatusBoard {
+    pub(crate) fn new() -> Self {
+        Self {
+            changed: Arc::new(Notify::new()),
+            errors: Arc::new(Mutex::new(BTreeMap::new())),
+            nodes: Arc::new(Mutex::new(BTreeMap::new())),
+            started_at: Utc::now(),
+        }
+    }
+
+    pub fn errored(&self, node: &str, kind: NodeKind, error: &str) {
+        self.errors
+            .lock()
+            .insert(node.to_string(), error.to_string());
+        self.update(node, kind, |status| {
+            status.error = Some(error.to_string());
+            status.state = NodeState::Errored;
+        });
+    }
+
+    pub fn errors(&self) -> BTreeMap<String, String> {
+        self.errors.lock().clone()
+    }
+
+    pub fn nodes(&self) -> BTreeMap<String, NodeStatus> {
+        self.nodes.lock().clone()
+    }
+
+    pub fn set(&self, node: &str, kind: NodeKind, state: NodeState) {
+        self.update(node, kind, |status| {
+            status.error = None;
+            status.state = state;
+        });
+    }
+
+    pub fn started_at(&self) -> DateTime<Utc> {
+        self.started_at
+    }
+
+    pub fn synced(&self, node: &str, kind: NodeKind, at: DateTime<Utc>) {
+        self.update(node, kind, |status| status.last_sync = Some(at));
+    }
+
+    /// Resolves once `done` holds for the nodes, checking again whenever one of them changes.
+    pub async fn wait_until(&self, done: impl Fn(&BTreeMap<String, NodeStatus>) -> bool) {
+        loop {
+            let changed = self.changed.notified();
+            if done(&self.nodes.lock()) {
+                return;
+            }
+            changed.await;
+        }
+    }
+
+    fn update(&self, node: &str, kind: NodeKind, change: impl FnOnce(&mut NodeStatus)) {
+        {
+            let mut nodes = self.nodes.lock();
+            let status = nodes.entry(node.to_string()).or_insert(NodeStatus {
+                error: None,
+                kind,
+                last_sync: None,
+                state: NodeState::Started,
+            });
+            change(status);
+        }
+        self.changed.notify_waiters();
+    }
+}
+
+/// The last segment of a type's path, to tell apart sinks registered for several entity types.
+pub(crate) fn short_type_name<T>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors_clear_on_the_next_state() {
+        let board = StatusBoard::new();
+
+        board.errored("google", NodeKind::Source, "Offline");
+        assert_eq!(
+            board.nodes()["google"].error(),
+            &Some("Offline".to_string())
+        );
+        assert_eq!(board.nodes()["google"].state(), &NodeState::Errored);
+
+        board.set("google", NodeKind::Source, NodeState::Syncing);
+        assert_eq!(board.nodes()["google"].error(), &None);
+        assert_eq!(board.nodes()["google"].state(), &NodeState::Syncing);
+    }
+
+    #[test]
+    fn test_errors_are_kept_after_recovery() {
+        let board = StatusBoard::new();
+
+        board.errored("google", NodeKind::Source, "Offline");
+        board.set("google", NodeKind::Source, NodeState::Stopped);
+
+        assert_eq!(board.errors()["google"], "Offline");
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_wakes_on_a_change() {
+        let board = StatusBoard::new();
+        board.set("google", NodeKind::Source, NodeState::Syncing);
+        let stopped =
+            |nodes: &BTreeMap<String, NodeStatus>| nodes["google"].state() == &NodeState::Stopped;
+
+        let waiting = tokio::spawn({
+            let board = board.clone();
+            async move { board.wait_until(stopped).await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiting.is_finished());
+
+        board.set("google", NodeKind::Source, NodeState::Stopped);
+        waiting.await.unwrap();
+    }
+
+    #[test]
+    fn test_synced_keeps_the_state() {
+        let board = StatusBoard::new();
+        let now = Utc::now();
+
+        board.set("google", NodeKind::Source, NodeState::Syncing);
+        board.synced("google", NodeKind::Source, now);
+
+        let status = &board.nodes()["google"];
+        assert_eq!(status.last_sync(), &Some(now));
+        assert_eq!(status.state(), &NodeState::Syncing);
+    }
+
+    #[test]
+    fn test_short_type_name() {
+        assert_eq!(short_type_name::<String>(), "String");
+        assert_eq!(short_type_name::<StatusBoard>(), "StatusBoard");
+    }
+}
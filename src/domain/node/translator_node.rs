@@ -0,0 +1,104 @@
+use crate::core::metrics;
+use crate::core::metrics::Metric;
+use crate::domain::channel_handle::{ChannelHandle, Readonly};
+use crate::domain::entity_data::EntityData;
+use crate::domain::entity_translator::EntityTranslator;
+use crate::domain::node::{short_type_name, Manager, Node, ReadonlyManager};
+use crate::domain::published::Published;
+use async_trait::async_trait;
+use log::error;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::OwnedSemaphorePermit;
+use tokio::{join, task};
+
+/// Republishes the entities of one channel as translated by an [EntityTranslator].
+pub(crate) struct TranslatorNode<InputType, OutputType>
+where
+    InputType: EntityData + 'static,
+    OutputType: EntityData + 'static,
+{
+    input: Readonly<Published<InputType>>,
+    lifecycle_manager: ReadonlyManager,
+    output: ChannelHandle<Published<OutputType>>,
+    translator: Arc<dyn EntityTranslator<InputType, OutputType>>,
+}
+
+impl<InputType, OutputType> TranslatorNode<InputType, OutputType>
+where
+    InputType: EntityData + 'static,
+    OutputType: EntityData + 'static,
+{
+    pub(crate) fn new(
+        manager: &Manager,
+        input: &Readonly<Published<InputType>>,
+        translator: &Arc<dyn EntityTranslator<InputType, OutputType>>,
+    ) -> Self {
+        Self {
+            input: input.clone(),
+            lifecycle_manager: manager.readonly(),
+            output: ChannelHandle::with_policy(input.capacity(), input.policy()),
+            translator: translator.clone(),
+        }
+    }
+
+    pub(crate) fn get_readonly_channel_handle(&self) -> Readonly<Published<OutputType>> {
+        self.output.read_only()
+    }
+}
+
+#[async_trait]
+impl<InputType, OutputType> Node for TranslatorNode<InputType, OutputType>
+where
+    InputType: EntityData + 'static,
+    OutputType: EntityData + 'static,
+{
+    async fn run(self: Box<Self>, permit: OwnedSemaphorePermit) {
+        let Self {
+            input,
+            lifecycle_manager,
+            mut output,
+            translator,
+        } = *self;
+        let mut receiver = input.get_receiver();
+
+        let task = task::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(published) => {
+                        let translated = match published {
+                            Published::Entity(entity) => {
+                                Published::Entity(translator.translate(&entity))
+                            }
+                            Published::Synced(acknowledgement) => {
+                                Published::Synced(acknowledgement.forward())
+                            }
+                        };
+                        if let Err(e) = output.send_or_wait(translated).await {
+                            error!("Error while sending translated entity: {}", e);
+                        }
+                    }
+                    Err(RecvError::Closed) => break,
+                    Err(RecvError::Lagged(amount)) => {
+                        metrics::add(
+                            Metric::ChannelLagged,
+                            &[("channel", short_type_name::<InputType>())],
+                            amount,
+                        );
+                        error!(
+                            "Lagged amount of {} in translator node, {} in total.",
+                            amount,
+                            input.report_lagged(amount)
+                        );
+                    }
+                }
+            }
+        });
+
+        let stop_task = lifecycle_manager.abort_after_grace_on_stop(&task).await;
+
+        drop(permit);
+
+        let (_task_result, _stop_result) = join!(task, stop_task);
+    }
+}
@@ -1,8 +1,12 @@
+use crate::core::metrics;
+use crate::core::metrics::Metric;
 use crate::domain::mpsc_handle::one_shot;
 use crate::domain::node::Manager;
 use crate::domain::oauth2::extra_parameters::{ExtraParameters, WithExtraParametersExt};
-use crate::domain::oauth2::token::{BasicTokenResponseExt, Token, TokenExt, TokenStatus};
+use crate::domain::oauth2::token::{read_token_status, BasicTokenResponseExt, Token, TokenStatus};
 use crate::domain::oauth2::ApplicationSecret;
+use crate::domain::request_layer::RequestLayer;
+use crate::domain::retry::RetryPolicy;
 use crate::server::Event::Redirect;
 use crate::server::{Code, Event, WebEventChannelHandle};
 use crate::static_init::error::Error::FailedAfterRetries;
@@ -19,21 +23,23 @@ use oauth2::{
 };
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::fs;
 use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::Semaphore;
 use tokio::task;
-use tokio::time::sleep;
+use tokio::time::{sleep, timeout_at, Instant};
 use Error::Oauth2CsrfMismatch;
 use Event::Oauth2Code;
 
 #[derive(Clone)]
 pub(crate) struct Client {
+    authorization_timeout: Duration,
     basic_client: BasicClient,
     extra_parameters: ExtraParameters,
     manager: Manager,
-    retry_max: u8,
-    retry_period: std::time::Duration,
+    request_layer: RequestLayer,
+    retry_policy: RetryPolicy,
     token_path: PathBuf,
     web_channel_handle: WebEventChannelHandle,
 }
@@ -43,16 +49,18 @@ impl Client {
         application_secret: ApplicationSecret,
         extra_parameters: &ExtraParameters,
         manager: &Manager,
+        request_layer: &RequestLayer,
         token_path: &Path,
         web_channel_handle: &WebEventChannelHandle,
     ) -> Self {
         let basic_client = application_secret.to_client();
         Self {
+            authorization_timeout: manager.core_config().authorization_timeout(),
             basic_client,
             extra_parameters: extra_parameters.clone(),
             manager: manager.clone(),
-            retry_max: 9,
-            retry_period: std::time::Duration::from_secs(2),
+            request_layer: request_layer.clone(),
+            retry_policy: manager.core_config().retry_policy(),
             token_path: token_path.to_owned(),
             web_channel_handle: web_channel_handle.clone(),
         }
@@ -60,7 +68,6 @@ impl Client {
 
     async fn await_code(&self) -> Result<Code, Error> {
         let mut receiver = self.web_channel_handle.get_receiver();
-        let mut attempts = self.retry_max + 1;
         let callback_path = Url::parse(
             self.basic_client
                 .redirect_url()
@@ -69,15 +76,17 @@ impl Client {
         .expect("Redirect URL not valid.")
         .path()
         .to_string();
+        // Nobody may ever authorize, but whoever does needs time to sign in and consent.
+        let deadline = Instant::now() + self.authorization_timeout;
 
         let task = task::spawn(async move {
             debug!("Waiting for callback code");
             loop {
-                if attempts == 0 {
-                    return Err(FailedAfterRetries);
-                }
-
-                match receiver.recv().await {
+                let Ok(received) = timeout_at(deadline, receiver.recv()).await else {
+                    debug!("Timed out waiting for callback code");
+                    return Err(Error::Oauth2CodeMissing);
+                };
+                match received {
                     Ok(event) => match event {
                         Oauth2Code(code, path) => {
                             if path == callback_path {
@@ -96,15 +105,19 @@ impl Client {
                     Err(e) => match e {
                         RecvError::Closed => {
                             debug!("Channel closed");
+                            return Err(FailedAfterRetries);
                         }
                         RecvError::Lagged(skipped_count) => {
+                            metrics::add(
+                                Metric::ChannelLagged,
+                                &[("channel", "web")],
+                                skipped_count,
+                            );
                             debug!("Skipped {} events", skipped_count);
                             continue;
                         }
                     },
                 }
-
-                attempts -= 1;
             }
         });
 
@@ -115,12 +128,14 @@ impl Client {
     }
 
     pub(crate) async fn get_token(&self, scopes: &[&str]) -> Result<AccessToken, Error> {
-        match self.get_token_status_from_file().await {
+        match read_token_status(&self.token_path).await {
             TokenStatus::Ok(token) => Ok(token.access_token().clone()),
             TokenStatus::Expired(refresh_token) => self
                 .refresh_token(&refresh_token)
                 .await
                 .map(|token| token.access_token().clone()),
+            // Nobody is around to authorize a one shot sync.
+            TokenStatus::Absent if self.manager.is_one_shot() => Err(Error::Oauth2TokenAbsent),
             TokenStatus::Absent => self.retrieve_token(scopes).await.map(|token| {
                 debug!("Token retrieved: {:?}", token);
                 token.access_token().clone()
@@ -128,15 +143,6 @@ impl Client {
         }
     }
 
-    async fn get_token_status_from_file(&self) -> TokenStatus {
-        fs::read_to_string(&self.token_path)
-            .await
-            .ok()
-            .map(|s| serde_yaml::from_str::<Token>(&s).ok())
-            .unwrap_or(None)
-            .get_status()
-    }
-
     async fn present_url(&self, url: &Url) -> Result<(), Error> {
         debug!("Presenting user url: {}", url);
         let (sender, mut receiver) = one_shot();
@@ -160,14 +166,9 @@ impl Client {
             .await
             .expect("Could not acquire semaphore");
 
-        let mut attempts = self.retry_max + 1;
+        let mut backoff = self.retry_policy.backoff();
 
         loop {
-            if attempts == 0 {
-                debug!("Max retries reached");
-                return Err(FailedAfterRetries);
-            }
-
             debug!("Sending oauth2 redirect url");
             match self
                 .web_channel_handle
@@ -183,8 +184,13 @@ impl Client {
                 }
                 Err(e) => {
                     debug!("Failed to send oauth2 redirect url: {:?}", e);
-                    sleep(self.retry_period).await;
-                    attempts -= 1;
+                    match backoff.next() {
+                        Some(delay) => sleep(delay).await,
+                        None => {
+                            debug!("Max retries reached");
+                            return Err(FailedAfterRetries);
+                        }
+                    }
                 }
             };
         }
@@ -202,11 +208,21 @@ impl Client {
     }
 
     async fn refresh_token(&self, refresh_token: &RefreshToken) -> Result<Token, Error> {
+        let result = self.exchange_refresh_token(refresh_token).await;
+        metrics::increment(Metric::OauthTokenRefreshes, &[metrics::result(&result)]);
+        result
+    }
+
+    /// Tried once: the refresh happens inside an API call, whose own retry covers it.
+    async fn exchange_refresh_token(&self, refresh_token: &RefreshToken) -> Result<Token, Error> {
         let token_status = self
-            .basic_client
-            .exchange_refresh_token(refresh_token)
-            .with_extra_parameters(&self.extra_parameters)
-            .request_async(async_http_client)
+            .request_layer
+            .request_once(|| {
+                self.basic_client
+                    .exchange_refresh_token(refresh_token)
+                    .with_extra_parameters(&self.extra_parameters)
+                    .request_async(async_http_client)
+            })
             .await
             .map_err(|e| e.to_error())?
             .to_token_status()
@@ -245,10 +261,13 @@ impl Client {
         }
 
         let token_status = self
-            .basic_client
-            .exchange_code(code.code().clone())
-            .set_pkce_verifier(pkce_verifier)
-            .request_async(async_http_client)
+            .request_layer
+            .request(|| {
+                self.basic_client
+                    .exchange_code(code.code().clone())
+                    .set_pkce_verifier(PkceCodeVerifier::new(pkce_verifier.secret().clone()))
+                    .request_async(async_http_client)
+            })
             .await
             .map_err(|e| e.to_error())?
             .to_token_status();
@@ -283,33 +302,56 @@ impl Client {
 mod tests {
     use super::*;
 
+    use crate::domain::config::tests::test_config;
+    use crate::domain::node::get_test_manager;
+    use crate::domain::oauth2::{extra_parameters, ApplicationSecretBuilder};
+    use crate::domain::rate_limiter::RateLimiter;
+
+    fn test_client() -> Client {
+        let app_secret = ApplicationSecretBuilder::default()
+            .auth_provider_x509_cert_url(None)
+            .auth_uri("http://localhost:41047".to_string())
+            .client_email(None)
+            .client_id("client_id".to_string())
+            .client_secret("client_secret".to_string())
+            .client_x509_cert_url(None)
+            .project_id(None)
+            .redirect_uris(vec!["http://localhost:41047".to_string()])
+            .token_uri("http://localhost:41047".to_string())
+            .build()
+            .unwrap();
+        Client::new(
+            app_secret,
+            &extra_parameters!("access_type" => "offline"),
+            &get_test_manager(&test_config()),
+            &RequestLayer::new(RateLimiter::unlimited(), RetryPolicy::default()),
+            Path::new("/test/path"),
+            &WebEventChannelHandle::new(),
+        )
+    }
+
+    mod await_code {
+        use super::*;
+
+        #[tokio::test(start_paused = true)]
+        async fn gives_up_after_the_authorization_timeout() {
+            let client = test_client();
+            let start = Instant::now();
+
+            assert!(matches!(
+                client.await_code().await,
+                Err(Error::Oauth2CodeMissing)
+            ));
+            assert_eq!(start.elapsed(), Duration::from_secs(60 * 60));
+        }
+    }
+
     mod make_redirect_url {
         use super::*;
-        use crate::domain::config::tests::test_config;
-        use crate::domain::node::get_test_manager;
-        use crate::domain::oauth2::{extra_parameters, ApplicationSecretBuilder};
 
         #[test]
         fn gets_right_parameters_in_redirect_url() {
-            let app_secret = ApplicationSecretBuilder::default()
-                .auth_provider_x509_cert_url(None)
-                .auth_uri("http://localhost:41047".to_string())
-                .client_email(None)
-                .client_id("client_id".to_string())
-                .client_secret("client_secret".to_string())
-                .client_x509_cert_url(None)
-                .project_id(None)
-                .redirect_uris(vec!["http://localhost:41047".to_string()])
-                .token_uri("http://localhost:41047".to_string())
-                .build()
-                .unwrap();
-            let client = Client::new(
-                app_secret,
-                &extra_parameters!("access_type" => "offline"),
-                &get_test_manager(&test_config()),
-                &Path::new("/test/path"),
-                &WebEventChannelHandle::new(),
-            );
+            let client = test_client();
 
             let (_pkce_verifier, redirect_url, _csrf_state) =
                 client.make_redirect_url(&["scope1", "scope2"]);
@@ -8,4 +8,6 @@ pub(crate) use application_secret::{ApplicationSecret, ApplicationSecretBuilder}
 
 pub(crate) use client::Client;
 
+pub(crate) use token::{read_token_status, TokenStatus};
+
 pub(crate) use extra_parameters::extra_parameters;
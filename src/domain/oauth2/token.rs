@@ -5,6 +5,8 @@ use oauth2::basic::{BasicTokenResponse, BasicTokenType};
 use oauth2::{AccessToken, RefreshToken, TokenResponse};
 use serde::{Deserialize, Serialize};
 use std::ops::Add;
+use std::path::Path;
+use tokio::fs;
 
 #[derive(Clone, Debug, Deserialize, Getters, Serialize)]
 pub(crate) struct Token {
@@ -66,6 +68,16 @@ impl TokenStatus {
     }
 }
 
+/// Reads the token stored at the given path, which is absent if there's none to read.
+pub(crate) async fn read_token_status(token_path: &Path) -> TokenStatus {
+    fs::read_to_string(token_path)
+        .await
+        .ok()
+        .map(|s| serde_yaml::from_str::<Token>(&s).ok())
+        .unwrap_or(None)
+        .get_status()
+}
+
 pub(crate) trait TokenExt {
     fn get_status(&self) -> TokenStatus;
 }
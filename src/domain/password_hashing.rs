@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+// Argon2id memory cost in KiB. Use a lower one for tests.
+#[cfg(test)]
+const DEFAULT_MEMORY_KIB: u32 = 64;
+#[cfg(not(test))]
+const DEFAULT_MEMORY_KIB: u32 = 19 * 1024;
+
+/// Costs for new Argon2id password hashes.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct PasswordHashing {
+    iterations: u32,
+    memory_kib: u32,
+    parallelism: u32,
+}
+
+impl Default for PasswordHashing {
+    fn default() -> Self {
+        Self {
+            iterations: 2,
+            memory_kib: DEFAULT_MEMORY_KIB,
+            parallelism: 1,
+        }
+    }
+}
+
+impl PasswordHashing {
+    pub(crate) fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    pub(crate) fn memory_kib(&self) -> u32 {
+        self.memory_kib
+    }
+
+    pub(crate) fn parallelism(&self) -> u32 {
+        self.parallelism
+    }
+}
+
+#[cfg(test)]
+impl PasswordHashing {
+    pub(crate) fn with_iterations(self, iterations: u32) -> Self {
+        Self { iterations, ..self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_fill_in_missing_costs() {
+        let settings: PasswordHashing = serde_yaml::from_str("iterations: 3").unwrap();
+
+        assert_eq!(settings.iterations(), 3);
+        assert_eq!(settings.memory_kib(), DEFAULT_MEMORY_KIB);
+        assert_eq!(settings.parallelism(), 1);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::domain::entity::Entity;
+use crate::domain::entity_data::EntityData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::oneshot;
+
+/// What a source publishes on its entity channel.
+#[derive(Clone, Debug)]
+pub(crate) enum Published<DataType>
+where
+    DataType: EntityData,
+{
+    Entity(Entity<DataType>),
+    /// Everything the source got in a sync was published before this.
+    Synced(Acknowledgement),
+}
+
+impl<DataType> Published<DataType>
+where
+    DataType: EntityData,
+{
+    /// The published entity, unless this marks the end of a sync.
+    pub(crate) fn entity(self) -> Option<Entity<DataType>> {
+        match self {
+            Published::Entity(entity) => Some(entity),
+            Published::Synced(_) => None,
+        }
+    }
+}
+
+/// Tells a source whether every sink that received the end of its sync stored everything up to
+/// it.
+///
+/// Each receiver gets its own copy, which counts as a failure if it's dropped without being
+/// acknowledged. The source hears once every copy is gone.
+#[derive(Debug)]
+pub(crate) struct Acknowledgement {
+    pending: Arc<Pending>,
+    settled: bool,
+}
+
+impl Acknowledgement {
+    /// An acknowledgement to publish, and what hears whether the sinks stored the sync.
+    pub(crate) fn new() -> (Self, oneshot::Receiver<bool>) {
+        let (sender, receiver) = oneshot::channel();
+        let acknowledgement = Self {
+            pending: Arc::new(Pending {
+                failed: AtomicBool::new(false),
+                sender: Some(sender),
+            }),
+            settled: true,
+        };
+        (acknowledgement, receiver)
+    }
+
+    /// Passes the acknowledgement on to the receivers of another channel.
+    pub(crate) fn forward(mut self) -> Self {
+        self.settled = true;
+        self
+    }
+
+    pub(crate) fn acknowledge(mut self, stored: bool) {
+        self.settled = stored;
+    }
+}
+
+impl Clone for Acknowledgement {
+    fn clone(&self) -> Self {
+        Self {
+            pending: self.pending.clone(),
+            settled: false,
+        }
+    }
+}
+
+impl Drop for Acknowledgement {
+    fn drop(&mut self) {
+        if !self.settled {
+            self.pending.failed.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Pending {
+    failed: AtomicBool,
+    sender: Option<oneshot::Sender<bool>>,
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        if let Some(sender) = self.sender.take() {
+            let _ = sender.send(!self.failed.load(Ordering::SeqCst));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stored_once_every_copy_is_acknowledged() {
+        let (acknowledgement, receiver) = Acknowledgement::new();
+        let first = acknowledgement.clone();
+        let forwarded = acknowledgement.clone().forward();
+        let second = forwarded.clone();
+        drop(acknowledgement);
+        drop(forwarded);
+
+        first.acknowledge(true);
+        second.acknowledge(true);
+
+        assert!(receiver.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_not_stored_if_any_copy_is_not() {
+        let (acknowledgement, receiver) = Acknowledgement::new();
+        let stored = acknowledgement.clone();
+        let failed = acknowledgement.clone();
+        let dropped = acknowledgement.clone();
+        drop(acknowledgement);
+
+        stored.acknowledge(true);
+        failed.acknowledge(false);
+        drop(dropped);
+
+        assert!(!receiver.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_stored_without_receivers() {
+        let (acknowledgement, receiver) = Acknowledgement::new();
+
+        drop(acknowledgement);
+
+        assert!(receiver.await.unwrap());
+    }
+}
@@ -0,0 +1,128 @@
+use crate::domain::config::SourceConfig;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+
+/// A token bucket limiting how often a source calls out, shared by every clone.
+#[derive(Clone, Debug)]
+pub(crate) struct RateLimiter {
+    bucket: Option<Arc<Mutex<Bucket>>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    per_second: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// Takes a token, or says how long until there is one.
+    fn take(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.capacity);
+        self.updated = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - self.tokens) / self.per_second,
+            ))
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Allows `per_second` requests a second on average, and up to `burst` at once.
+    pub(crate) fn new(per_second: f64, burst: u32) -> Self {
+        let capacity = f64::from(burst.max(1));
+        Self {
+            bucket: Some(Arc::new(Mutex::new(Bucket {
+                capacity,
+                per_second,
+                tokens: capacity,
+                updated: Instant::now(),
+            }))),
+        }
+    }
+
+    /// Reads the limit from the source's config. Sources without one aren't limited.
+    pub(crate) fn for_source(config: Option<&SourceConfig>) -> Self {
+        match config.and_then(|config| *config.max_requests_per_second()) {
+            Some(per_second) if per_second > 0.0 => Self::new(
+                per_second,
+                config
+                    .and_then(|config| *config.request_burst())
+                    .unwrap_or(1),
+            ),
+            _ => Self::unlimited(),
+        }
+    }
+
+    pub(crate) fn unlimited() -> Self {
+        Self { bucket: None }
+    }
+
+    /// Waits until the source may make another request.
+    pub(crate) async fn acquire(&self) {
+        let Some(bucket) = &self.bucket else {
+            return;
+        };
+        loop {
+            let result = bucket.lock().take(Instant::now());
+            match result {
+                Ok(()) => return,
+                Err(wait) => sleep(wait).await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_burst_then_steady_rate() {
+        let limiter = RateLimiter::new(2.0, 3);
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        limiter.acquire().await;
+        limiter.acquire().await;
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_clones_share_the_bucket() {
+        let limiter = RateLimiter::new(1.0, 1);
+        let clone = limiter.clone();
+        let start = Instant::now();
+
+        limiter.acquire().await;
+        clone.acquire().await;
+
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_sources_without_a_limit_are_unlimited() {
+        let config: SourceConfig = serde_yaml::from_str("poll_interval_seconds: 60").unwrap();
+        let limiter = RateLimiter::for_source(Some(&config));
+        let start = Instant::now();
+
+        for _ in 0..100 {
+            limiter.acquire().await;
+        }
+
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::domain::node::Manager;
+use crate::domain::rate_limiter::RateLimiter;
+use crate::domain::retry::{RetryPolicy, Retryable};
+use std::fmt::Display;
+use std::future::Future;
+
+/// What every outbound request from a source goes through, whichever client makes it, so API
+/// calls and token refreshes share one rate limit and retry the same way.
+#[derive(Clone, Debug)]
+pub(crate) struct RequestLayer {
+    rate_limiter: RateLimiter,
+    retry_policy: RetryPolicy,
+}
+
+impl RequestLayer {
+    pub(crate) fn new(rate_limiter: RateLimiter, retry_policy: RetryPolicy) -> Self {
+        Self {
+            rate_limiter,
+            retry_policy,
+        }
+    }
+
+    /// Limited as the named source's config says, retrying as the core config says.
+    pub(crate) fn for_source(manager: &Manager, name: &str) -> Self {
+        let core_config = manager.core_config();
+        Self::new(
+            RateLimiter::for_source(core_config.source(name)),
+            core_config.retry_policy(),
+        )
+    }
+
+    /// Makes the request once the rate limiter allows it, and again for each retry.
+    pub(crate) async fn request<T, E, Call, Attempt>(&self, call: Call) -> Result<T, E>
+    where
+        E: Display + Retryable,
+        Call: Fn() -> Attempt,
+        Attempt: Future<Output = Result<T, E>>,
+    {
+        self.retry_policy
+            .retry(|| async {
+                self.rate_limiter.acquire().await;
+                call().await
+            })
+            .await
+    }
+
+    /// Makes the request once the rate limiter allows it, without retrying, for requests made
+    /// inside another request's retry.
+    pub(crate) async fn request_once<T, Attempt>(&self, call: impl FnOnce() -> Attempt) -> T
+    where
+        Attempt: Future<Output = T>,
+    {
+        self.rate_limiter.acquire().await;
+        call().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::static_init::error::Error;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retries_wait_for_the_rate_limiter() {
+        let request_layer = RequestLayer::new(RateLimiter::new(1.0, 1), RetryPolicy::default());
+        let attempts = AtomicUsize::new(0);
+        let start = Instant::now();
+
+        let result = request_layer
+            .request(|| async {
+                match attempts.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(Error::Connection("Offline".to_string())),
+                    _ => Ok("refreshed"),
+                }
+            })
+            .await;
+
+        assert_eq!(result, Ok("refreshed"));
+        // The retry delays alone come to less than the two seconds the limiter makes them wait.
+        assert!(start.elapsed() >= Duration::from_secs(2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_request_once_does_not_retry() {
+        let request_layer = RequestLayer::new(RateLimiter::unlimited(), RetryPolicy::default());
+        let attempts = AtomicUsize::new(0);
+
+        let result: Result<(), Error> = request_layer
+            .request_once(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(Error::Connection("Offline".to_string()))
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,266 @@
+use crate::static_init::error::Error;
+use log::warn;
+use oauth2::basic::BasicErrorResponse;
+use oauth2::RequestTokenError;
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use std::error::Error as StdError;
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+
+/// Whether an operation that failed with this error may succeed if it's tried again.
+pub(crate) trait Retryable {
+    fn is_retryable(&self) -> bool;
+}
+
+impl Retryable for Error {
+    fn is_retryable(&self) -> bool {
+        matches!(self, Error::Connection(_) | Error::Io(_))
+    }
+}
+
+/// Only failures to reach the server are retried. The server's answer won't change.
+impl<E> Retryable for RequestTokenError<E, BasicErrorResponse>
+where
+    E: StdError,
+{
+    fn is_retryable(&self) -> bool {
+        matches!(self, RequestTokenError::Request(_))
+    }
+}
+
+/// How outbound operations retry: exponential backoff with jitter, until they've taken too long or
+/// tried too often.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    initial_delay_millis: u64,
+    /// Takes up to this fraction off each delay at random, so retries don't arrive together.
+    jitter: f64,
+    max_delay_seconds: u64,
+    max_elapsed_seconds: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_retries: Option<usize>,
+    multiplier: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay_millis: 500,
+            jitter: 0.2,
+            max_delay_seconds: 30,
+            max_elapsed_seconds: 120,
+            max_retries: None,
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The delays to wait before each retry, ending when the policy gives up.
+    pub(crate) fn backoff(&self) -> Backoff {
+        Backoff {
+            policy: self.clone(),
+            retries: 0,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Runs the operation until it succeeds, fails with an error that isn't retryable, or the
+    /// policy gives up, returning the last result.
+    pub(crate) async fn retry<T, E, Operation, Attempt>(
+        &self,
+        mut operation: Operation,
+    ) -> Result<T, E>
+    where
+        E: Display + Retryable,
+        Operation: FnMut() -> Attempt,
+        Attempt: Future<Output = Result<T, E>>,
+    {
+        let mut backoff = self.backoff();
+        loop {
+            match operation().await {
+                Err(e) if e.is_retryable() => match backoff.next() {
+                    Some(delay) => {
+                        warn!("Retrying in {:?} after: {}", delay, e);
+                        sleep(delay).await;
+                    }
+                    None => return Err(e),
+                },
+                result => return result,
+            }
+        }
+    }
+
+    /// Polls every `interval` at first, backing off from there, until it has polled `attempts`
+    /// times. Neither the max delay nor the max elapsed time cuts that short.
+    pub(crate) fn polling(mut self, interval: Duration, attempts: usize) -> Self {
+        self.initial_delay_millis = interval.as_millis() as u64;
+        self.max_delay_seconds = self
+            .max_delay_seconds
+            .max(interval.as_secs_f64().ceil() as u64);
+        self.max_elapsed_seconds = u64::MAX;
+        self.max_retries = Some(attempts);
+        self
+    }
+
+    fn delay(&self, retry: usize, random: f64) -> Duration {
+        let seconds = (self.initial_delay_millis as f64 / 1000.0
+            * self.multiplier.powi(retry.min(i32::MAX as usize) as i32))
+        .min(self.max_delay_seconds as f64);
+        Duration::from_secs_f64(seconds * (1.0 - self.jitter.clamp(0.0, 1.0) * random))
+    }
+}
+
+#[cfg(test)]
+impl RetryPolicy {
+    pub(crate) fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+}
+
+pub(crate) struct Backoff {
+    policy: RetryPolicy,
+    retries: usize,
+    started_at: Instant,
+}
+
+impl Iterator for Backoff {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        if self
+            .policy
+            .max_retries
+            .is_some_and(|max_retries| self.retries >= max_retries)
+        {
+            return None;
+        }
+
+        let delay = self
+            .policy
+            .delay(self.retries, thread_rng().gen_range(0.0..=1.0));
+        if self.started_at.elapsed() + delay > Duration::from_secs(self.policy.max_elapsed_seconds)
+        {
+            return None;
+        }
+
+        self.retries += 1;
+        Some(delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn policy(yaml: &str) -> RetryPolicy {
+        serde_yaml::from_str(yaml).expect("Could not parse retry policy")
+    }
+
+    #[test]
+    fn test_delays_grow_exponentially_up_to_the_max() {
+        let policy = policy("initial_delay_millis: 1000\nmax_delay_seconds: 5");
+
+        assert_eq!(policy.delay(0, 0.0), Duration::from_secs(1));
+        assert_eq!(policy.delay(1, 0.0), Duration::from_secs(2));
+        assert_eq!(policy.delay(2, 0.0), Duration::from_secs(4));
+        assert_eq!(policy.delay(3, 0.0), Duration::from_secs(5));
+        assert_eq!(policy.delay(1000, 0.0), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_jitter_shortens_delays() {
+        let policy = policy("initial_delay_millis: 1000\njitter: 0.5");
+
+        assert_eq!(policy.delay(0, 1.0), Duration::from_millis(500));
+        assert_eq!(policy.delay(0, 0.5), Duration::from_millis(750));
+    }
+
+    #[test]
+    fn test_backoff_stops_after_max_retries() {
+        let policy = RetryPolicy::default().with_max_retries(3);
+
+        assert_eq!(policy.backoff().count(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_backoff_stops_before_max_elapsed() {
+        let policy = policy("initial_delay_millis: 1000\njitter: 0\nmax_elapsed_seconds: 3");
+        let mut delays = Vec::new();
+
+        for delay in policy.backoff() {
+            sleep(delay).await;
+            delays.push(delay);
+        }
+
+        assert_eq!(delays, vec![Duration::from_secs(1), Duration::from_secs(2)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_polling_is_not_cut_short() {
+        let policy = policy("jitter: 0\nmax_delay_seconds: 1\nmax_elapsed_seconds: 2")
+            .polling(Duration::from_secs(5), 3);
+        let mut delays = Vec::new();
+
+        for delay in policy.backoff() {
+            sleep(delay).await;
+            delays.push(delay);
+        }
+
+        assert_eq!(delays, vec![Duration::from_secs(5); 3]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retries_until_success() {
+        let attempts = AtomicUsize::new(0);
+
+        let result = RetryPolicy::default()
+            .retry(|| async {
+                match attempts.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(Error::Connection("Offline".to_string())),
+                    _ => Ok("synced"),
+                }
+            })
+            .await;
+
+        assert_eq!(result, Ok("synced"));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_fatal_errors_are_not_retried() {
+        let attempts = AtomicUsize::new(0);
+
+        let result: Result<(), Error> = RetryPolicy::default()
+            .retry(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(Error::Oauth2CsrfMismatch)
+            })
+            .await;
+
+        assert_eq!(result, Err(Error::Oauth2CsrfMismatch));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_gives_up_with_the_last_error() {
+        let policy = RetryPolicy::default().with_max_retries(2);
+        let attempts = AtomicUsize::new(0);
+
+        let result: Result<(), Error> = policy
+            .retry(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(Error::Io("Disk full".to_string()))
+            })
+            .await;
+
+        assert_eq!(result, Err(Error::Io("Disk full".to_string())));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+}
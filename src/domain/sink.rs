@@ -0,0 +1,27 @@
+use crate::domain::entity::Entity;
+use crate::domain::entity_data::EntityData;
+use crate::domain::entity_user::EntityUser;
+use crate::domain::module_state::NamedModule;
+use crate::static_init::error::Error;
+use async_trait::async_trait;
+
+#[async_trait]
+pub(crate) trait Sink<DataType>: EntityUser + NamedModule + Send + 'static
+where
+    DataType: EntityData,
+{
+    fn sink_identifier(&self) -> &'static str {
+        Self::name()
+    }
+
+    /// Called when the lifecycle asks this sink to re-read its configuration.
+    fn config_changed(&mut self) {}
+
+    /// Stores entities received from the source with the given identifier.
+    async fn put(&mut self, source: &str, entities: &[Entity<DataType>]) -> Result<(), Error>;
+
+    /// Called once everything the source with the given identifier got in a sync has been put.
+    async fn flush(&mut self, _source: &str) -> Result<(), Error> {
+        Ok(())
+    }
+}
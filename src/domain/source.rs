@@ -0,0 +1,31 @@
+use crate::domain::entity::Entity;
+use crate::domain::entity_data::EntityData;
+use crate::domain::entity_user::EntityUser;
+use crate::domain::module_state::NamedModule;
+use crate::static_init::error::Error;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+#[async_trait]
+pub(crate) trait Source<DataType>: EntityUser + NamedModule + Send + 'static
+where
+    DataType: EntityData,
+{
+    fn source_identifier(&self) -> &'static str {
+        Self::name()
+    }
+
+    /// Called when the lifecycle asks this source to re-read its configuration.
+    fn config_changed(&mut self) {}
+
+    /// Whether the source can't get anything until someone authorizes it.
+    fn is_waiting_for_authorization(&self) -> bool {
+        false
+    }
+
+    async fn get(&mut self, since: DateTime<Utc>) -> Result<Vec<Entity<DataType>>, Error>;
+
+    /// Called once the sinks are done with everything got so far, with whether they stored all of
+    /// it. Nothing got since the last time they did should count as backed up until then.
+    async fn acknowledged(&mut self, _stored: bool) {}
+}
@@ -0,0 +1,157 @@
+use crate::domain::entity_channel::EntityChannel;
+use crate::domain::entity_data::EntityData;
+use crate::domain::entity_translator::EntityTranslator;
+use crate::domain::node::{Manager, Node, TranslatorNode};
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+trait Bridge: Send + Sync {
+    fn bridge(
+        &self,
+        manager: &Manager,
+        input: &EntityChannel,
+    ) -> Option<(EntityChannel, Box<dyn Node>)>;
+}
+
+struct TranslatorBridge<InputType, OutputType>
+where
+    InputType: EntityData + 'static,
+    OutputType: EntityData + 'static,
+{
+    translator: Arc<dyn EntityTranslator<InputType, OutputType>>,
+}
+
+impl<InputType, OutputType> Bridge for TranslatorBridge<InputType, OutputType>
+where
+    InputType: EntityData + 'static,
+    OutputType: EntityData + 'static,
+{
+    fn bridge(
+        &self,
+        manager: &Manager,
+        input: &EntityChannel,
+    ) -> Option<(EntityChannel, Box<dyn Node>)> {
+        let node = TranslatorNode::new(manager, &input.downcast()?, &self.translator);
+        let output = EntityChannel::new(input.source(), &node.get_readonly_channel_handle());
+        Some((output, Box::new(node)))
+    }
+}
+
+/// The known [EntityTranslator]s, keyed by their input and output entity data types.
+#[derive(Default)]
+pub(crate) struct TranslatorRegistry {
+    bridges: HashMap<(TypeId, TypeId), Box<dyn Bridge>>,
+}
+
+impl TranslatorRegistry {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn register<InputType, OutputType, TranslatorType>(
+        &mut self,
+        translator: TranslatorType,
+    ) where
+        InputType: EntityData + 'static,
+        OutputType: EntityData + 'static,
+        TranslatorType: EntityTranslator<InputType, OutputType>,
+    {
+        let key = (translator.input_entity(), translator.output_entity());
+        let translator: Arc<dyn EntityTranslator<InputType, OutputType>> = Arc::new(translator);
+        self.bridges
+            .insert(key, Box::new(TranslatorBridge { translator }));
+    }
+
+    /// Makes a channel of `output` entity data from `input`.
+    ///
+    /// Returns the input itself when no translation is needed, along with the node that must be
+    /// run to translate otherwise. Returns `None` when no translator is registered.
+    pub(crate) fn route(
+        &self,
+        manager: &Manager,
+        input: &EntityChannel,
+        output: TypeId,
+    ) -> Option<(EntityChannel, Option<Box<dyn Node>>)> {
+        if input.data_type() == output {
+            return Some((input.clone(), None));
+        }
+
+        let (channel, node) = self
+            .bridges
+            .get(&(input.data_type(), output))?
+            .bridge(manager, input)?;
+        Some((channel, Some(node)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::channel_handle::ChannelHandle;
+    use crate::domain::config::tests::test_config;
+    use crate::domain::entity::Entity;
+    use crate::domain::node::get_test_manager;
+    use crate::domain::published::Published;
+    use uuid::Uuid;
+
+    struct UuidToString;
+
+    impl EntityTranslator<Uuid, String> for UuidToString {
+        fn translate(&self, entity: &Entity<Uuid>) -> Entity<String> {
+            Entity::new(
+                entity.created_at(),
+                entity.data().to_string(),
+                entity.id(),
+                entity.updated_at(),
+            )
+        }
+    }
+
+    mod route {
+        use super::*;
+
+        fn uuid_channel() -> EntityChannel {
+            let handle: ChannelHandle<Published<Uuid>> = ChannelHandle::new();
+            EntityChannel::new("test", &handle.read_only())
+        }
+
+        #[test]
+        fn test_same_type_needs_no_translation() {
+            let manager = get_test_manager(&test_config());
+            let registry = TranslatorRegistry::new();
+
+            let (channel, node) = registry
+                .route(&manager, &uuid_channel(), TypeId::of::<Uuid>())
+                .unwrap();
+
+            assert_eq!(channel.data_type(), TypeId::of::<Uuid>());
+            assert!(node.is_none());
+        }
+
+        #[test]
+        fn test_unregistered_translation_is_none() {
+            let manager = get_test_manager(&test_config());
+            let registry = TranslatorRegistry::new();
+
+            assert!(registry
+                .route(&manager, &uuid_channel(), TypeId::of::<String>())
+                .is_none());
+        }
+
+        #[test]
+        fn test_registered_translation() {
+            let manager = get_test_manager(&test_config());
+            let mut registry = TranslatorRegistry::new();
+            registry.register(UuidToString);
+
+            let (channel, node) = registry
+                .route(&manager, &uuid_channel(), TypeId::of::<String>())
+                .unwrap();
+
+            assert_eq!(channel.data_type(), TypeId::of::<String>());
+            assert_eq!(channel.source(), "test");
+            assert!(node.is_some());
+        }
+    }
+}
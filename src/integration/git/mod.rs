@@ -0,0 +1,66 @@
+use crate::core::node_registry::NodeRegistry;
+use crate::domain::config::Config;
+use crate::domain::module_state::NamedModule;
+use std::path::{Path, PathBuf};
+
+mod sink;
+
+pub(crate) use sink::{Format, Sink};
+
+const DEFAULT_FOLDER: &str = "git";
+
+pub(crate) fn register(registry: &mut NodeRegistry) {
+    registry
+        .register_opt_in_sink(|handles| sink::<String>(&handles.lifecycle_manager().core_config()));
+}
+
+/// A sink for `DataType` set up from the sink's settings.
+pub(crate) fn sink<DataType>(config: &Config) -> Result<Sink<DataType>, String> {
+    Ok(Sink::new(repository_path(config), config.email().as_deref()).with_format(format(config)?))
+}
+
+/// The sink's `format` setting, or YAML.
+pub(crate) fn format(config: &Config) -> Result<Format, String> {
+    config
+        .sink(Sink::<String>::name())
+        .and_then(|sink| sink.settings().get("format"))
+        .map(|format| serde_yaml::from_value(format.clone()).map_err(|e| e.to_string()))
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+/// The repository named by the sink's `path` setting, or a folder under the site state folder.
+pub(crate) fn repository_path(config: &Config) -> PathBuf {
+    config
+        .sink(Sink::<String>::name())
+        .and_then(|sink| sink.settings().get("path"))
+        .and_then(|path| path.as_str())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(config.site_folder()).join(DEFAULT_FOLDER))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repository_path() {
+        let config = Config::with_all_properties(None, None, None, Some("site".to_string()));
+        assert_eq!(repository_path(&config), PathBuf::from("site/git"));
+
+        let config: Config = serde_yaml::from_str("sinks:\n  git:\n    path: /backup").unwrap();
+        assert_eq!(repository_path(&config), PathBuf::from("/backup"));
+    }
+
+    #[test]
+    fn test_format() {
+        let config = Config::with_all_properties(None, None, None, None);
+        assert_eq!(format(&config), Ok(Format::Yaml));
+
+        let config: Config = serde_yaml::from_str("sinks:\n  git:\n    format: json").unwrap();
+        assert_eq!(format(&config), Ok(Format::Json));
+
+        let config: Config = serde_yaml::from_str("sinks:\n  git:\n    format: toml").unwrap();
+        assert!(format(&config).is_err());
+    }
+}
@@ -0,0 +1,454 @@
+use crate::domain::entity::Entity;
+use crate::domain::entity_data::EntityData;
+use crate::domain::entity_user::EntityUser;
+use crate::domain::module_state::NamedModule;
+use crate::domain::sink::Sink as DomainSink;
+use crate::static_init::error::{Error, IoErrorExt, JsonErrorExt, SerdeErrorExt};
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+const COMMITTER_NAME: &str = "Cloud Scraper";
+const DEFAULT_COMMITTER_EMAIL: &str = "cloud-scraper@localhost";
+
+lazy_static! {
+    static ref REPOSITORIES: std::sync::Mutex<HashMap<PathBuf, Arc<Mutex<Repository>>>> =
+        std::sync::Mutex::new(HashMap::new());
+}
+
+/// A repository that every sink writing to it takes turns with, whatever entity data they take.
+#[derive(Default)]
+struct Repository {
+    initialized: bool,
+}
+
+fn repository(path: &Path) -> Arc<Mutex<Repository>> {
+    REPOSITORIES
+        .lock()
+        .expect("Git repositories mutex poisoned.")
+        .entry(path.to_path_buf())
+        .or_default()
+        .clone()
+}
+
+/// How each entity's file is written.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Format {
+    Json,
+    #[default]
+    Yaml,
+}
+
+impl Format {
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Yaml => "yaml",
+        }
+    }
+
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<String, Error> {
+        match self {
+            Format::Json => serde_json::to_string_pretty(value)
+                .map(|json| json + "\n")
+                .map_err(|e| e.to_json_serialization_error()),
+            Format::Yaml => {
+                serde_yaml::to_string(value).map_err(|e| e.to_yaml_serialization_error())
+            }
+        }
+    }
+}
+
+/// Writes each entity it receives as a YAML or JSON file in a local git repository, committing
+/// once a source's sync is over.
+pub(crate) struct Sink<DataType> {
+    committer_email: String,
+    format: Format,
+    path: PathBuf,
+    repository: Arc<Mutex<Repository>>,
+    /// What each source has changed since its last commit.
+    summaries: HashMap<String, Summary>,
+    phantom: PhantomData<DataType>,
+}
+
+impl<DataType> NamedModule for Sink<DataType> {
+    fn name() -> &'static str {
+        "git"
+    }
+}
+
+impl<DataType> EntityUser for Sink<DataType>
+where
+    DataType: 'static,
+{
+    fn supported_entity_data() -> Vec<TypeId> {
+        vec![TypeId::of::<DataType>()]
+    }
+}
+
+#[async_trait]
+impl<DataType> DomainSink<DataType> for Sink<DataType>
+where
+    DataType: EntityData + 'static,
+{
+    async fn put(&mut self, source: &str, entities: &[Entity<DataType>]) -> Result<(), Error> {
+        let repository = self.repository.clone();
+        let mut repository = repository.lock().await;
+        self.initialize(&mut repository).await?;
+
+        let mut summary = self.summaries.remove(source).unwrap_or_default();
+        let result = self.write(source, entities, &mut summary).await;
+        self.summaries.insert(source.to_string(), summary);
+        result
+    }
+
+    async fn flush(&mut self, source: &str) -> Result<(), Error> {
+        let message = match self.summaries.get(source) {
+            Some(summary) if !summary.is_empty() => summary.message(source),
+            _ => {
+                self.summaries.remove(source);
+                return Ok(());
+            }
+        };
+
+        let repository = self.repository.clone();
+        let _repository = repository.lock().await;
+        // Only the source's own folder, so no other source's changes end up in its commit.
+        self.git(&["add", "--all", "--", source]).await?;
+        self.git(&["commit", "--quiet", "--message", &message, "--", source])
+            .await?;
+        // Kept until now, so a failed commit is retried with everything since the last one.
+        self.summaries.remove(source);
+        Ok(())
+    }
+}
+
+impl<DataType> Sink<DataType> {
+    pub(crate) fn new(path: PathBuf, committer_email: Option<&str>) -> Self {
+        Self {
+            committer_email: committer_email
+                .unwrap_or(DEFAULT_COMMITTER_EMAIL)
+                .to_string(),
+            format: Format::default(),
+            repository: repository(&path),
+            path,
+            summaries: HashMap::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    fn entity_path(&self, source: &str, id: &str) -> PathBuf {
+        self.path
+            .join(source)
+            .join(format!("{}.{}", hex::encode(id), self.format.extension()))
+    }
+
+    async fn git(&self, args: &[&str]) -> Result<String, Error> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.path)
+            .args(["-c", &format!("user.name={}", COMMITTER_NAME)])
+            .args(["-c", &format!("user.email={}", self.committer_email)])
+            .args(args)
+            .output()
+            .await
+            .map_err(|e| e.to_error())?;
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            Err(Error::Io(format!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )))
+        }
+    }
+
+    async fn initialize(&self, repository: &mut Repository) -> Result<(), Error> {
+        if repository.initialized {
+            return Ok(());
+        }
+
+        if !fs::try_exists(self.path.join(".git"))
+            .await
+            .map_err(|e| e.to_error())?
+        {
+            debug!("Creating git repository in {:?}", self.path);
+            fs::create_dir_all(&self.path)
+                .await
+                .map_err(|e| e.to_error())?;
+            self.git(&["init", "--quiet"]).await?;
+        }
+
+        repository.initialized = true;
+        Ok(())
+    }
+}
+
+impl<DataType> Sink<DataType>
+where
+    DataType: EntityData,
+{
+    /// Writes the entities' files, counting what changed in `summary`.
+    async fn write(
+        &self,
+        source: &str,
+        entities: &[Entity<DataType>],
+        summary: &mut Summary,
+    ) -> Result<(), Error> {
+        for entity in entities {
+            let path = self.entity_path(source, entity.id());
+            let existing = match fs::read_to_string(&path).await {
+                Ok(existing) => Some(existing),
+                Err(e) if e.kind() == ErrorKind::NotFound => None,
+                Err(e) => return Err(e.to_error()),
+            };
+
+            if entity.data().is_deleted() {
+                if existing.is_some() {
+                    fs::remove_file(&path).await.map_err(|e| e.to_error())?;
+                    summary.removed += 1;
+                }
+                continue;
+            }
+
+            let serialized = self.format.serialize(entity)?;
+            match existing {
+                Some(existing) if existing == serialized => continue,
+                Some(_) => summary.changed += 1,
+                None => summary.added += 1,
+            }
+            fs::create_dir_all(self.path.join(source))
+                .await
+                .map_err(|e| e.to_error())?;
+            fs::write(&path, serialized)
+                .await
+                .map_err(|e| e.to_error())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Summary {
+    added: usize,
+    changed: usize,
+    removed: usize,
+}
+
+impl Summary {
+    fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    fn message(&self, source: &str) -> String {
+        format!(
+            "Sync {}: {} added, {} changed, {} removed",
+            source, self.added, self.changed, self.removed
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::google::tests::{test_task, test_task_list};
+    use crate::integration::google::GoogleTask;
+    use google_tasks1::api::Task;
+    use std::env::temp_dir;
+    use tokio::join;
+    use uuid::Uuid;
+
+    struct TestRepository(PathBuf);
+
+    impl TestRepository {
+        fn new() -> Self {
+            Self(temp_dir().join(format!("cloud-scraper-git-{}", Uuid::new_v4())))
+        }
+    }
+
+    impl Drop for TestRepository {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    async fn log<DataType>(sink: &Sink<DataType>) -> Vec<String> {
+        sink.git(&["log", "--format=%s"])
+            .await
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_summary_message() {
+        let summary = Summary {
+            added: 2,
+            changed: 1,
+            removed: 0,
+        };
+        assert_eq!(
+            summary.message("google"),
+            "Sync google: 2 added, 1 changed, 0 removed"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_flush_commits_each_sync() {
+        let repository = TestRepository::new();
+        let mut sink = Sink::new(repository.0.clone(), None);
+
+        sink.put("source", &[Entity::new_now("first".to_string(), "1")])
+            .await
+            .unwrap();
+        sink.put("source", &[Entity::new_now("second".to_string(), "2")])
+            .await
+            .unwrap();
+        sink.flush("source").await.unwrap();
+        let unchanged = fs::read_to_string(sink.entity_path("source", "1"))
+            .await
+            .unwrap();
+        let unchanged: Entity<String> = serde_yaml::from_str(&unchanged).unwrap();
+        sink.put(
+            "source",
+            &[unchanged, Entity::new_now("changed".to_string(), "2")],
+        )
+        .await
+        .unwrap();
+        sink.flush("source").await.unwrap();
+        sink.put("source", &[]).await.unwrap();
+        sink.flush("source").await.unwrap();
+        sink.flush("elsewhere").await.unwrap();
+
+        assert_eq!(
+            log(&sink).await,
+            vec![
+                "Sync source: 0 added, 1 changed, 0 removed",
+                "Sync source: 2 added, 0 changed, 0 removed",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_commits_are_retried_with_the_next_flush() {
+        let repository = TestRepository::new();
+        let mut sink = Sink::new(repository.0.clone(), None);
+        sink.put("source", &[Entity::new_now("first".to_string(), "1")])
+            .await
+            .unwrap();
+        let lock = repository.0.join(".git").join("index.lock");
+        fs::write(&lock, "").await.unwrap();
+
+        assert!(sink.flush("source").await.is_err());
+        fs::remove_file(&lock).await.unwrap();
+        sink.put("source", &[Entity::new_now("second".to_string(), "2")])
+            .await
+            .unwrap();
+        sink.flush("source").await.unwrap();
+
+        assert_eq!(
+            log(&sink).await,
+            vec!["Sync source: 2 added, 0 changed, 0 removed"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_json_format() {
+        let repository = TestRepository::new();
+        let mut sink = Sink::new(repository.0.clone(), None).with_format(Format::Json);
+        let entity = Entity::new_now("first".to_string(), "1");
+
+        sink.put("source", std::slice::from_ref(&entity))
+            .await
+            .unwrap();
+        sink.flush("source").await.unwrap();
+
+        let path = sink.entity_path("source", "1");
+        assert_eq!(path.extension().unwrap(), "json");
+        let written: Entity<String> =
+            serde_json::from_str(&fs::read_to_string(path).await.unwrap()).unwrap();
+        assert_eq!(written, entity);
+    }
+
+    #[tokio::test]
+    async fn test_deleted_entities_are_removed() {
+        let repository = TestRepository::new();
+        let mut sink = Sink::new(repository.0.clone(), Some("me@example.com"));
+        let task = GoogleTask::entity(&test_task_list(), &test_task()).unwrap();
+        let deleted = Task {
+            deleted: Some(true),
+            ..test_task()
+        };
+        let deleted = GoogleTask::entity(&test_task_list(), &deleted).unwrap();
+
+        sink.put("google", std::slice::from_ref(&task))
+            .await
+            .unwrap();
+        sink.flush("google").await.unwrap();
+        sink.put("google", &[deleted]).await.unwrap();
+        sink.flush("google").await.unwrap();
+
+        assert!(!fs::try_exists(sink.entity_path("google", task.id()))
+            .await
+            .unwrap());
+        assert_eq!(
+            log(&sink).await,
+            vec![
+                "Sync google: 0 added, 0 changed, 1 removed",
+                "Sync google: 1 added, 0 changed, 0 removed",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sinks_sharing_a_repository_commit_only_their_own_sources() {
+        let repository = TestRepository::new();
+        let mut strings = Sink::new(repository.0.clone(), None);
+        let mut tasks = Sink::new(repository.0.clone(), None);
+        let string = Entity::new_now("stub".to_string(), "1");
+        let task = GoogleTask::entity(&test_task_list(), &test_task()).unwrap();
+
+        let (stub, google) = join!(
+            strings.put("stub", std::slice::from_ref(&string)),
+            tasks.put("google", std::slice::from_ref(&task)),
+        );
+        stub.unwrap();
+        google.unwrap();
+        let (stub, google) = join!(strings.flush("stub"), tasks.flush("google"));
+        stub.unwrap();
+        google.unwrap();
+
+        assert_eq!(log(&strings).await.len(), 2);
+        for source in ["stub", "google"] {
+            let last = strings
+                .git(&["log", "--format=%s", "--name-only", "-1", "--", source])
+                .await
+                .unwrap();
+            let mut lines = last.lines().filter(|line| !line.is_empty());
+            assert_eq!(
+                lines.next().unwrap(),
+                format!("Sync {}: 1 added, 0 changed, 0 removed", source)
+            );
+            assert!(lines.all(|file| file.starts_with(&format!("{}/", source))));
+        }
+    }
+}
@@ -1,12 +1,14 @@
 use crate::core::module::State;
 use crate::core::node_handles::NodeHandles;
+use crate::core::users::Identity;
 use crate::domain::config::Config;
 use crate::domain::module_state::ModuleState;
 use crate::domain::node::Manager;
 use crate::domain::oauth2::ApplicationSecret;
 use crate::domain::oauth2::ApplicationSecretBuilder;
 use crate::integration::google::Source;
-use crate::server::auth::auth_validation;
+use crate::server::auth::admin_validation;
+use crate::server::csrf::{csrf_form, csrf_token, with_csrf_cookie};
 use crate::server::errors::Rejectable;
 use crate::server::javascript::WithRedirect;
 use crate::static_init::error::{Error, IoErrorExt, SerdeErrorExt};
@@ -145,38 +147,48 @@ pub fn config_google(
         .and(warp::path("google"))
         .and(path::end())
         .and(warp::get())
-        .and(auth_validation())
-        .map(move || {
+        .and(admin_validation())
+        .and(csrf_token())
+        .map(move |_admin: Identity, csrf_token| {
             let node_handles = get_node_handles.clone();
-            format_response(node_handles)
+            format_response(node_handles, csrf_token)
         })
         .and_then(|future| future)
         .or(warp::path("config")
             .and(warp::path("google"))
             .and(path::end())
             .and(warp::post())
-            .and(auth_validation())
-            .and(warp::body::form())
-            .map(move |form_map| {
+            .and(admin_validation())
+            .and(csrf_form())
+            .map(move |_admin: Identity, form_map| {
                 let node_handles = post_node_handles.clone();
                 update_config(form_map, node_handles)
             })
             .and_then(|future| future))
 }
 
-async fn format_response(handles: NodeHandles) -> Result<impl Reply, Rejection> {
+async fn format_response(
+    handles: NodeHandles,
+    csrf_token: String,
+) -> Result<impl Reply, Rejection> {
     let existing_config = get_config().await;
-    Ok(reply::html(
-        format_config_google_html(handles, &existing_config).await,
+    Ok(with_csrf_cookie(
+        reply::html(format_config_google_html(handles, &existing_config, &csrf_token).await),
+        &csrf_token,
     ))
 }
 
-async fn format_config_google_html(handles: NodeHandles, config: &Option<ConfigQuery>) -> String {
-    let page_data = if let Some(config) = config {
+async fn format_config_google_html(
+    handles: NodeHandles,
+    config: &Option<ConfigQuery>,
+    csrf_token: &str,
+) -> String {
+    let mut page_data = if let Some(config) = config {
         config.to_page_data()
     } else {
         ConfigQuery::empty_page_data()
     };
+    page_data.insert("csrf_token", csrf_token.to_string());
 
     let page_data = page_data.with_redirect_script(&handles);
 
@@ -254,9 +266,11 @@ pub async fn get_config() -> Option<ConfigQuery> {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use crate::core::users::tests::{test_admin, with_test_users_scope};
     use crate::server::auth::gen_token_for_path;
+    use crate::server::csrf::tests::{csrf_cookie, csrf_field, TEST_CSRF_TOKEN};
     use crate::test::tests::CleanableTestFile;
     use lazy_static::lazy_static;
     use std::sync::Mutex;
@@ -268,7 +282,8 @@ mod tests {
         pub static ref TEST_MUTEX: Mutex<()> = Mutex::new(());
     }
 
-    async fn make_config_file_and_lock<'a>() -> CleanableTestFile<'a> {
+    #[allow(clippy::await_holding_lock)]
+    pub(crate) async fn make_config_file_and_lock<'a>() -> CleanableTestFile<'a> {
         CleanableTestFile::new(
             TEST_MUTEX.lock().expect("Could not lock mutex."),
             config_path()
@@ -319,21 +334,25 @@ mod tests {
 
         #[tokio::test]
         async fn with_none_returns_html_with_defaults() {
+            let _scope = with_test_users_scope().await;
             let _lock = make_config_file_and_lock().await;
 
             reset().await;
 
-            let token = gen_token_for_path("/");
+            let token = gen_token_for_path("/", &test_admin()).await;
             let node_handles = get_test_node_handles();
             let filter = config_google(&node_handles);
             let res = request()
                 .method("GET")
-                .header(COOKIE, token.to_cookie_string())
+                .header(
+                    COOKIE,
+                    format!("{}; {}", token.to_cookie_string(), csrf_cookie()),
+                )
                 .path("/config/google")
                 .reply(&filter)
                 .await;
 
-            let expected = format_config_google_html(node_handles, &None).await;
+            let expected = format_config_google_html(node_handles, &None, TEST_CSRF_TOKEN).await;
             let actual = String::from_utf8(res.body().to_vec()).unwrap();
 
             assert_eq!(res.status(), StatusCode::OK);
@@ -357,20 +376,25 @@ mod tests {
 
         #[tokio::test]
         async fn with_config_returns_html_with_config_values() {
+            let _scope = with_test_users_scope().await;
             let _lock = make_config_file_and_lock().await;
 
             let config = test_config();
-            let token = gen_token_for_path("/");
+            let token = gen_token_for_path("/", &test_admin()).await;
             let node_handles = get_test_node_handles();
             let filter = config_google(&node_handles);
             let res = request()
                 .method("GET")
-                .header(COOKIE, token.to_cookie_string())
+                .header(
+                    COOKIE,
+                    format!("{}; {}", token.to_cookie_string(), csrf_cookie()),
+                )
                 .path("/config/google")
                 .reply(&filter)
                 .await;
 
-            let expected = format_config_google_html(node_handles, &Some(config)).await;
+            let expected =
+                format_config_google_html(node_handles, &Some(config), TEST_CSRF_TOKEN).await;
             let actual = String::from_utf8(res.body().to_vec()).unwrap();
 
             assert_eq!(res.status(), StatusCode::OK);
@@ -392,9 +416,10 @@ mod tests {
 
         #[tokio::test]
         async fn post_translates_into_event() {
+            let _scope = with_test_users_scope().await;
             let _lock = make_config_file_and_lock().await;
 
-            let token = gen_token_for_path("/");
+            let token = gen_token_for_path("/", &test_admin()).await;
             let node_handles = get_test_node_handles();
             let mut lifecycle_handle = node_handles.lifecycle_manager().readonly().get_receiver();
             let lifecycle_abort_handle = task::spawn(async move {
@@ -403,9 +428,12 @@ mod tests {
             let filter = config_google(&node_handles);
             let res = request()
                 .method("POST")
-                .header(COOKIE, token.to_cookie_string())
+                .header(
+                    COOKIE,
+                    format!("{}; {}", token.to_cookie_string(), csrf_cookie()),
+                )
                 .path("/config/google")
-                .body(test_config_form_encoded())
+                .body(format!("{}&{}", test_config_form_encoded(), csrf_field()))
                 .reply(&filter)
                 .await;
 
@@ -419,6 +447,28 @@ mod tests {
             lifecycle_abort_handle.await;
         }
 
+        #[tokio::test]
+        async fn post_without_csrf_token_keeps_the_config() {
+            let _scope = with_test_users_scope().await;
+            let _lock = make_config_file_and_lock().await;
+
+            reset().await;
+
+            let token = gen_token_for_path("/", &test_admin()).await;
+            let node_handles = get_test_node_handles();
+            let filter = config_google(&node_handles);
+            let res = request()
+                .method("POST")
+                .header(COOKIE, token.to_cookie_string())
+                .path("/config/google")
+                .body(test_config_form_encoded())
+                .reply(&filter)
+                .await;
+
+            assert_ne!(res.status(), StatusCode::FOUND);
+            assert!(get_config().await.is_none());
+        }
+
         mod to_application_secret {
             use super::*;
             use crate::domain::DomainConfig;
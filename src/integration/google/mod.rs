@@ -1,5 +1,33 @@
+use crate::core::node_registry::NodeRegistry;
+use crate::integration::{git, store};
+use std::time::Duration;
+
 pub mod auth;
 mod source;
 mod tasks;
+mod translator;
 
 pub(crate) use source::Source;
+pub(crate) use tasks::GoogleTask;
+pub(crate) use translator::Translator;
+
+#[cfg(test)]
+pub(crate) use tasks::tests;
+
+const POLL_INTERVAL_SECONDS: u64 = 10;
+
+pub(crate) fn register(registry: &mut NodeRegistry) {
+    registry.register_source(
+        |handles| Source::new(handles.lifecycle_manager(), handles.web_channel_handle()),
+        Duration::from_secs(POLL_INTERVAL_SECONDS),
+    );
+    registry.register_sink(|handles| {
+        store::Sink::<GoogleTask>::new(store::EntityStore::new(
+            &handles.lifecycle_manager().core_config(),
+        ))
+    });
+    registry.register_opt_in_sink(|handles| {
+        git::sink::<GoogleTask>(&handles.lifecycle_manager().core_config())
+    });
+    registry.register_translator(Translator);
+}
@@ -1,24 +1,30 @@
+use crate::domain::entity::Entity;
+use crate::domain::entity_user::EntityUser;
 use crate::domain::module_state::NamedModule;
-use crate::domain::node::{InitReplier, Lifecycle, Manager};
-use crate::domain::oauth2::{extra_parameters, Client};
+use crate::domain::node::Manager;
+use crate::domain::oauth2::{extra_parameters, read_token_status, Client, TokenStatus};
+use crate::domain::request_layer::RequestLayer;
+use crate::domain::source::Source as DomainSource;
 use crate::integration::google::auth::web::get_config;
 use crate::integration::google::auth::DelegateBuilder;
-use crate::integration::google::tasks::sync;
+use crate::integration::google::tasks::{sync, GoogleTask};
 use crate::server::auth::get_token_path;
 use crate::server::WebEventChannelHandle;
-use derive_getters::Getters;
-use log::{error, info, trace};
+use crate::static_init::error::Error;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::{error, info};
 use std::any::TypeId;
-use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore};
-use tokio::time::sleep;
-use tokio::{join, task};
-use Lifecycle::{Init, ReadConfig, Stop};
 
-#[derive(Clone, Debug, Getters)]
+#[derive(Clone)]
 pub struct Source {
+    /// Whether a token was stored as of the latest sync.
+    authorized: bool,
+    client: Option<Client>,
     lifecycle_manager: Manager,
+    load_config: bool,
+    /// Shared by the tasks API and the OAuth client, so token refreshes count towards the limit.
+    request_layer: RequestLayer,
     web_channel_handle: WebEventChannelHandle,
 }
 
@@ -28,140 +34,90 @@ impl NamedModule for Source {
     }
 }
 
+impl EntityUser for Source {
+    fn supported_entity_data() -> Vec<TypeId> {
+        vec![TypeId::of::<GoogleTask>()]
+    }
+}
+
+#[async_trait]
+impl DomainSource<GoogleTask> for Source {
+    fn config_changed(&mut self) {
+        self.load_config = true;
+    }
+
+    fn is_waiting_for_authorization(&self) -> bool {
+        !self.authorized
+    }
+
+    async fn get(&mut self, since: DateTime<Utc>) -> Result<Vec<Entity<GoogleTask>>, Error> {
+        if self.load_config {
+            info!("Loading google source");
+            self.load_config = false;
+            self.client = self.load_client().await;
+        }
+
+        let result = self.get_tasks(since).await;
+        self.authorized = Self::has_token().await;
+        result
+    }
+}
+
 impl Source {
     pub fn new(manager: &Manager, web_channel_handle: &WebEventChannelHandle) -> Self {
         Self {
+            authorized: false,
+            client: None,
             lifecycle_manager: manager.clone(),
+            load_config: true,
+            request_layer: RequestLayer::for_source(manager, Self::name()),
             web_channel_handle: web_channel_handle.clone(),
         }
     }
 
-    pub async fn run(&self, google_permit: OwnedSemaphorePermit) {
-        let (load_sender, mut load_receiver) = mpsc::channel(1);
-        let core_config = self.lifecycle_manager.core_config().clone();
-        let semaphore = Arc::new(Semaphore::new(1));
-        let mut permit = semaphore
-            .clone()
-            .acquire_owned()
-            .await
-            .expect("Could not acquire semaphore");
-        let lifecycle_manager = self.lifecycle_manager.clone();
-        let web_channel_handle = self.web_channel_handle.clone();
-
-        let task = task::spawn(async move {
-            drop(permit);
-
-            loop {
-                match load_receiver.recv().await {
-                    Some(_) => {
-                        info!("Loading google source");
-                    }
-                    None => {
-                        error!("Channel closed");
-                        break;
-                    }
-                }
-                let application_secret = if let Some(config) = get_config().await {
-                    config.to_application_secret(&core_config)
-                } else {
-                    continue;
-                };
-                let token_path = match get_token_path::<Self>().await {
-                    Ok(path) => path,
-                    Err(e) => {
-                        error!("Problem getting or creating the token path: {}", e);
-                        continue;
-                    }
-                };
-                let client = Client::new(
-                    application_secret,
-                    &extra_parameters!("access_type" => "offline"),
-                    &lifecycle_manager,
-                    &token_path,
-                    &web_channel_handle,
-                );
-                loop {
-                    if !load_receiver.is_empty() || load_receiver.is_closed() {
-                        break;
-                    }
-
-                    let delegate = match DelegateBuilder::default().client(client.clone()).build() {
-                        Ok(delegate) => delegate,
-                        Err(e) => {
-                            error!("Error while creating Google authentication delegate: {}", e);
-                            break;
-                        }
-                    };
-                    sync(delegate).await;
-                    sleep(Duration::from_secs(10)).await;
-                }
+    /// Only asks for what changed since the last sync the sinks stored, so resetting when the
+    /// source last synced fetches everything again.
+    async fn get_tasks(&self, since: DateTime<Utc>) -> Result<Vec<Entity<GoogleTask>>, Error> {
+        match &self.client {
+            Some(client) => {
+                let delegate = DelegateBuilder::default()
+                    .client(client.clone())
+                    .build()
+                    .map_err(|e| Error::Builder(e.to_string()))?;
+                sync(delegate, since, &self.request_layer).await
             }
-        });
-
-        {
-            let _permit = semaphore
-                .acquire()
-                .await
-                .expect("Could not acquire semaphore");
-        }
-
-        macro_rules! send_load {
-            () => {
-                match load_sender.send(()).await {
-                    Ok(_) => {
-                        trace!("Sent load event");
-                    }
-                    Err(e) => {
-                        error!("Error while sending event: {}", e);
-                        break;
-                    }
-                }
-            };
+            None => Ok(vec![]),
         }
+    }
 
-        permit = semaphore
-            .clone()
-            .acquire_owned()
-            .await
-            .expect("Could not acquire semaphore");
-
-        let task_abort_handle = task.abort_handle();
-        let mut stop_event_receiver = self.lifecycle_manager.readonly().get_receiver();
-        let lifetime_task = task::spawn(async move {
-            drop(permit);
-            loop {
-                match stop_event_receiver.recv().await {
-                    Ok(event) => match event {
-                        Stop => {
-                            task_abort_handle.abort();
-                            break;
-                        }
-                        Init(event) => {
-                            send_load!();
-                            event.reply_to_init_with((), "google_source").await
-                        }
-                        ReadConfig(type_id) => {
-                            if type_id == TypeId::of::<Source>() {
-                                send_load!();
-                            }
-                        }
-                    },
-                    Err(e) => {
-                        error!("Error while receiving event: {}", e);
-                    }
-                }
+    /// Whether a token is stored, even one that needs refreshing.
+    async fn has_token() -> bool {
+        match get_token_path::<Self>().await {
+            Ok(path) => !matches!(read_token_status(&path).await, TokenStatus::Absent),
+            Err(e) => {
+                error!("Problem getting or creating the token path: {}", e);
+                false
             }
-        });
-
-        {
-            let _permit = semaphore
-                .acquire()
-                .await
-                .expect("Could not acquire semaphore");
         }
+    }
 
-        drop(google_permit);
-
-        let (_task_result, _stop_result) = join!(task, lifetime_task);
+    async fn load_client(&self) -> Option<Client> {
+        let core_config = self.lifecycle_manager.core_config();
+        let application_secret = get_config().await?.to_application_secret(&core_config);
+        let token_path = match get_token_path::<Self>().await {
+            Ok(path) => path,
+            Err(e) => {
+                error!("Problem getting or creating the token path: {}", e);
+                return None;
+            }
+        };
+        Some(Client::new(
+            application_secret,
+            &extra_parameters!("access_type" => "offline"),
+            &self.lifecycle_manager,
+            &self.request_layer,
+            &token_path,
+            &self.web_channel_handle,
+        ))
     }
 }
@@ -0,0 +1,118 @@
+use crate::domain::entity::Entity;
+use crate::domain::entity_data::EntityData;
+use chrono::{DateTime, Utc};
+use derive_getters::Getters;
+use google_tasks1::api::{Task, TaskList};
+use serde::{Deserialize, Serialize};
+
+/// A Google task, along with the task list it belongs to.
+#[derive(Clone, Debug, Deserialize, Getters, PartialEq, Serialize)]
+pub(crate) struct GoogleTask {
+    completed: Option<DateTime<Utc>>,
+    deleted: bool,
+    due: Option<DateTime<Utc>>,
+    hidden: bool,
+    notes: Option<String>,
+    parent: Option<String>,
+    position: Option<String>,
+    status: Option<String>,
+    task_list_id: String,
+    task_list_title: Option<String>,
+    title: Option<String>,
+    web_view_link: Option<String>,
+}
+
+impl EntityData for GoogleTask {
+    fn is_deleted(&self) -> bool {
+        self.deleted
+    }
+}
+
+impl GoogleTask {
+    /// Makes an entity of a task from the Tasks API, if it has an id.
+    ///
+    /// The API has no creation time, so the last update time stands in for it. The store keeps
+    /// the one from the first copy it sees.
+    pub(crate) fn entity(task_list: &TaskList, task: &Task) -> Option<Entity<Self>> {
+        let id = task.id.as_ref()?;
+        let updated = parse_time(&task.updated).unwrap_or(DateTime::<Utc>::MIN_UTC);
+        let data = Self {
+            completed: parse_time(&task.completed),
+            deleted: task.deleted.unwrap_or_default(),
+            due: parse_time(&task.due),
+            hidden: task.hidden.unwrap_or_default(),
+            notes: task.notes.clone(),
+            parent: task.parent.clone(),
+            position: task.position.clone(),
+            status: task.status.clone(),
+            task_list_id: task_list.id.clone().unwrap_or_default(),
+            task_list_title: task_list.title.clone(),
+            title: task.title.clone(),
+            web_view_link: task.web_view_link.clone(),
+        };
+        Some(Entity::new(updated, data, id, updated))
+    }
+}
+
+fn parse_time(time: &Option<String>) -> Option<DateTime<Utc>> {
+    time.as_ref()
+        .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+        .map(|time| time.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn test_task_list() -> TaskList {
+        TaskList {
+            id: Some("list".to_string()),
+            title: Some("My Tasks".to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn test_task() -> Task {
+        Task {
+            completed: Some("2024-06-02T10:00:00.000Z".to_string()),
+            due: Some("2024-06-03T00:00:00.000Z".to_string()),
+            id: Some("task".to_string()),
+            status: Some("completed".to_string()),
+            title: Some("Back up the tasks".to_string()),
+            updated: Some("2024-06-02T10:00:01.000Z".to_string()),
+            ..Default::default()
+        }
+    }
+
+    mod entity {
+        use super::*;
+
+        #[test]
+        fn test_task_is_converted() {
+            let entity = GoogleTask::entity(&test_task_list(), &test_task()).unwrap();
+            let updated = DateTime::parse_from_rfc3339("2024-06-02T10:00:01Z").unwrap();
+
+            assert_eq!(entity.id(), "task");
+            assert_eq!(entity.created_at(), updated);
+            assert_eq!(entity.updated_at(), updated);
+
+            let task = entity.data();
+            assert!(task.completed().is_some());
+            assert!(!task.deleted());
+            assert_eq!(task.status(), &Some("completed".to_string()));
+            assert_eq!(task.task_list_id(), "list");
+            assert_eq!(task.task_list_title(), &Some("My Tasks".to_string()));
+            assert_eq!(task.title(), &Some("Back up the tasks".to_string()));
+        }
+
+        #[test]
+        fn test_task_without_id_is_skipped() {
+            let task = Task {
+                id: None,
+                ..test_task()
+            };
+
+            assert!(GoogleTask::entity(&test_task_list(), &task).is_none());
+        }
+    }
+}
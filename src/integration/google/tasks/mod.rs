@@ -1,3 +1,8 @@
+mod google_task;
 mod sync;
 
+pub(crate) use google_task::GoogleTask;
 pub(crate) use sync::sync;
+
+#[cfg(test)]
+pub(crate) use google_task::tests;
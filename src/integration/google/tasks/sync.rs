@@ -1,8 +1,77 @@
+use crate::core::metrics;
+use crate::core::metrics::Metric;
+use crate::domain::entity::Entity;
+use crate::domain::request_layer::RequestLayer;
+use crate::domain::retry::Retryable;
 use crate::integration::google::auth::Delegate;
+use crate::integration::google::tasks::GoogleTask;
+use crate::static_init::error::Error;
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+use google_tasks1::api::TaskList;
 use google_tasks1::{hyper_rustls, TasksHub};
-use log::info;
+use hyper_rustls::HttpsConnector;
+use hyper_util::client::legacy::connect::HttpConnector;
+use log::debug;
+use std::future::Future;
 
-pub(crate) async fn sync(delegate: Delegate) {
+const MAX_RESULTS: i32 = 100;
+/// How far before the last sync to ask from, so a clock behind Google's doesn't miss changes.
+/// The sinks find the tasks fetched twice unchanged.
+const UPDATED_MIN_OVERLAP: TimeDelta = TimeDelta::seconds(60);
+
+type Hub = TasksHub<HttpsConnector<HttpConnector>>;
+
+/// The hub, with requests that go through the source's request layer.
+struct Api<'a> {
+    hub: Hub,
+    request_layer: &'a RequestLayer,
+}
+
+impl Api<'_> {
+    async fn request<T, Response, Call, Attempt>(&self, call: Call) -> Result<T, Error>
+    where
+        Call: Fn() -> Attempt,
+        Attempt: Future<Output = Result<(Response, T), google_tasks1::Error>>,
+    {
+        self.request_layer
+            .request(|| async {
+                call()
+                    .await
+                    .inspect_err(|_| metrics::increment(Metric::GoogleApiErrors, &[]))
+            })
+            .await
+            .map(|(_, page)| page)
+            .map_err(to_error)
+    }
+}
+
+/// Connection failures, rate limiting and server errors may go away, so they're retried.
+impl Retryable for google_tasks1::Error {
+    fn is_retryable(&self) -> bool {
+        match self {
+            google_tasks1::Error::HttpError(_) | google_tasks1::Error::Io(_) => true,
+            google_tasks1::Error::BadRequest(value) => value["error"]["code"]
+                .as_u64()
+                .is_some_and(is_retryable_status),
+            google_tasks1::Error::Failure(response) => {
+                is_retryable_status(response.status().as_u16().into())
+            }
+            _ => false,
+        }
+    }
+}
+
+fn is_retryable_status(status: u64) -> bool {
+    status == 429 || status >= 500
+}
+
+/// Gets every task changed since the last sync the sinks stored in every task list, including
+/// completed, hidden and deleted tasks.
+pub(crate) async fn sync(
+    delegate: Delegate,
+    since: DateTime<Utc>,
+    request_layer: &RequestLayer,
+) -> Result<Vec<Entity<GoogleTask>>, Error> {
     let client = hyper_util::client::legacy::Client::builder(hyper_util::rt::TokioExecutor::new())
         .build(
             hyper_rustls::HttpsConnectorBuilder::new()
@@ -12,7 +81,122 @@ pub(crate) async fn sync(delegate: Delegate) {
                 .enable_http1()
                 .build(),
         );
-    let hub = TasksHub::new(client, delegate);
-    let task_lists = hub.tasklists().list().doit().await;
-    info!("task lists: {:?}", task_lists);
+    let api = Api {
+        hub: TasksHub::new(client, delegate),
+        request_layer,
+    };
+
+    let updated_min = updated_min(since);
+    let mut entities = Vec::new();
+    for task_list in task_lists(&api).await? {
+        entities.extend(tasks(&api, &task_list, updated_min.as_deref()).await?);
+    }
+    Ok(entities)
+}
+
+/// The `updatedMin` parameter, unless there hasn't been a sync to go on from.
+fn updated_min(since: DateTime<Utc>) -> Option<String> {
+    if since == DateTime::<Utc>::MIN_UTC {
+        return None;
+    }
+    since
+        .checked_sub_signed(UPDATED_MIN_OVERLAP)
+        .map(|time| time.to_rfc3339_opts(SecondsFormat::Millis, true))
+}
+
+async fn task_lists(api: &Api<'_>) -> Result<Vec<TaskList>, Error> {
+    let mut task_lists = Vec::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let token = page_token.as_deref();
+        let page = api
+            .request(|| {
+                let mut call = api.hub.tasklists().list().max_results(MAX_RESULTS);
+                if let Some(token) = token {
+                    call = call.page_token(token);
+                }
+                call.doit()
+            })
+            .await?;
+        task_lists.extend(page.items.unwrap_or_default());
+
+        page_token = page.next_page_token;
+        if page_token.is_none() {
+            debug!("{} task lists", task_lists.len());
+            return Ok(task_lists);
+        }
+    }
+}
+
+async fn tasks(
+    api: &Api<'_>,
+    task_list: &TaskList,
+    updated_min: Option<&str>,
+) -> Result<Vec<Entity<GoogleTask>>, Error> {
+    let Some(task_list_id) = &task_list.id else {
+        return Ok(vec![]);
+    };
+
+    let mut tasks = Vec::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let token = page_token.as_deref();
+        let page = api
+            .request(|| {
+                let mut call = api
+                    .hub
+                    .tasks()
+                    .list(task_list_id)
+                    .max_results(MAX_RESULTS)
+                    .show_completed(true)
+                    .show_deleted(true)
+                    .show_hidden(true);
+                if let Some(updated_min) = updated_min {
+                    call = call.updated_min(updated_min);
+                }
+                if let Some(token) = token {
+                    call = call.page_token(token);
+                }
+                call.doit()
+            })
+            .await?;
+        tasks.extend(
+            page.items
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|task| GoogleTask::entity(task_list, task)),
+        );
+
+        page_token = page.next_page_token;
+        if page_token.is_none() {
+            debug!("{} tasks in task list {}", tasks.len(), task_list_id);
+            return Ok(tasks);
+        }
+    }
+}
+
+fn to_error(error: google_tasks1::Error) -> Error {
+    Error::Connection(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_sync_has_no_updated_min() {
+        assert!(updated_min(DateTime::<Utc>::MIN_UTC).is_none());
+    }
+
+    #[test]
+    fn test_updated_min_overlaps_the_last_sync() {
+        let since = DateTime::parse_from_rfc3339("2024-06-02T10:00:00Z")
+            .unwrap()
+            .to_utc();
+
+        assert_eq!(
+            updated_min(since),
+            Some("2024-06-02T09:59:00.000Z".to_string())
+        );
+    }
 }
@@ -0,0 +1,48 @@
+use crate::domain::entity::Entity;
+use crate::domain::entity_translator::EntityTranslator;
+use crate::integration::google::tasks::GoogleTask;
+
+/// Translates Google tasks into a line of text.
+pub(crate) struct Translator;
+
+impl EntityTranslator<GoogleTask, String> for Translator {
+    fn translate(&self, entity: &Entity<GoogleTask>) -> Entity<String> {
+        let task = entity.data();
+        let status = if *task.deleted() {
+            "deleted"
+        } else if task.completed().is_some() {
+            "done"
+        } else {
+            "to do"
+        };
+        Entity::new(
+            entity.created_at(),
+            format!(
+                "{}: {} ({})",
+                task.task_list_title().as_deref().unwrap_or_default(),
+                task.title().as_deref().unwrap_or_default(),
+                status
+            ),
+            entity.id(),
+            entity.updated_at(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::google::tasks::tests::{test_task, test_task_list};
+
+    #[test]
+    fn test_translate() {
+        let entity = GoogleTask::entity(&test_task_list(), &test_task()).unwrap();
+
+        let translated = Translator.translate(&entity);
+
+        assert_eq!(translated.data(), "My Tasks: Back up the tasks (done)");
+        assert_eq!(translated.id(), entity.id());
+        assert_eq!(translated.created_at(), entity.created_at());
+        assert_eq!(translated.updated_at(), entity.updated_at());
+    }
+}
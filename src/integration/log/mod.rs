@@ -1,50 +1,52 @@
+use crate::core::node_registry::NodeRegistry;
 use crate::domain;
-use crate::domain::channel_handle::Readonly;
 use crate::domain::entity::Entity;
-use crate::domain::node::{Manager, ReadonlyManager};
+use crate::domain::entity_user::EntityUser;
+use crate::domain::module_state::NamedModule;
+use crate::domain::sink::Sink as DomainSink;
+use crate::static_init::error::Error;
+use async_trait::async_trait;
 use log::info;
+use std::any::TypeId;
 use std::fmt::Debug;
-use tokio::join;
-use tokio::sync::OwnedSemaphorePermit;
-use tokio::task;
-use uuid::Uuid;
 
 #[derive(Debug)]
-pub(crate) struct Sink {
-    manager: ReadonlyManager,
-    stub_receiver: Readonly<Entity<Uuid>>,
-}
+pub(crate) struct Sink {}
 
-impl Sink {
-    pub(crate) fn new(manager: &Manager, stub_receiver: &Readonly<Entity<Uuid>>) -> Self {
-        Self {
-            manager: manager.readonly(),
-            stub_receiver: stub_receiver.clone(),
-        }
+impl NamedModule for Sink {
+    fn name() -> &'static str {
+        "log"
     }
+}
 
-    pub(crate) async fn run(&mut self, log_permit: OwnedSemaphorePermit) {
-        let mut stub_receiver = self.stub_receiver.get_receiver();
-        let task = task::spawn(async move {
-            loop {
-                if let Ok(entity) = stub_receiver.recv().await {
-                    info!("{}", stringify(entity));
-                } else {
-                    break;
-                }
-            }
-        });
-
-        let stop_handle = self.manager.abort_on_stop(&task).await;
+impl EntityUser for Sink {
+    fn supported_entity_data() -> Vec<TypeId> {
+        vec![TypeId::of::<String>()]
+    }
+}
 
-        drop(log_permit);
+#[async_trait]
+impl DomainSink<String> for Sink {
+    async fn put(&mut self, _source: &str, entities: &[Entity<String>]) -> Result<(), Error> {
+        for entity in entities {
+            info!("{}", stringify(entity));
+        }
+        Ok(())
+    }
+}
 
-        let (_task_result, _stop_result) = join!(task, stop_handle);
+impl Sink {
+    pub(crate) fn new() -> Self {
+        Self {}
     }
 }
 
-fn stringify<T: domain::entity_data::EntityData>(entity: Entity<T>) -> String {
-    match serde_yaml::to_string(&entity) {
+pub(crate) fn register(registry: &mut NodeRegistry) {
+    registry.register_sink(|_| Sink::new());
+}
+
+fn stringify<T: domain::entity_data::EntityData>(entity: &Entity<T>) -> String {
+    match serde_yaml::to_string(entity) {
         Ok(stringified) => stringified,
         Err(e) => {
             format!(
@@ -60,14 +62,19 @@ fn stringify<T: domain::entity_data::EntityData>(entity: Entity<T>) -> String {
 mod tests {
     use super::*;
     use crate::block_on;
+    use crate::domain::channel_handle::ChannelHandle;
     use crate::domain::config::tests::test_config;
-    use crate::domain::node::LifecycleChannelHandle;
-    use crate::integration::stub::Source;
+    use crate::domain::entity_channel::EntityChannel;
+    use crate::domain::node::{ConsumerNode, LifecycleChannelHandle, Manager, Node, SinkNode};
+    use crate::domain::published::Published;
+    use crate::domain::translator_registry::TranslatorRegistry;
     use crate::test::Logger;
     use log::trace;
     use std::sync::Arc;
+    use std::time::Duration;
     use tokio::sync::Semaphore;
     use tokio::task::JoinSet;
+    use tokio::time::sleep;
 
     #[test]
     fn test_log_sink() {
@@ -79,11 +86,17 @@ mod tests {
                 let config = test_config();
                 let lifecycle_channel_handle = LifecycleChannelHandle::new();
                 let mut manager = Manager::new(&config, lifecycle_channel_handle);
-                let stub_source = Source::new(&manager);
-
-                let mut sink = Sink::new(&manager, &stub_source.get_readonly_channel_handle());
-
-                let mut stub_source_handle = stub_source.get_channel_handle();
+                let mut source_handle = ChannelHandle::new();
+
+                let mut sink = SinkNode::new(&manager, Sink::new());
+                let translator = sink
+                    .connect(
+                        &manager,
+                        &TranslatorRegistry::new(),
+                        &EntityChannel::new("test", &source_handle.read_only()),
+                    )
+                    .unwrap();
+                assert!(translator.is_none());
 
                 let mut join_set = JoinSet::new();
 
@@ -94,20 +107,23 @@ mod tests {
                     .await
                     .expect("Could not acquire semaphore");
 
-                let _abort_handle = join_set.spawn(async move { sink.run(permit).await });
+                let _abort_handle = join_set.spawn(async move { Box::new(sink).run(permit).await });
 
                 let _permit = semaphore
                     .acquire()
                     .await
                     .expect("Could not acquire semaphore");
 
-                stub_source_handle
-                    .send(Entity::new_now(Uuid::new_v4(), "1"))
+                source_handle
+                    .send(Published::Entity(Entity::new_now("data".to_string(), "1")))
                     .unwrap();
-                stub_source_handle
-                    .send(Entity::new_now(Uuid::new_v4(), "2"))
+                source_handle
+                    .send(Published::Entity(Entity::new_now("data".to_string(), "2")))
                     .unwrap();
 
+                sleep(Duration::from_millis(100)).await;
+                // The sink drains until its sources close, so close this one as a source would.
+                drop(source_handle);
                 manager.send_stop().unwrap();
 
                 while let Some(result) = join_set.join_next().await {
@@ -1,3 +1,15 @@
+use crate::core::node_registry::NodeRegistry;
+
+pub(crate) mod git;
 pub(crate) mod google;
 pub(crate) mod log;
+pub(crate) mod store;
 pub(crate) mod stub;
+
+pub(crate) fn register(registry: &mut NodeRegistry) {
+    git::register(registry);
+    google::register(registry);
+    log::register(registry);
+    store::register(registry);
+    stub::register(registry);
+}
@@ -0,0 +1,163 @@
+use serde_yaml::Value;
+use std::fmt::{Display, Formatter};
+
+/// One difference between two versions of an entity, at a dotted path into its YAML.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Change {
+    Added {
+        path: String,
+        value: Value,
+    },
+    Changed {
+        path: String,
+        from: Value,
+        to: Value,
+    },
+    Removed {
+        path: String,
+        value: Value,
+    },
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::Added { path, value } => write!(f, "+ {}: {}", path, compact(value)),
+            Change::Changed { path, from, to } => {
+                write!(f, "~ {}: {} -> {}", path, compact(from), compact(to))
+            }
+            Change::Removed { path, value } => write!(f, "- {}: {}", path, compact(value)),
+        }
+    }
+}
+
+/// Lists the changes that turn `from` into `to`, descending into mappings and sequences.
+pub(crate) fn diff(from: &Value, to: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_at("", from, to, &mut changes);
+    changes
+}
+
+fn diff_at(path: &str, from: &Value, to: &Value, changes: &mut Vec<Change>) {
+    match (from, to) {
+        (Value::Mapping(from), Value::Mapping(to)) => {
+            for (key, from_value) in from {
+                let key_path = join(path, &compact(key));
+                match to.get(key) {
+                    Some(to_value) => diff_at(&key_path, from_value, to_value, changes),
+                    None => changes.push(Change::Removed {
+                        path: key_path,
+                        value: from_value.clone(),
+                    }),
+                }
+            }
+            for (key, to_value) in to {
+                if !from.contains_key(key) {
+                    changes.push(Change::Added {
+                        path: join(path, &compact(key)),
+                        value: to_value.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Sequence(from), Value::Sequence(to)) => {
+            for index in 0..from.len().max(to.len()) {
+                let index_path = format!("{}[{}]", path, index);
+                match (from.get(index), to.get(index)) {
+                    (Some(from_value), Some(to_value)) => {
+                        diff_at(&index_path, from_value, to_value, changes)
+                    }
+                    (Some(from_value), None) => changes.push(Change::Removed {
+                        path: index_path,
+                        value: from_value.clone(),
+                    }),
+                    (None, Some(to_value)) => changes.push(Change::Added {
+                        path: index_path,
+                        value: to_value.clone(),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ => {
+            if from != to {
+                changes.push(Change::Changed {
+                    path: path.to_string(),
+                    from: from.clone(),
+                    to: to.clone(),
+                });
+            }
+        }
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn compact(value: &Value) -> String {
+    match serde_yaml::to_string(value) {
+        Ok(yaml) => yaml.trim_end().to_string(),
+        Err(e) => format!("<{}>", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(text: &str) -> Value {
+        serde_yaml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn test_equal_values_have_no_changes() {
+        let value = yaml("a: 1\nb: [x, y]");
+        assert!(diff(&value, &value).is_empty());
+    }
+
+    #[test]
+    fn test_nested_changes_have_paths() {
+        let changes = diff(
+            &yaml("data:\n  title: old\n  notes: gone\nlist: [a]"),
+            &yaml("data:\n  title: new\n  due: soon\nlist: [a, b]"),
+        );
+
+        assert_eq!(
+            changes,
+            vec![
+                Change::Changed {
+                    path: "data.title".to_string(),
+                    from: yaml("old"),
+                    to: yaml("new"),
+                },
+                Change::Removed {
+                    path: "data.notes".to_string(),
+                    value: yaml("gone"),
+                },
+                Change::Added {
+                    path: "data.due".to_string(),
+                    value: yaml("soon"),
+                },
+                Change::Added {
+                    path: "list[1]".to_string(),
+                    value: yaml("b"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let change = Change::Changed {
+            path: "data.title".to_string(),
+            from: yaml("old"),
+            to: yaml("new"),
+        };
+        assert_eq!(change.to_string(), "~ data.title: old -> new");
+    }
+}
@@ -0,0 +1,464 @@
+use crate::domain::config::Config;
+use crate::domain::entity::Entity;
+use crate::domain::entity_data::EntityData;
+use crate::integration::store::diff;
+use crate::integration::store::diff::Change;
+use crate::static_init::error::{Error, IoErrorExt, SerdeErrorExt};
+use serde_yaml::Value;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+const STORE_FOLDER: &str = "store";
+
+/// Keeps entities on disk under the site state folder, one YAML file per source and entity id.
+#[derive(Clone, Debug)]
+pub(crate) struct EntityStore {
+    root: PathBuf,
+}
+
+impl EntityStore {
+    pub(crate) fn new(config: &Config) -> Self {
+        Self::with_root(Path::new(config.site_folder()).join(STORE_FOLDER))
+    }
+
+    pub(crate) fn with_root(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// The changes between two revisions of an entity, numbered from 1 for the oldest.
+    ///
+    /// Returns `None` if either revision does not exist.
+    pub(crate) async fn diff<DataType>(
+        &self,
+        source: &str,
+        id: &str,
+        from: usize,
+        to: usize,
+    ) -> Result<Option<Vec<Change>>, Error>
+    where
+        DataType: EntityData,
+    {
+        let revisions = self.revisions::<DataType>(source, id).await?;
+        let revision = |number: usize| number.checked_sub(1).and_then(|i| revisions.get(i));
+        match (revision(from), revision(to)) {
+            (Some(from), Some(to)) => Ok(Some(diff::diff(&to_value(from)?, &to_value(to)?))),
+            _ => Ok(None),
+        }
+    }
+
+    /// The current version of every entity stored from the source.
+    pub(crate) async fn entities<DataType>(
+        &self,
+        source: &str,
+    ) -> Result<Vec<Entity<DataType>>, Error>
+    where
+        DataType: EntityData,
+    {
+        let mut entries = match fs::read_dir(self.root.join(source)).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.to_error()),
+        };
+        let mut entities = Vec::new();
+        while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_error())? {
+            let path = entry.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "yaml")
+            {
+                entities.extend(read_entity(&path).await?);
+            }
+        }
+        Ok(entities)
+    }
+
+    pub(crate) async fn get<DataType>(
+        &self,
+        source: &str,
+        id: &str,
+    ) -> Result<Option<Entity<DataType>>, Error>
+    where
+        DataType: EntityData,
+    {
+        read_entity(&self.entity_path(source, id)).await
+    }
+
+    /// Writes the entity, keeping any earlier copy with the same id from the same source as a
+    /// revision. The earlier copy's creation time is kept, since some sources can't say when an
+    /// entity was made.
+    ///
+    /// Returns the new revision number, or `None` if the entity is unchanged.
+    pub(crate) async fn put<DataType>(
+        &self,
+        source: &str,
+        entity: &Entity<DataType>,
+    ) -> Result<Option<usize>, Error>
+    where
+        DataType: EntityData + PartialEq,
+    {
+        let current = self.get(source, entity.id()).await?;
+        let entity = &Entity::new(
+            current
+                .as_ref()
+                .map_or(entity.created_at(), Entity::created_at),
+            entity.data().clone(),
+            entity.id(),
+            entity.updated_at(),
+        );
+        if current.as_ref() == Some(entity) {
+            return Ok(None);
+        }
+
+        let path = self.entity_path(source, entity.id());
+        fs::create_dir_all(self.root.join(source))
+            .await
+            .map_err(|e| e.to_error())?;
+
+        let mut revision = 1;
+        if current.is_some() {
+            let history = self.history_path(source, entity.id());
+            fs::create_dir_all(&history)
+                .await
+                .map_err(|e| e.to_error())?;
+            revision = history_len(&history).await? + 1;
+            fs::copy(&path, history.join(format!("{}.yaml", revision)))
+                .await
+                .map_err(|e| e.to_error())?;
+            revision += 1;
+        }
+
+        let yaml = serde_yaml::to_string(entity).map_err(|e| e.to_yaml_serialization_error())?;
+
+        // Write then rename, so a crash never leaves a half-written entity behind.
+        let partial = path.with_extension("partial");
+        fs::write(&partial, yaml).await.map_err(|e| e.to_error())?;
+        fs::rename(&partial, &path)
+            .await
+            .map_err(|e| e.to_error())?;
+        Ok(Some(revision))
+    }
+
+    /// The name of every source with stored entities, in order.
+    pub(crate) async fn sources(&self) -> Result<Vec<String>, Error> {
+        let mut entries = match fs::read_dir(&self.root).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.to_error()),
+        };
+        let mut sources = Vec::new();
+        while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_error())? {
+            if entry.file_type().await.map_err(|e| e.to_error())?.is_dir() {
+                sources.extend(entry.file_name().to_str().map(str::to_string));
+            }
+        }
+        sources.sort();
+        Ok(sources)
+    }
+
+    /// Every version of an entity, oldest first. The last is the current version.
+    pub(crate) async fn revisions<DataType>(
+        &self,
+        source: &str,
+        id: &str,
+    ) -> Result<Vec<Entity<DataType>>, Error>
+    where
+        DataType: EntityData,
+    {
+        let history = self.history_path(source, id);
+        let mut revisions = Vec::new();
+        for revision in 1..=history_len(&history).await? {
+            let path = history.join(format!("{}.yaml", revision));
+            match read_entity(&path).await? {
+                Some(entity) => revisions.push(entity),
+                None => return Err(Error::Io(format!("Missing revision {:?}", path))),
+            }
+        }
+        revisions.extend(self.get(source, id).await?);
+        Ok(revisions)
+    }
+
+    fn entity_path(&self, source: &str, id: &str) -> PathBuf {
+        // Ids come from cloud services, so they are hex encoded to make them safe file names.
+        self.root
+            .join(source)
+            .join(format!("{}.yaml", hex::encode(id)))
+    }
+
+    fn history_path(&self, source: &str, id: &str) -> PathBuf {
+        self.entity_path(source, id).with_extension("history")
+    }
+}
+
+async fn history_len(history: &Path) -> Result<usize, Error> {
+    let mut entries = match fs::read_dir(history).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.to_error()),
+    };
+    let mut len = 0;
+    while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_error())? {
+        if entry
+            .path()
+            .extension()
+            .is_some_and(|extension| extension == "yaml")
+        {
+            len += 1;
+        }
+    }
+    Ok(len)
+}
+
+async fn read_entity<DataType>(path: &Path) -> Result<Option<Entity<DataType>>, Error>
+where
+    DataType: EntityData,
+{
+    match fs::read_to_string(path).await {
+        Ok(yaml) => serde_yaml::from_str(&yaml)
+            .map(Some)
+            .map_err(|e| e.to_yaml_serialization_error()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.to_error()),
+    }
+}
+
+fn to_value<DataType>(entity: &Entity<DataType>) -> Result<Value, Error>
+where
+    DataType: EntityData,
+{
+    serde_yaml::to_value(entity).map_err(|e| e.to_yaml_serialization_error())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use chrono::{DateTime, TimeDelta, Utc};
+    use std::env::temp_dir;
+    use uuid::Uuid;
+
+    /// A store root in the temp folder that is removed when it goes out of scope.
+    pub(crate) struct TestRoot(PathBuf);
+
+    impl TestRoot {
+        pub(crate) fn new() -> Self {
+            Self(temp_dir().join(format!("cloud-scraper-{}", Uuid::new_v4())))
+        }
+
+        pub(crate) fn store(&self) -> EntityStore {
+            EntityStore::with_root(self.0.clone())
+        }
+    }
+
+    impl Drop for TestRoot {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_new_uses_the_site_folder() {
+        let config = Config::with_all_properties(None, None, None, Some("site".to_string()));
+        let store = EntityStore::new(&config);
+        assert_eq!(store.root, PathBuf::from("site/store"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_entity_is_none() {
+        let root = TestRoot::new();
+        let entity: Option<Entity<String>> = root.store().get("source", "absent").await.unwrap();
+        assert!(entity.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_put_survives_reopening() {
+        let root = TestRoot::new();
+        let created_at = Utc::now() - TimeDelta::try_days(1).unwrap();
+        let entity = Entity::new(created_at, "data".to_string(), "a/b id", Utc::now());
+
+        root.store().put("source", &entity).await.unwrap();
+
+        let stored: Entity<String> = root.store().get("source", "a/b id").await.unwrap().unwrap();
+        assert_eq!(stored, entity);
+        assert_eq!(stored.created_at(), created_at);
+    }
+
+    #[tokio::test]
+    async fn test_put_keeps_the_first_created_at() {
+        let root = TestRoot::new();
+        let store = root.store();
+        let created_at = Utc::now() - TimeDelta::try_days(1).unwrap();
+        store
+            .put(
+                "source",
+                &Entity::new(created_at, "old".to_string(), "id", created_at),
+            )
+            .await
+            .unwrap();
+
+        let updated_at = Utc::now();
+        let revision = store
+            .put(
+                "source",
+                &Entity::new(updated_at, "new".to_string(), "id", updated_at),
+            )
+            .await
+            .unwrap();
+
+        let stored: Entity<String> = store.get("source", "id").await.unwrap().unwrap();
+        assert_eq!(revision, Some(2));
+        assert_eq!(stored.created_at(), created_at);
+        assert_eq!(stored.updated_at(), updated_at);
+        assert_eq!(stored.data(), "new");
+    }
+
+    #[tokio::test]
+    async fn test_entities_are_kept_per_source() {
+        let root = TestRoot::new();
+        let store = root.store();
+        store
+            .put("one", &Entity::new_now("first".to_string(), "id"))
+            .await
+            .unwrap();
+        store
+            .put("two", &Entity::new_now("second".to_string(), "id"))
+            .await
+            .unwrap();
+
+        let one: Entity<String> = store.get("one", "id").await.unwrap().unwrap();
+        let two: Entity<String> = store.get("two", "id").await.unwrap().unwrap();
+        assert_eq!(one.data(), "first");
+        assert_eq!(two.data(), "second");
+    }
+
+    #[tokio::test]
+    async fn test_sources_lists_each_source_once() {
+        let root = TestRoot::new();
+        let store = root.store();
+        assert!(store.sources().await.unwrap().is_empty());
+
+        for (source, id) in [("two", "a"), ("one", "a"), ("two", "b")] {
+            store
+                .put(source, &Entity::new_now("data".to_string(), id))
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(store.sources().await.unwrap(), vec!["one", "two"]);
+    }
+
+    #[tokio::test]
+    async fn test_put_replaces_older_copy() {
+        let root = TestRoot::new();
+        let store = root.store();
+        let created_at = Utc::now();
+        store
+            .put(
+                "source",
+                &Entity::new(created_at, "old".to_string(), "id", created_at),
+            )
+            .await
+            .unwrap();
+        let newer = Entity::new(created_at, "new".to_string(), "id", Utc::now());
+        store.put("source", &newer).await.unwrap();
+
+        let stored: Entity<String> = store.get("source", "id").await.unwrap().unwrap();
+        assert_eq!(stored, newer);
+    }
+
+    #[tokio::test]
+    async fn test_entities_are_the_current_versions() {
+        let root = TestRoot::new();
+        let store = root.store();
+        let created_at = Utc::now();
+        store
+            .put(
+                "source",
+                &Entity::new(created_at, "old".to_string(), "1", created_at),
+            )
+            .await
+            .unwrap();
+        let newer = Entity::new(created_at, "new".to_string(), "1", Utc::now());
+        store.put("source", &newer).await.unwrap();
+        let other = Entity::new_now("other".to_string(), "2");
+        store.put("source", &other).await.unwrap();
+
+        let mut entities: Vec<Entity<String>> = store.entities("source").await.unwrap();
+        entities.sort_by(|a, b| a.id().cmp(b.id()));
+
+        assert_eq!(entities, vec![newer, other]);
+        assert!(store.entities::<String>("absent").await.unwrap().is_empty());
+    }
+
+    mod revisions {
+        use super::*;
+
+        fn version(data: &str, updated_at: DateTime<Utc>) -> Entity<String> {
+            Entity::new(DateTime::<Utc>::MIN_UTC, data.to_string(), "id", updated_at)
+        }
+
+        #[tokio::test]
+        async fn test_changes_are_kept_as_revisions() {
+            let root = TestRoot::new();
+            let store = root.store();
+            let first = version("first", Utc::now() - TimeDelta::try_hours(2).unwrap());
+            let second = version("second", Utc::now() - TimeDelta::try_hours(1).unwrap());
+            let third = version("third", Utc::now());
+
+            assert_eq!(store.put("source", &first).await.unwrap(), Some(1));
+            assert_eq!(store.put("source", &second).await.unwrap(), Some(2));
+            assert_eq!(store.put("source", &second).await.unwrap(), None);
+            assert_eq!(store.put("source", &third).await.unwrap(), Some(3));
+
+            let revisions: Vec<Entity<String>> = store.revisions("source", "id").await.unwrap();
+            assert_eq!(revisions, vec![first, second, third]);
+        }
+
+        #[tokio::test]
+        async fn test_unknown_entity_has_no_revisions() {
+            let root = TestRoot::new();
+            let revisions: Vec<Entity<String>> =
+                root.store().revisions("source", "id").await.unwrap();
+            assert!(revisions.is_empty());
+        }
+
+        #[tokio::test]
+        async fn test_diff_between_revisions() {
+            let root = TestRoot::new();
+            let store = root.store();
+            let updated_at = Utc::now();
+            store
+                .put("source", &version("first", updated_at))
+                .await
+                .unwrap();
+            store
+                .put("source", &version("second", updated_at))
+                .await
+                .unwrap();
+
+            let changes = store
+                .diff::<String>("source", "id", 1, 2)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                changes,
+                vec![Change::Changed {
+                    path: "data".to_string(),
+                    from: Value::String("first".to_string()),
+                    to: Value::String("second".to_string()),
+                }]
+            );
+
+            assert!(store
+                .diff::<String>("source", "id", 1, 3)
+                .await
+                .unwrap()
+                .is_none());
+            assert!(store
+                .diff::<String>("source", "id", 0, 1)
+                .await
+                .unwrap()
+                .is_none());
+        }
+    }
+}
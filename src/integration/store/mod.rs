@@ -0,0 +1,17 @@
+use crate::core::node_registry::NodeRegistry;
+
+mod diff;
+mod entity_store;
+mod sink;
+
+pub(crate) use entity_store::EntityStore;
+pub(crate) use sink::Sink;
+
+#[cfg(test)]
+pub(crate) use entity_store::tests::TestRoot;
+
+pub(crate) fn register(registry: &mut NodeRegistry) {
+    registry.register_sink(|handles| {
+        Sink::<String>::new(EntityStore::new(&handles.lifecycle_manager().core_config()))
+    });
+}
@@ -0,0 +1,101 @@
+use crate::domain::entity::Entity;
+use crate::domain::entity_data::EntityData;
+use crate::domain::entity_user::EntityUser;
+use crate::domain::module_state::NamedModule;
+use crate::domain::sink::Sink as DomainSink;
+use crate::integration::store::diff::Change;
+use crate::integration::store::EntityStore;
+use crate::static_init::error::Error;
+use async_trait::async_trait;
+use log::info;
+use std::any::TypeId;
+use std::marker::PhantomData;
+
+/// Keeps everything it receives in the [EntityStore], logging what changed in each new revision.
+pub(crate) struct Sink<DataType> {
+    store: EntityStore,
+    phantom: PhantomData<DataType>,
+}
+
+impl<DataType> NamedModule for Sink<DataType> {
+    fn name() -> &'static str {
+        "store"
+    }
+}
+
+impl<DataType> EntityUser for Sink<DataType>
+where
+    DataType: 'static,
+{
+    fn supported_entity_data() -> Vec<TypeId> {
+        vec![TypeId::of::<DataType>()]
+    }
+}
+
+#[async_trait]
+impl<DataType> DomainSink<DataType> for Sink<DataType>
+where
+    DataType: EntityData + PartialEq + 'static,
+{
+    async fn put(&mut self, source: &str, entities: &[Entity<DataType>]) -> Result<(), Error> {
+        for entity in entities {
+            let Some(revision) = self.store.put(source, entity).await? else {
+                continue;
+            };
+            if revision > 1 {
+                if let Some(changes) = self
+                    .store
+                    .diff::<DataType>(source, entity.id(), revision - 1, revision)
+                    .await?
+                {
+                    let changes: Vec<_> = changes.iter().map(Change::to_string).collect();
+                    info!(
+                        "{} {} revision {}:\n{}",
+                        source,
+                        entity.id(),
+                        revision,
+                        changes.join("\n")
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<DataType> Sink<DataType> {
+    pub(crate) fn new(store: EntityStore) -> Self {
+        Self {
+            store,
+            phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::store::entity_store::tests::TestRoot;
+
+    #[tokio::test]
+    async fn test_put_stores_every_entity() {
+        let root = TestRoot::new();
+        let mut sink = Sink::new(root.store());
+        let entities = vec![
+            Entity::new_now("first".to_string(), "1"),
+            Entity::new_now("second".to_string(), "2"),
+        ];
+
+        sink.put("source", &entities).await.unwrap();
+
+        for entity in entities {
+            let stored: Entity<String> = root
+                .store()
+                .get("source", entity.id())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(stored, entity);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::domain::entity::Entity;
+use crate::domain::entity_user::EntityUser;
+use crate::domain::module_state::NamedModule;
+use crate::domain::source::Source as DomainSource;
+use crate::static_init::error::Error;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::any::TypeId;
+use std::fmt::Debug;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub(crate) struct Source {
+    entity: Entity<Uuid>,
+}
+
+impl NamedModule for Source {
+    fn name() -> &'static str {
+        "stub"
+    }
+}
+
+impl EntityUser for Source {
+    fn supported_entity_data() -> Vec<TypeId> {
+        vec![TypeId::of::<Uuid>()]
+    }
+}
+
+#[async_trait]
+impl DomainSource<Uuid> for Source {
+    async fn get(&mut self, since: DateTime<Utc>) -> Result<Vec<Entity<Uuid>>, Error> {
+        if self.entity.created_at() > since {
+            Ok(vec![self.entity.clone()])
+        } else {
+            Ok(vec![])
+        }
+    }
+}
+
+impl Source {
+    pub(crate) fn new() -> Self {
+        Self {
+            entity: Entity::new_now(Uuid::new_v4(), &format!("stub {:?}", Uuid::new_v4())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine::tests::stub_config;
+    use crate::domain::mpsc_handle::one_shot;
+    use crate::domain::node::{LifecycleChannelHandle, Manager, Node, SourceNode};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::Semaphore;
+    use tokio::task::JoinSet;
+
+    mod get {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_stub_source_get_returns_the_entity_once() {
+            let mut source = Source::new();
+
+            let entities = source.get(DateTime::<Utc>::MIN_UTC).await.unwrap();
+            assert_eq!(entities.len(), 1);
+
+            let entities = source.get(Utc::now()).await.unwrap();
+            assert!(entities.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stub_source_run() {
+        let config = stub_config();
+        let lifecycle_channel_handle = LifecycleChannelHandle::new();
+        let mut manager = Manager::new(&config, lifecycle_channel_handle.clone());
+        let mut join_set = JoinSet::new();
+        let interval = Duration::from_secs(1);
+        let node = SourceNode::new(&manager, Source::new(), interval);
+
+        let mut read_receiver = node.get_readonly_channel_handle().get_receiver();
+        let semaphore = Arc::new(Semaphore::new(1));
+        let permit = semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("Could not acquire semaphore");
+        let _source_run = join_set.spawn(async move { Box::new(node).run(permit).await });
+        let _permit = semaphore
+            .acquire()
+            .await
+            .expect("Could not acquire semaphore");
+        let (sender, _receiver) = one_shot();
+        manager.send_init(sender).unwrap();
+
+        let event = read_receiver.recv().await.unwrap().entity().unwrap();
+        let diff = Utc::now().timestamp_millis() - event.created_at().timestamp_millis();
+        // Other tests sync the stub too, and the first sync waits for the schedule after theirs.
+        assert!(diff < interval.as_millis() as i64 + 100);
+
+        manager.send_stop().unwrap();
+
+        while let Some(res) = join_set.join_next().await {
+            res.expect("Error while running task");
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use crate::domain::entity::Entity;
+use crate::domain::entity_translator::EntityTranslator;
+use uuid::Uuid;
+
+/// Translates stub entities into plain strings.
+pub(crate) struct Translator;
+
+impl EntityTranslator<Uuid, String> for Translator {
+    fn translate(&self, entity: &Entity<Uuid>) -> Entity<String> {
+        Entity::new(
+            entity.created_at(),
+            entity.data().to_string(),
+            entity.id(),
+            entity.updated_at(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate() {
+        let uuid = Uuid::new_v4();
+        let entity = Entity::new_now(uuid, "id");
+
+        let translated = Translator.translate(&entity);
+
+        assert_eq!(translated.data(), &uuid.to_string());
+        assert_eq!(translated.id(), "id");
+        assert_eq!(translated.created_at(), entity.created_at());
+        assert_eq!(translated.updated_at(), entity.updated_at());
+    }
+}
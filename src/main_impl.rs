@@ -1,8 +1,8 @@
-use crate::core::cli::Command::{RootPassword, Serve};
-use crate::core::cli::{Cli, Command, ServeArgs};
-use crate::core::construct_config;
+use crate::core::cli::Command::{Export, RootPassword, Serve, Sync, Users};
+use crate::core::cli::{Cli, Command, ServeArgs, UsersCommand};
 use crate::core::engine::{Engine, EngineImpl};
-use crate::core::root_password::{create_root_password, root_password_exists};
+use crate::core::users::{add_user, list_users, remove_user, users_exist, Role, ROOT_USER};
+use crate::core::{construct_config, export};
 use crate::domain::config::Config;
 use crate::server;
 use crate::server::WebServer;
@@ -24,15 +24,23 @@ where
         Command::Config(config_args) => {
             construct_config(config_args).await;
         }
-        RootPassword(_root_password_args) => {
-            create_root_password().await?;
+        Export(export_args) => {
+            debug!("Reading config...");
+            let config = Interface::construct_config(&ServeArgs::from(&export_args.config_args));
+
+            debug!("Exporting");
+            export(&config, export_args).await?;
+        }
+        RootPassword(root_password_args) => {
+            let config = Interface::construct_config(&root_password_args.into());
+            add_user(ROOT_USER, Role::Admin, &config.password_hashing()).await?;
         }
         Serve(serve_args) => {
-            debug!("Checking root password...");
-            if !root_password_exists().await {
+            debug!("Checking users...");
+            if !users_exist().await {
                 return Err(
-                    "Root password not set. Use root-password to create a password before running \
-                    serve."
+                    "No users set. Use users add or root-password to create an admin before \
+                    running serve."
                         .to_string(),
                 );
             }
@@ -50,8 +58,38 @@ where
             let engine = Interface::construct_engine(config, server);
 
             debug!("Starting engine");
-            engine.start().await;
+            engine.start().await?;
         }
+        Sync(sync_args) => {
+            debug!("Reading config...");
+            let config = Interface::construct_config(&sync_args.into());
+
+            debug!("Checking config...");
+            config.sanity_check()?;
+
+            debug!("Constructing engine...");
+            let server = Interface::construct_server(config.clone());
+            let engine = Interface::construct_engine(config, server);
+
+            debug!("Syncing");
+            engine.sync().await?;
+        }
+        Users(users_args) => match &users_args.command {
+            UsersCommand::Add {
+                config_args,
+                name,
+                role,
+            } => {
+                let config = Interface::construct_config(&config_args.into());
+                add_user(name, *role, &config.password_hashing()).await?;
+            }
+            UsersCommand::List => {
+                for identity in list_users().await? {
+                    println!("{}\t{}", identity.name(), identity.role());
+                }
+            }
+            UsersCommand::Remove { name } => remove_user(name).await?,
+        },
     }
     Ok(())
 }
@@ -92,8 +130,9 @@ impl MainInterface for CoreInterface {
 mod tests {
     use super::*;
     use crate::block_on;
+    use crate::core::cli::ConfigArgs;
     use crate::core::engine::MockEngine;
-    use crate::core::root_password::tests::with_test_root_password_scope;
+    use crate::core::users::tests::with_test_users_scope;
     use crate::domain::config::tests::{test_config, test_config_with};
     use crate::domain::config::DomainConfig;
     use crate::server::MockWebServer;
@@ -190,7 +229,9 @@ mod tests {
                 note_called!(ENGINE_CONSTRUCTOR);
                 let mut eng = MockEngine::new();
 
-                eng.expect_start().times(1).returning(|| Box::pin(async {}));
+                eng.expect_start()
+                    .times(1)
+                    .returning(|| Box::pin(async { Ok(()) }));
 
                 eng
             }
@@ -216,7 +257,9 @@ mod tests {
                 note_called!(ENGINE_CONSTRUCTOR);
                 let mut eng = MockEngine::new();
 
-                eng.expect_start().times(1).returning(|| Box::pin(async {}));
+                eng.expect_start()
+                    .times(1)
+                    .returning(|| Box::pin(async { Ok(()) }));
 
                 eng
             }
@@ -225,7 +268,7 @@ mod tests {
 
     macro_rules! with_password_file {
         ($b:block) => {
-            let _scope = block_on!(with_test_root_password_scope());
+            let _scope = block_on!(with_test_users_scope());
             $b
         };
     }
@@ -241,11 +284,11 @@ mod tests {
             }
             let _ = block_on!(main_impl::<MockMainInterface>());
 
-            assert_eq!(MockMainInterface::is_log_initializer_called(), true);
-            assert_eq!(MockMainInterface::is_cli_getter_called(), true);
-            assert_eq!(MockMainInterface::is_config_constructor_called(), true);
-            assert_eq!(MockMainInterface::is_server_constructor_called(), true);
-            assert_eq!(MockMainInterface::is_engine_constructor_called(), true);
+            assert!(MockMainInterface::is_log_initializer_called());
+            assert!(MockMainInterface::is_cli_getter_called());
+            assert!(MockMainInterface::is_config_constructor_called());
+            assert!(MockMainInterface::is_server_constructor_called());
+            assert!(MockMainInterface::is_engine_constructor_called());
         });
     }
 
@@ -260,11 +303,11 @@ mod tests {
             }
             block_on!(main_impl::<MockMainInterface>()).unwrap();
 
-            assert_eq!(MockMainInterface::is_log_initializer_called(), true);
-            assert_eq!(MockMainInterface::is_cli_getter_called(), true);
-            assert_eq!(MockMainInterface::is_config_constructor_called(), true);
-            assert_eq!(MockMainInterface::is_server_constructor_called(), true);
-            assert_eq!(MockMainInterface::is_engine_constructor_called(), true);
+            assert!(MockMainInterface::is_log_initializer_called());
+            assert!(MockMainInterface::is_cli_getter_called());
+            assert!(MockMainInterface::is_config_constructor_called());
+            assert!(MockMainInterface::is_server_constructor_called());
+            assert!(MockMainInterface::is_engine_constructor_called());
         });
     }
 
@@ -282,14 +325,49 @@ mod tests {
 
             Logger::use_in(|logger| {
                 block_on!(main_impl::<MockMainInterface>()).unwrap();
-                assert_eq!(
-                    logger.log_entry_exists(&LogEntry::debug("Starting engine")),
-                    true
-                );
+                assert!(logger.log_entry_exists(&LogEntry::debug("Starting engine")));
             });
         });
     }
 
+    #[test]
+    fn test_sync_does_not_need_a_root_password_or_start_the_engine() {
+        with_verifiers!(MockMainInterface);
+        impl MainInterface for MockMainInterface {
+            with_empty_logging!();
+            fn get_cli() -> Cli {
+                note_called!(CLI_GETTER);
+                Cli {
+                    command: Sync(ConfigArgs { config: None }),
+                }
+            }
+            fn construct_config(_serve_args: &ServeArgs) -> Arc<Config> {
+                note_called!(CONFIG_CONSTRUCTOR);
+                TEST_CONFIG.clone()
+            }
+            fn construct_server(_config: Arc<Config>) -> impl WebServer {
+                note_called!(SERVER_CONSTRUCTOR);
+                MockWebServer::new()
+            }
+            fn construct_engine(_config: Arc<Config>, _server: impl WebServer) -> impl Engine {
+                note_called!(ENGINE_CONSTRUCTOR);
+                let mut eng = MockEngine::new();
+
+                eng.expect_start().never();
+                eng.expect_sync()
+                    .times(1)
+                    .returning(|| Box::pin(async { Err("stub failed".to_string()) }));
+
+                eng
+            }
+        }
+
+        let result = block_on!(main_impl::<MockMainInterface>());
+
+        assert_eq!(result, Err("stub failed".to_string()));
+        assert!(MockMainInterface::is_engine_constructor_called());
+    }
+
     #[test]
     fn test_barfs_insane_config() {
         with_password_file!({
@@ -1,16 +1,21 @@
 mod challenge_token_server;
 mod types;
 
+use crate::core::metrics;
+use crate::core::metrics::Metric;
 use crate::domain::config::Config;
+use crate::domain::retry::RetryPolicy;
 use crate::server::acme::types::CertAndPrivateKey;
 use crate::server::site_state::SiteState;
 use acme2::{
     gen_ec_p256_private_key, AccountBuilder, AuthorizationStatus, ChallengeStatus, Csr,
     DirectoryBuilder, Error, OrderBuilder, OrderStatus,
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use std::future::Future;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::time::sleep;
 use tokio::{fs, join};
 use x509_parser::parse_x509_certificate;
 use x509_parser::pem::parse_x509_pem;
@@ -44,6 +49,12 @@ impl Acme {
             return Ok(());
         }
 
+        let result = self.renew_certs().await;
+        metrics::increment(Metric::AcmeRenewals, &[metrics::result(&result)]);
+        result
+    }
+
+    async fn renew_certs(&self) -> Result<(), String> {
         let cert_and_private_key = self
             .get_cert()
             .await
@@ -58,6 +69,10 @@ impl Acme {
 
     async fn get_cert(&self) -> Result<CertAndPrivateKey, Error> {
         let domain_config = self.config.domain_config();
+        let retry_policy = self.config.retry_policy().polling(
+            Duration::from_secs(domain_config.poll_interval_seconds()),
+            domain_config.poll_attempts(),
+        );
 
         // Create a new ACMEv2 directory for Let's Encrypt.
         let dir = DirectoryBuilder::new(LETS_ENCRYPT_URL.to_string())
@@ -120,14 +135,20 @@ impl Acme {
             // Start the validation of the challenge.
             let challenge = challenge.validate().await?;
             let challenge_until_result = async {
-                // challenge every interval seconds until it is in either the
+                // challenge with backoff until it is in either the
                 // `valid` or `invalid` state.
-                let challenge = challenge
-                    .wait_done(
-                        Duration::from_secs(domain_config.poll_interval_seconds()),
-                        domain_config.poll_attempts(),
-                    )
-                    .await?;
+                let challenge = poll_while(
+                    &retry_policy,
+                    challenge,
+                    |challenge| {
+                        matches!(
+                            challenge.status,
+                            ChallengeStatus::Pending | ChallengeStatus::Processing
+                        )
+                    },
+                    |challenge| async move { challenge.poll().await },
+                )
+                .await?;
                 log::debug!(
                     "Stopped waiting for challenge completion. Challenge is: {:?}",
                     challenge
@@ -142,26 +163,28 @@ impl Acme {
             let (_challenge_result, _challenge_token_server_result) =
                 join!(challenge_until_result, challenge_token_server_wait_handle,);
 
-            // Poll the authorization every interval seconds until it is in either the
+            // Poll the authorization with backoff until it is in either the
             // `valid` or `invalid` state.
-            let authorization = auth
-                .wait_done(
-                    Duration::from_secs(self.config.domain_config().poll_interval_seconds()),
-                    self.config.domain_config().poll_attempts(),
-                )
-                .await?;
+            let authorization = poll_while(
+                &retry_policy,
+                auth,
+                |auth| auth.status == AuthorizationStatus::Pending,
+                |auth| auth.poll(),
+            )
+            .await?;
             assert_eq!(authorization.status, AuthorizationStatus::Valid)
         }
 
-        // Poll the order every interval seconds until it is in either the
+        // Poll the order with backoff until it is in either the
         // `ready` or `invalid` state. Ready means that it is now ready
         // for finalization (certificate creation).
-        let order = order
-            .wait_ready(
-                Duration::from_secs(self.config.domain_config().poll_interval_seconds()),
-                self.config.domain_config().poll_attempts(),
-            )
-            .await?;
+        let order = poll_while(
+            &retry_policy,
+            order,
+            |order| order.status == OrderStatus::Pending,
+            |order| async move { order.poll().await },
+        )
+        .await?;
         log::debug!("Stopped waiting for order ready");
 
         assert_eq!(order.status, OrderStatus::Ready);
@@ -175,15 +198,21 @@ impl Acme {
         let order = order.finalize(Csr::Automatic(pkey.clone())).await?;
         log::debug!("Signing Request created");
 
-        // Poll the order every interval seconds until it is in either the
+        // Poll the order with backoff until it is in either the
         // `valid` or `invalid` state. Valid means that the certificate
         // has been provisioned, and is now ready for download.
-        let order = order
-            .wait_done(
-                Duration::from_secs(self.config.domain_config().poll_interval_seconds()),
-                self.config.domain_config().poll_attempts(),
-            )
-            .await?;
+        let order = poll_while(
+            &retry_policy,
+            order,
+            |order| {
+                matches!(
+                    order.status,
+                    OrderStatus::Pending | OrderStatus::Ready | OrderStatus::Processing
+                )
+            },
+            |order| async move { order.poll().await },
+        )
+        .await?;
         log::debug!("Stopped waiting for order completion");
 
         assert_eq!(order.status, OrderStatus::Valid);
@@ -231,6 +260,45 @@ impl Acme {
     }
 }
 
+/// Polls until the value is no longer pending, waiting between polls as the retry policy says.
+async fn poll_while<T, Pending, Poll, Polled>(
+    retry_policy: &RetryPolicy,
+    mut value: T,
+    pending: Pending,
+    mut poll: Poll,
+) -> Result<T, Error>
+where
+    Pending: Fn(&T) -> bool,
+    Poll: FnMut(T) -> Polled,
+    Polled: Future<Output = Result<T, Error>>,
+{
+    let mut backoff = retry_policy.backoff();
+    while pending(&value) {
+        let Some(delay) = backoff.next() else {
+            return Err(Error::MaxAttemptsExceeded);
+        };
+        sleep(delay).await;
+        value = poll(value).await?;
+    }
+    Ok(value)
+}
+
+/// When the site's certificate expires, if it serves TLS and has one.
+pub(crate) async fn cert_expiry(config: &Config) -> Option<DateTime<Utc>> {
+    if !config.uses_tls() {
+        return None;
+    }
+
+    let site_state = SiteState::new(config);
+    match get_cert_validity(site_state.cert_path()).await {
+        Ok(validity) => DateTime::from_timestamp(validity.not_after, 0),
+        Err(e) => {
+            log::error!("Failed to get certificate expiry timestamp: {}", e);
+            None
+        }
+    }
+}
+
 async fn cert_is_not_expired(path: &str) -> bool {
     // Get the expiry timestamp of the certificate
     let validity = match get_cert_validity(path).await {
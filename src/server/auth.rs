@@ -1,40 +1,113 @@
 use crate::core::module::State;
+use crate::core::users::{find_user, Identity};
+use crate::domain::config::Config;
 use crate::domain::module_state::{ModuleState, NamedModule};
 use chrono::{DateTime, TimeDelta, Utc};
+use derive_getters::Getters;
 use lazy_static::lazy_static;
+use log::{error, info};
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fmt::Debug;
 use std::ops::Add;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use std::time::Duration;
+use std::{fs, io};
+use tokio::time::interval;
 use warp::reject::Reject;
 use warp::Filter;
 
 const KEY_BYTES: usize = 16;
 const MAX_TOKEN_AGE_SECONDS: u64 = 24 * 60 * 60;
+const SESSION_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const SESSIONS_FILE: &str = "sessions.yaml";
 
 lazy_static! {
-    static ref TOKEN_MANAGER: Mutex<TokenManager> = Mutex::new(TokenManager::new());
+    static ref SESSION_STORE: Mutex<SessionStore> = Mutex::new(SessionStore::new());
+    /// Held from changing the sessions until they're saved, so saves land in the same order.
+    static ref SESSION_SAVES: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
 }
 
-pub fn gen_token_for_path(path: &str) -> Token {
-    TOKEN_MANAGER
-        .lock()
-        .expect("Token manager mutex poisoned.")
-        .put_token(Token::new(
-            path,
-            &Duration::from_secs(MAX_TOKEN_AGE_SECONDS),
-        ))
+fn session_store() -> std::sync::MutexGuard<'static, SessionStore> {
+    SESSION_STORE.lock().expect("Session store mutex poisoned.")
 }
 
-pub fn token_is_valid(token: &str) -> bool {
-    TOKEN_MANAGER
-        .lock()
-        .expect("Token manager mutex poisoned.")
-        .token_is_valid(token)
+/// Changes the sessions, then saves them if `change` says it changed them. Only the change and
+/// serializing happen under the store's lock, so nobody waits on the disk to read a session.
+async fn change_sessions<T>(change: impl FnOnce(&mut SessionStore) -> (T, bool)) -> T {
+    let _saving = SESSION_SAVES.lock().await;
+    let (result, serialized) = {
+        let mut store = session_store();
+        let (result, changed) = change(&mut store);
+        (result, if changed { store.serialize() } else { None })
+    };
+    if let Some((path, yaml)) = serialized {
+        save_sessions(&path, yaml).await;
+    }
+    result
+}
+
+/// Writes then renames, so a crash never leaves half the sessions behind.
+async fn save_sessions(path: &Path, yaml: String) {
+    let partial = path.with_extension("partial");
+    let result = match tokio::fs::write(&partial, yaml).await {
+        Ok(_) => tokio::fs::rename(&partial, path).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        error!("Could not save sessions to {:?}: {}", path, e);
+    }
+}
+
+pub async fn gen_token_for_path(path: &str, user: &Identity) -> Token {
+    let token = Token::new(path, &Duration::from_secs(MAX_TOKEN_AGE_SECONDS));
+    change_sessions(|store| (store.put_token(token, user), true)).await
+}
+
+/// Keeps sessions in the site state folder from now on, so they survive restarts.
+pub(crate) fn load_sessions(config: &Config) {
+    let path = Path::new(config.site_folder()).join(SESSIONS_FILE);
+    if let Err(e) = session_store().load(path) {
+        error!("Could not load sessions: {}", e);
+    }
+}
+
+pub(crate) async fn purge_expired_sessions_periodically() {
+    let mut interval = interval(SESSION_PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        change_sessions(|store| ((), store.purge_expired())).await;
+    }
+}
+
+/// Every unexpired session, oldest first.
+pub(crate) fn sessions() -> Vec<Session> {
+    session_store().sessions()
+}
+
+pub(crate) fn session_for_token(token: &str) -> Option<Session> {
+    session_store().session_for_token(token)
+}
+
+/// Returns whether there was a session with the id.
+pub(crate) async fn revoke_session(id: &str) -> bool {
+    change_sessions(|store| {
+        let revoked = store.revoke(|session| session.id == id);
+        (revoked, revoked)
+    })
+    .await
+}
+
+pub(crate) async fn revoke_token(token: &str) -> bool {
+    let hash = hash_token(token);
+    change_sessions(|store| {
+        let revoked = store.revoke(|session| session.token_hash == hash);
+        (revoked, revoked)
+    })
+    .await
 }
 
 #[derive(Debug)]
@@ -48,16 +121,49 @@ impl Unauthorized {
 
 impl Reject for Unauthorized {}
 
-pub fn auth_validation() -> impl Filter<Extract = (), Error = warp::Rejection> + Copy {
-    warp::cookie::<String>("token")
-        .and_then(|cookie: String| async move {
-            if token_is_valid(&cookie) {
-                Ok(())
-            } else {
+#[derive(Debug)]
+pub struct Forbidden;
+
+impl Forbidden {
+    pub fn rejection() -> warp::Rejection {
+        warp::reject::custom(Forbidden)
+    }
+}
+
+impl Reject for Forbidden {}
+
+/// Extracts who the session's token belongs to, with the role they have now rather than at
+/// login. Ends the session if they've since been removed.
+pub fn auth_validation() -> impl Filter<Extract = (Identity,), Error = warp::Rejection> + Copy {
+    warp::cookie::<String>("token").and_then(|cookie: String| async move {
+        let session = session_for_token(&cookie).ok_or_else(Unauthorized::rejection)?;
+        match find_user(session.user.name()).await {
+            Ok(Some(user)) => Ok(user),
+            Ok(None) => {
+                info!(
+                    "Ended a session of {}, who is no longer a user.",
+                    session.user.name()
+                );
+                revoke_session(&session.id).await;
+                Err(Unauthorized::rejection())
+            }
+            Err(e) => {
+                error!("Could not look up {}: {}", session.user.name(), e);
                 Err(Unauthorized::rejection())
             }
-        })
-        .untuple_one()
+        }
+    })
+}
+
+/// Like [auth_validation], but forbids anyone who isn't an admin.
+pub fn admin_validation() -> impl Filter<Extract = (Identity,), Error = warp::Rejection> + Copy {
+    auth_validation().and_then(|user: Identity| async move {
+        if user.is_admin() {
+            Ok(user)
+        } else {
+            Err(Forbidden::rejection())
+        }
+    })
 }
 
 pub async fn get_token_path<Module>() -> Result<PathBuf, std::io::Error>
@@ -89,6 +195,11 @@ impl Token {
         }
     }
 
+    /// Replaces the token cookie with one that has already expired.
+    pub fn removal_cookie_string() -> String {
+        "token=; Path=/; HttpOnly; Max-Age=0; Secure".to_string()
+    }
+
     pub fn to_cookie_string(&self) -> String {
         format!(
             "token={}; Path={}; HttpOnly; Max-Age={}; Secure",
@@ -99,52 +210,190 @@ impl Token {
     }
 }
 
-struct TokenManager {
-    tokens_by_value: HashMap<String, Token>,
+#[cfg(test)]
+impl Token {
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+/// A logged in browser. Only a hash of its token is kept, so the sessions file can't be used to log
+/// in.
+#[derive(Clone, Debug, Deserialize, Getters, PartialEq, Serialize)]
+pub(crate) struct Session {
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    id: String,
+    #[getter(skip)]
+    token_hash: String,
+    user: Identity,
 }
 
-impl TokenManager {
+impl Session {
+    fn is_expired(&self) -> bool {
+        Utc::now() >= self.expires_at
+    }
+}
+
+struct SessionStore {
+    path: Option<PathBuf>,
+    sessions: Vec<Session>,
+}
+
+impl SessionStore {
     fn new() -> Self {
-        TokenManager {
-            tokens_by_value: HashMap::new(),
+        SessionStore {
+            path: None,
+            sessions: Vec::new(),
+        }
+    }
+
+    /// Adds the sessions saved at the path, and saves there from now on.
+    fn load(&mut self, path: PathBuf) -> Result<(), String> {
+        if let Some(folder) = path.parent() {
+            fs::create_dir_all(folder)
+                .map_err(|e| format!("Could not create {:?} because of {}", folder, e))?;
         }
+        self.path = Some(path.clone());
+        let mut sessions: Vec<Session> = match fs::read_to_string(&path) {
+            Ok(yaml) => serde_yaml::from_str(&yaml)
+                .map_err(|e| format!("Could not parse {:?} because of {}", path, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(format!("Could not read {:?} because of {}", path, e)),
+        };
+        sessions.append(&mut self.sessions);
+        sessions.sort_by_key(|session| session.created_at);
+        self.sessions = sessions;
+        self.purge_expired();
+        Ok(())
     }
 
-    fn put_token(&mut self, token: Token) -> Token {
-        self.tokens_by_value
-            .insert(token.value.clone(), token.clone());
+    /// Returns whether any had expired.
+    fn purge_expired(&mut self) -> bool {
+        self.revoke(|session| session.is_expired())
+    }
+
+    fn put_token(&mut self, token: Token, user: &Identity) -> Token {
+        self.sessions.push(Session {
+            created_at: Utc::now(),
+            expires_at: token.max_age,
+            id: thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(KEY_BYTES)
+                .map(char::from)
+                .collect(),
+            token_hash: hash_token(&token.value),
+            user: user.clone(),
+        });
         token
     }
 
-    fn token_is_valid(&self, token: &str) -> bool {
-        match self.tokens_by_value.get(token) {
-            Some(stored_token) => Utc::now() < stored_token.max_age,
-            None => false,
+    /// Removes the matching sessions, returning whether there were any.
+    fn revoke(&mut self, matches: impl Fn(&Session) -> bool) -> bool {
+        let count = self.sessions.len();
+        self.sessions.retain(|session| !matches(session));
+        self.sessions.len() != count
+    }
+
+    /// Where to save the sessions and what to write there, once they're loaded from somewhere.
+    fn serialize(&self) -> Option<(PathBuf, String)> {
+        let path = self.path.clone()?;
+        match serde_yaml::to_string(&self.sessions) {
+            Ok(yaml) => Some((path, yaml)),
+            Err(e) => {
+                error!("Could not serialize sessions: {}", e);
+                None
+            }
         }
     }
+
+    fn session_for_token(&self, token: &str) -> Option<Session> {
+        let hash = hash_token(token);
+        self.sessions
+            .iter()
+            .find(|session| session.token_hash == hash && !session.is_expired())
+            .cloned()
+    }
+
+    fn sessions(&self) -> Vec<Session> {
+        self.sessions
+            .iter()
+            .filter(|session| !session.is_expired())
+            .cloned()
+            .collect()
+    }
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    mod token_manager {
+    mod session_store {
         use super::*;
+        use crate::core::users::tests::test_admin;
+        use std::env::temp_dir;
         use std::time;
+        use uuid::Uuid;
 
         #[test]
-        fn test_token_manager() {
-            let mut token_manager = TokenManager::new();
-            let token = token_manager.put_token(Token::new("/", &Duration::from_secs(1)));
-            assert!(token_manager.token_is_valid(&token.value));
+        fn test_session_store() {
+            let mut session_store = SessionStore::new();
+            let token =
+                session_store.put_token(Token::new("/", &Duration::from_secs(1)), &test_admin());
+            assert!(session_store.session_for_token(&token.value).is_some());
         }
 
         #[test]
-        fn test_token_manager_expired() {
-            let mut token_manager = TokenManager::new();
-            let token = token_manager.put_token(Token::new("/", &Duration::from_millis(1)));
+        fn test_session_store_expired() {
+            let mut session_store = SessionStore::new();
+            let token =
+                session_store.put_token(Token::new("/", &Duration::from_millis(1)), &test_admin());
             std::thread::sleep(time::Duration::from_millis(2));
-            assert!(!token_manager.token_is_valid(&token.value));
+            assert!(session_store.session_for_token(&token.value).is_none());
+
+            session_store.purge_expired();
+            assert!(session_store.sessions.is_empty());
+        }
+
+        #[test]
+        fn test_revoked_tokens_are_invalid() {
+            let mut session_store = SessionStore::new();
+            let token =
+                session_store.put_token(Token::new("/", &Duration::from_secs(60)), &test_admin());
+            let other =
+                session_store.put_token(Token::new("/", &Duration::from_secs(60)), &test_admin());
+            let id = session_store.session_for_token(&token.value).unwrap().id;
+
+            assert!(session_store.revoke(|session| session.id == id));
+
+            assert!(session_store.session_for_token(&token.value).is_none());
+            assert!(session_store.session_for_token(&other.value).is_some());
+        }
+
+        #[tokio::test]
+        async fn test_sessions_survive_reloading() {
+            let path = temp_dir().join(format!("cloud-scraper-sessions-{}.yaml", Uuid::new_v4()));
+            let mut session_store = SessionStore::new();
+            session_store.load(path.clone()).unwrap();
+            let token =
+                session_store.put_token(Token::new("/", &Duration::from_secs(60)), &test_admin());
+            let (saved_path, yaml) = session_store.serialize().unwrap();
+            save_sessions(&saved_path, yaml).await;
+
+            let mut reloaded = SessionStore::new();
+            reloaded.load(path.clone()).unwrap();
+            let yaml = fs::read_to_string(&path).unwrap();
+            let _ = fs::remove_file(&path);
+
+            assert_eq!(
+                reloaded.session_for_token(&token.value).unwrap().user,
+                test_admin()
+            );
+            assert!(!yaml.contains(&token.value));
         }
     }
 }
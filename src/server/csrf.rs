@@ -0,0 +1,182 @@
+use crate::server::page::format_error_html;
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use subtle::ConstantTimeEq;
+use warp::http::header::SET_COOKIE;
+use warp::reject::Reject;
+use warp::{reply, Filter, Rejection, Reply};
+
+const CSRF_COOKIE: &str = "csrf";
+const CSRF_FIELD: &str = "csrf_token";
+const TOKEN_LENGTH: usize = 32;
+
+#[derive(Debug)]
+pub struct CsrfMismatch;
+
+impl Reject for CsrfMismatch {}
+
+/// The double-submit token for the forms on a page. The browser's existing token is reused, so
+/// forms in other tabs stay valid.
+pub fn csrf_token() -> impl Filter<Extract = (String,), Error = Infallible> + Clone {
+    warp::cookie::optional::<String>(CSRF_COOKIE).map(|cookie: Option<String>| {
+        cookie
+            .filter(|token| is_well_formed(token))
+            .unwrap_or_else(new_token)
+    })
+}
+
+/// Sets the cookie that forms rendered with the token are checked against.
+pub fn with_csrf_cookie(reply: impl Reply, token: &str) -> impl Reply {
+    reply::with_header(
+        reply,
+        SET_COOKIE,
+        format!(
+            "{}={}; Path=/; HttpOnly; SameSite=Strict; Secure",
+            CSRF_COOKIE, token
+        ),
+    )
+}
+
+/// The posted form, once its token matches the cookie. The token field is removed.
+pub fn csrf_form() -> impl Filter<Extract = (HashMap<String, String>,), Error = Rejection> + Clone {
+    warp::cookie::optional::<String>(CSRF_COOKIE)
+        .and(warp::body::form())
+        .and_then(
+            |cookie: Option<String>, mut form: HashMap<String, String>| async move {
+                let submitted = form.remove(CSRF_FIELD);
+                match (cookie, submitted) {
+                    (Some(cookie), Some(submitted))
+                        if is_well_formed(&cookie)
+                            && bool::from(cookie.as_bytes().ct_eq(submitted.as_bytes())) =>
+                    {
+                        Ok(form)
+                    }
+                    _ => {
+                        log::warn!("Rejected a form post with a missing or wrong CSRF token.");
+                        Err(warp::reject::custom(CsrfMismatch))
+                    }
+                }
+            },
+        )
+}
+
+pub fn format_forbidden_html() -> String {
+    format_error_html(
+        "Forbidden",
+        "The form has expired or didn't come from this site. Go back, reload it and try again.",
+    )
+}
+
+fn is_well_formed(token: &str) -> bool {
+    token.len() == TOKEN_LENGTH && token.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn new_token() -> String {
+    thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use warp::http::header::COOKIE;
+    use warp::test::request;
+
+    pub(crate) const TEST_CSRF_TOKEN: &str = "0123456789abcdefghijklmnopqrstuv";
+
+    pub(crate) fn csrf_cookie() -> String {
+        format!("{}={}", CSRF_COOKIE, TEST_CSRF_TOKEN)
+    }
+
+    pub(crate) fn csrf_field() -> String {
+        format!("{}={}", CSRF_FIELD, TEST_CSRF_TOKEN)
+    }
+
+    mod csrf_token {
+        use super::*;
+
+        #[tokio::test]
+        async fn reuses_the_cookie() {
+            let token = warp::test::request()
+                .header(COOKIE, csrf_cookie())
+                .filter(&csrf_token())
+                .await
+                .unwrap();
+
+            assert_eq!(token, TEST_CSRF_TOKEN);
+        }
+
+        #[tokio::test]
+        async fn replaces_a_malformed_cookie() {
+            let token = warp::test::request()
+                .header(COOKIE, "csrf=short")
+                .filter(&csrf_token())
+                .await
+                .unwrap();
+
+            assert!(is_well_formed(&token));
+            assert_ne!(token, "short");
+        }
+    }
+
+    mod csrf_form {
+        use super::*;
+
+        fn post(cookie: Option<String>, body: String) -> warp::test::RequestBuilder {
+            let builder = request().method("POST").body(body);
+            match cookie {
+                Some(cookie) => builder.header(COOKIE, cookie),
+                None => builder,
+            }
+        }
+
+        #[tokio::test]
+        async fn matching_token_passes_the_form_on() {
+            let form = post(Some(csrf_cookie()), format!("id=1&{}", csrf_field()))
+                .filter(&csrf_form())
+                .await
+                .unwrap();
+
+            assert_eq!(form.get("id").unwrap(), "1");
+            assert!(!form.contains_key(CSRF_FIELD));
+        }
+
+        #[tokio::test]
+        async fn missing_cookie_is_rejected() {
+            let rejection = post(None, format!("id=1&{}", csrf_field()))
+                .filter(&csrf_form())
+                .await
+                .unwrap_err();
+
+            assert!(rejection.find::<CsrfMismatch>().is_some());
+        }
+
+        #[tokio::test]
+        async fn missing_field_is_rejected() {
+            let rejection = post(Some(csrf_cookie()), "id=1".to_string())
+                .filter(&csrf_form())
+                .await
+                .unwrap_err();
+
+            assert!(rejection.find::<CsrfMismatch>().is_some());
+        }
+
+        #[tokio::test]
+        async fn different_token_is_rejected() {
+            let rejection = post(
+                Some(csrf_cookie()),
+                format!("id=1&{}=vutsrqponmlkjihgfedcba9876543210", CSRF_FIELD),
+            )
+            .filter(&csrf_form())
+            .await
+            .unwrap_err();
+
+            assert!(rejection.find::<CsrfMismatch>().is_some());
+        }
+    }
+}
@@ -5,6 +5,7 @@ use warp::reject::Reject;
 
 #[derive(Debug)]
 pub enum Rejection {
+    ConfigRejection(String),
     SendRejection(String),
 }
 
@@ -13,6 +14,7 @@ impl Reject for Rejection {}
 impl fmt::Display for Rejection {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Rejection::ConfigRejection(e) => write!(f, "Config error: {}", e),
             Rejection::SendRejection(e) => write!(f, "Send error: {}", e),
         }
     }
@@ -58,6 +60,7 @@ mod tests {
             Rejection::SendRejection(message) => {
                 assert_eq!(message, expected_message.to_string());
             }
+            other => panic!("Expected a send rejection, got {}", other),
         }
     }
 
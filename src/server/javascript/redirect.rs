@@ -41,7 +41,7 @@ pub trait WithRedirect {
 impl WithRedirect for HashMap<&str, String> {
     fn with_redirect_script(mut self, handles: &NodeHandles) -> Self {
         let core_config = handles.lifecycle_manager().core_config();
-        self.insert("redirect_script", redirect(core_config));
+        self.insert("redirect_script", redirect(&core_config));
         self
     }
 }
@@ -0,0 +1,314 @@
+use crate::domain::login_throttling::LoginThrottling;
+use log::warn;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use warp::reject::Reject;
+
+#[derive(Debug)]
+pub struct LoginThrottled {
+    retry_after: Duration,
+}
+
+impl LoginThrottled {
+    pub fn rejection(retry_after: Duration) -> warp::Rejection {
+        warp::reject::custom(Self { retry_after })
+    }
+
+    /// Whole seconds, rounded up, for the `Retry-After` header.
+    pub fn retry_after_seconds(&self) -> u64 {
+        self.retry_after.as_secs() + u64::from(self.retry_after.subsec_nanos() > 0)
+    }
+}
+
+impl Reject for LoginThrottled {}
+
+#[derive(Debug)]
+struct Failures {
+    count: u32,
+    /// Attempts let through that haven't succeeded or failed yet.
+    in_flight: u32,
+    last_failed_at: Instant,
+    retry_at: Instant,
+}
+
+impl Failures {
+    fn new(now: Instant) -> Self {
+        Self {
+            count: 0,
+            in_flight: 0,
+            last_failed_at: now,
+            retry_at: now,
+        }
+    }
+
+    fn record(&mut self, now: Instant, window: Duration) {
+        if now.duration_since(self.last_failed_at) > window {
+            self.count = 0;
+        }
+        self.count += 1;
+        self.last_failed_at = now;
+    }
+
+    fn wait(&self, now: Instant) -> Option<Duration> {
+        (self.retry_at > now).then(|| self.retry_at - now)
+    }
+}
+
+#[derive(Debug)]
+struct Attempts {
+    by_ip: HashMap<IpAddr, Failures>,
+    global: Failures,
+}
+
+/// Tracks failed logins, so the login form can refuse attempts that come too soon.
+#[derive(Debug)]
+pub struct LoginThrottle {
+    attempts: Mutex<Attempts>,
+    settings: LoginThrottling,
+}
+
+impl LoginThrottle {
+    pub fn new(settings: LoginThrottling) -> Self {
+        Self {
+            attempts: Mutex::new(Attempts {
+                by_ip: HashMap::new(),
+                global: Failures::new(Instant::now()),
+            }),
+            settings,
+        }
+    }
+
+    /// Lets the address try to log in, or says how long it must wait first.
+    ///
+    /// Only one attempt per address is let through at a time, so attempts sent together can't all
+    /// get in before the first of them fails.
+    pub fn attempt(self: &Arc<Self>, ip: IpAddr) -> Result<LoginAttempt, Duration> {
+        self.attempt_at(ip, Instant::now())?;
+        Ok(LoginAttempt {
+            ip,
+            settled: false,
+            throttle: self.clone(),
+        })
+    }
+
+    fn attempt_at(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        let mut attempts = self.attempts.lock();
+        if let Some(wait) = Self::wait(&attempts, ip, now) {
+            return Err(wait);
+        }
+        let failures = attempts
+            .by_ip
+            .entry(ip)
+            .or_insert_with(|| Failures::new(now));
+        if failures.in_flight > 0 {
+            // However the attempt in flight ends, this one would have to wait at least this long.
+            return Err(self.settings.delay(failures.count + 1));
+        }
+        failures.in_flight += 1;
+        Ok(())
+    }
+
+    #[cfg(test)]
+    fn check_at(&self, ip: IpAddr, now: Instant) -> Option<Duration> {
+        Self::wait(&self.attempts.lock(), ip, now)
+    }
+
+    fn wait(attempts: &Attempts, ip: IpAddr, now: Instant) -> Option<Duration> {
+        let ip_wait = attempts
+            .by_ip
+            .get(&ip)
+            .and_then(|failures| failures.wait(now));
+        attempts.global.wait(now).max(ip_wait)
+    }
+
+    fn succeeded(&self, ip: IpAddr) {
+        self.attempts.lock().by_ip.remove(&ip);
+    }
+
+    fn failed_at(&self, ip: IpAddr, now: Instant) {
+        let settings = &self.settings;
+        let window = settings.failure_window();
+        let mut attempts = self.attempts.lock();
+        attempts.by_ip.retain(|_, failures| {
+            failures.in_flight > 0
+                || failures.wait(now).is_some()
+                || failures.last_failed_at + window > now
+        });
+
+        let failures = attempts
+            .by_ip
+            .entry(ip)
+            .or_insert_with(|| Failures::new(now));
+        failures.in_flight = failures.in_flight.saturating_sub(1);
+        failures.record(now, window);
+        if failures.count >= settings.ip_lockout_failures() {
+            let lockout = settings.ip_lockout();
+            warn!(
+                "Locked out logins from {} for {:?} after {} failures.",
+                ip, lockout, failures.count
+            );
+            failures.count = 0;
+            failures.retry_at = now + lockout;
+        } else {
+            failures.retry_at = now + settings.delay(failures.count);
+        }
+
+        let global = &mut attempts.global;
+        global.record(now, window);
+        if global.count >= settings.global_lockout_failures() {
+            let lockout = settings.global_lockout();
+            warn!(
+                "Locked out all logins for {:?} after {} failures.",
+                lockout, global.count
+            );
+            global.count = 0;
+            global.retry_at = now + lockout;
+        }
+    }
+}
+
+/// A login attempt let through the throttle. It counts as failed unless it's said to have
+/// succeeded, so dropping the request part way doesn't get it off.
+#[derive(Debug)]
+pub struct LoginAttempt {
+    ip: IpAddr,
+    settled: bool,
+    throttle: Arc<LoginThrottle>,
+}
+
+impl LoginAttempt {
+    pub fn succeeded(mut self) {
+        self.settled = true;
+        self.throttle.succeeded(self.ip);
+    }
+
+    pub fn failed(self) {}
+}
+
+impl Drop for LoginAttempt {
+    fn drop(&mut self) {
+        if !self.settled {
+            self.throttle.failed_at(self.ip, Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    const ATTACKER: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+    const OWNER: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2));
+
+    fn throttle(yaml: &str) -> LoginThrottle {
+        LoginThrottle::new(serde_yaml::from_str(yaml).expect("Could not parse login throttling"))
+    }
+
+    #[test]
+    fn test_failures_delay_only_that_address() {
+        let throttle = throttle("initial_delay_millis: 1000");
+        let now = Instant::now();
+
+        throttle.failed_at(ATTACKER, now);
+        throttle.failed_at(ATTACKER, now);
+
+        assert_eq!(
+            throttle.check_at(ATTACKER, now),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            throttle.check_at(ATTACKER, now + Duration::from_secs(1)),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            throttle.check_at(ATTACKER, now + Duration::from_secs(2)),
+            None
+        );
+        assert_eq!(throttle.check_at(OWNER, now), None);
+    }
+
+    #[test]
+    fn test_address_is_locked_out_after_too_many_failures() {
+        let throttle = throttle("ip_lockout_failures: 3\nip_lockout_seconds: 60");
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            throttle.failed_at(ATTACKER, now);
+        }
+
+        assert_eq!(
+            throttle.check_at(ATTACKER, now),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            throttle.check_at(ATTACKER, now + Duration::from_secs(60)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_everyone_is_locked_out_after_too_many_failures() {
+        let throttle = throttle("global_lockout_failures: 2\nglobal_lockout_seconds: 60");
+        let now = Instant::now();
+
+        throttle.failed_at(ATTACKER, now);
+        throttle.failed_at(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 3)), now);
+
+        assert_eq!(throttle.check_at(OWNER, now), Some(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_old_failures_are_forgotten() {
+        let throttle = throttle("failure_window_seconds: 60\nip_lockout_failures: 2");
+        let now = Instant::now();
+
+        throttle.failed_at(ATTACKER, now);
+        let later = now + Duration::from_secs(61);
+        throttle.failed_at(ATTACKER, later);
+
+        assert_eq!(
+            throttle.check_at(ATTACKER, later),
+            Some(Duration::from_millis(500))
+        );
+    }
+
+    #[test]
+    fn test_success_clears_the_address() {
+        let throttle = LoginThrottle::new(LoginThrottling::default());
+        let now = Instant::now();
+
+        throttle.failed_at(ATTACKER, now);
+        throttle.succeeded(ATTACKER);
+
+        assert_eq!(throttle.check_at(ATTACKER, now), None);
+    }
+
+    #[test]
+    fn test_only_one_attempt_per_address_at_a_time() {
+        let throttle = Arc::new(throttle("initial_delay_millis: 1000"));
+
+        let attempt = throttle.attempt(ATTACKER).unwrap();
+
+        assert_eq!(
+            throttle.attempt(ATTACKER).unwrap_err(),
+            Duration::from_secs(1)
+        );
+        assert!(throttle.attempt(OWNER).is_ok());
+        attempt.succeeded();
+        assert!(throttle.attempt(ATTACKER).is_ok());
+    }
+
+    #[test]
+    fn test_dropped_attempts_count_as_failed() {
+        let throttle = Arc::new(throttle("initial_delay_millis: 1000"));
+
+        drop(throttle.attempt(ATTACKER).unwrap());
+
+        assert!(throttle.check_at(ATTACKER, Instant::now()).is_some());
+    }
+}
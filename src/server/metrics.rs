@@ -0,0 +1,12 @@
+use crate::core::metrics::render;
+use warp::http::header::CONTENT_TYPE;
+use warp::{reply, Filter, Rejection, Reply};
+
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Unauthenticated, so Prometheus can scrape it. It only exposes counts and durations.
+pub fn metrics() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("metrics")
+        .and(warp::get())
+        .map(|| reply::with_header(render(), CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE))
+}
@@ -1,13 +1,18 @@
 mod acme;
 pub(crate) mod auth;
+pub(crate) mod csrf;
 pub(crate) mod errors;
 mod events;
 pub(crate) mod javascript;
+pub(crate) mod login_throttle;
+mod metrics;
+mod nodes;
 mod oauth2;
 mod page;
 mod root;
 mod routes;
 mod site_state;
+mod status;
 mod web_server;
 mod websocket;
 
@@ -0,0 +1,193 @@
+use crate::core::node_handles::NodeHandles;
+use crate::core::users::Identity;
+use crate::domain::node::Manager;
+use crate::server::auth::admin_validation;
+use crate::server::csrf::csrf_form;
+use crate::server::errors::{Rejectable, Rejection as ServerRejection};
+use std::collections::HashMap;
+use warp::{Filter, Rejection, Reply};
+
+const ROOT_PATH: &str = "/";
+
+/// Lets admins pause and resume a source or sink by name, and reload the config, without
+/// restarting the service.
+pub fn nodes(
+    handles: &NodeHandles,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let pause_manager = handles.lifecycle_manager().clone();
+    let resume_manager = handles.lifecycle_manager().clone();
+    let reload_manager = handles.lifecycle_manager().clone();
+    warp::path!("nodes" / "pause")
+        .and(warp::post())
+        .and(admin_validation())
+        .and(csrf_form())
+        .and_then(move |admin: Identity, form: HashMap<String, String>| {
+            let manager = pause_manager.clone();
+            async move { send_to_node(manager, admin, form, "paused", Manager::send_pause_to) }
+        })
+        .or(warp::path!("nodes" / "resume")
+            .and(warp::post())
+            .and(admin_validation())
+            .and(csrf_form())
+            .and_then(move |admin: Identity, form: HashMap<String, String>| {
+                let manager = resume_manager.clone();
+                async move {
+                    send_to_node(manager, admin, form, "resumed", Manager::send_resume_to)
+                }
+            }))
+        .or(warp::path!("config" / "reload")
+            .and(warp::post())
+            .and(admin_validation())
+            .and(csrf_form())
+            .and_then(move |admin: Identity, _form: HashMap<String, String>| {
+                let mut manager = reload_manager.clone();
+                async move {
+                    match manager.send_reload_config() {
+                        Ok(_) => {
+                            log::info!("{} reloaded the config.", admin.name());
+                            Ok(redirect_to_root())
+                        }
+                        Err(e) => {
+                            log::error!("Could not reload the config: {}", e);
+                            Err(warp::reject::custom(ServerRejection::ConfigRejection(e)))
+                        }
+                    }
+                }
+            }))
+}
+
+fn send_to_node<SendError: Rejectable>(
+    mut manager: Manager,
+    admin: Identity,
+    form: HashMap<String, String>,
+    done: &str,
+    send: impl Fn(&mut Manager, &str) -> Result<bool, SendError>,
+) -> Result<impl Reply, Rejection> {
+    let node = form.get("node").ok_or_else(warp::reject::not_found)?;
+    match send(&mut manager, node) {
+        Ok(true) => {
+            log::info!("{} {} {}.", admin.name(), done, node);
+            Ok(redirect_to_root())
+        }
+        Ok(false) => Err(warp::reject::not_found()),
+        Err(e) => Err(e.into_rejection()),
+    }
+}
+
+fn redirect_to_root() -> impl Reply {
+    warp::redirect::found(warp::http::Uri::from_static(ROOT_PATH))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::node_handles::tests::get_test_node_handles;
+    use crate::core::users::tests::{test_admin, test_viewer, with_test_users_scope};
+    use crate::domain::node::Lifecycle::{Pause, Resume};
+    use crate::integration::google::Source;
+    use crate::server::auth::gen_token_for_path;
+    use crate::server::csrf::tests::{csrf_cookie, csrf_field};
+    use crate::server::page::handlers;
+    use std::any::TypeId;
+    use warp::http::header::{COOKIE, LOCATION};
+    use warp::http::StatusCode;
+    use warp::test::request;
+
+    fn filter(
+        handles: &NodeHandles,
+    ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+        nodes(handles).recover(handlers::handle_rejection)
+    }
+
+    async fn post(handles: &NodeHandles, user: &Identity, path: &str, body: &str) -> StatusCode {
+        let token = gen_token_for_path("/", user).await;
+        request()
+            .method("POST")
+            .header(
+                COOKIE,
+                format!("{}; {}", token.to_cookie_string(), csrf_cookie()),
+            )
+            .path(path)
+            .body(format!("{}&{}", body, csrf_field()))
+            .reply(&filter(handles))
+            .await
+            .status()
+    }
+
+    #[tokio::test]
+    async fn pauses_and_resumes_a_node_by_name() {
+        let _scope = with_test_users_scope().await;
+        let handles = get_test_node_handles();
+        handles.lifecycle_manager().register_node::<Source>();
+        let mut receiver = handles.lifecycle_manager().readonly().get_receiver();
+
+        let token = gen_token_for_path("/", &test_admin()).await;
+        let res = request()
+            .method("POST")
+            .header(
+                COOKIE,
+                format!("{}; {}", token.to_cookie_string(), csrf_cookie()),
+            )
+            .path("/nodes/pause")
+            .body(format!("node=google&{}", csrf_field()))
+            .reply(&filter(&handles))
+            .await;
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(res.headers().get(LOCATION).unwrap(), ROOT_PATH);
+        assert_eq!(
+            post(&handles, &test_admin(), "/nodes/resume", "node=google").await,
+            StatusCode::FOUND
+        );
+
+        let google = TypeId::of::<Source>();
+        assert_eq!(receiver.recv().await.unwrap(), Pause(google));
+        assert_eq!(receiver.recv().await.unwrap(), Resume(google));
+    }
+
+    #[tokio::test]
+    async fn unknown_nodes_are_not_found() {
+        let _scope = with_test_users_scope().await;
+        let handles = get_test_node_handles();
+
+        assert_eq!(
+            post(&handles, &test_admin(), "/nodes/pause", "node=nothing").await,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn viewers_are_forbidden() {
+        let _scope = with_test_users_scope().await;
+        let handles = get_test_node_handles();
+        handles.lifecycle_manager().register_node::<Source>();
+
+        for (path, body) in [
+            ("/nodes/pause", "node=google"),
+            ("/nodes/resume", "node=google"),
+            ("/config/reload", ""),
+        ] {
+            assert_eq!(
+                post(&handles, &test_viewer(), path, body).await,
+                StatusCode::FORBIDDEN
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn without_csrf_token_is_rejected() {
+        let _scope = with_test_users_scope().await;
+        let handles = get_test_node_handles();
+        handles.lifecycle_manager().register_node::<Source>();
+        let token = gen_token_for_path("/", &test_admin()).await;
+
+        let res = request()
+            .method("POST")
+            .header(COOKIE, token.to_cookie_string())
+            .path("/nodes/pause")
+            .body("node=google")
+            .reply(&filter(&handles))
+            .await;
+
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    }
+}
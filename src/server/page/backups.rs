@@ -0,0 +1,195 @@
+use crate::core::node_handles::NodeHandles;
+use crate::core::users::Identity;
+use crate::domain::entity::Entity;
+use crate::integration::store::EntityStore;
+use crate::server::auth::auth_validation;
+use crate::static_init::error::Error;
+use handlebars::Handlebars;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use serde_yaml::Value;
+use warp::{reply, Filter, Rejection};
+
+const BACKUPS_TEMPLATE: &str = "backups";
+const BACKUPS: &str = "backups";
+
+lazy_static! {
+    pub static ref PAGE_TEMPLATE: Handlebars<'static> = {
+        let mut handlebars = Handlebars::new();
+        handlebars
+            .register_template_string(
+                BACKUPS_TEMPLATE,
+                include_str!("../../../resources/html/backups.html"),
+            )
+            .expect("Could not register backups template");
+        handlebars
+    };
+}
+
+#[derive(Serialize)]
+struct EntityRow {
+    created_at: String,
+    data: String,
+    id: String,
+    updated_at: String,
+}
+
+#[derive(Default, Serialize)]
+struct BackupsPage {
+    entities: Vec<EntityRow>,
+    source: Option<String>,
+    sources: Vec<String>,
+}
+
+/// Read-only pages of the stored entities, which anyone logged in can browse.
+pub fn backups_pages(
+    handles: &NodeHandles,
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    pages(EntityStore::new(&handles.lifecycle_manager().core_config()))
+}
+
+fn pages(store: EntityStore) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    let sources_store = store.clone();
+    warp::path(BACKUPS)
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(auth_validation())
+        .and_then(move |_user: Identity| {
+            let store = sources_store.clone();
+            async move { format_sources_html(&store).await.map(reply::html) }
+        })
+        .or(warp::path(BACKUPS)
+            .and(warp::path::param::<String>())
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(auth_validation())
+            .and_then(move |source: String, _user: Identity| {
+                let store = store.clone();
+                async move { format_source_html(&store, &source).await.map(reply::html) }
+            }))
+}
+
+async fn format_sources_html(store: &EntityStore) -> Result<String, Rejection> {
+    let page = BackupsPage {
+        sources: store.sources().await.map_err(rejection)?,
+        ..Default::default()
+    };
+    Ok(render(&page))
+}
+
+/// The source's entities in the same order as export writes them.
+async fn format_source_html(store: &EntityStore, source: &str) -> Result<String, Rejection> {
+    // Only sources already in the store, so the name can't lead anywhere else on disk.
+    if !store
+        .sources()
+        .await
+        .map_err(rejection)?
+        .iter()
+        .any(|stored| stored == source)
+    {
+        return Err(warp::reject::not_found());
+    }
+
+    let mut entities: Vec<Entity<Value>> = store.entities(source).await.map_err(rejection)?;
+    entities.sort_by(|a, b| {
+        a.updated_at()
+            .cmp(&b.updated_at())
+            .then_with(|| a.id().cmp(b.id()))
+    });
+    let page = BackupsPage {
+        entities: entities
+            .iter()
+            .map(|entity| EntityRow {
+                created_at: entity.created_at().to_rfc3339(),
+                data: match entity.data() {
+                    Value::String(data) => data.clone(),
+                    data => serde_yaml::to_string(data).unwrap_or_else(|e| e.to_string()),
+                },
+                id: entity.id().to_string(),
+                updated_at: entity.updated_at().to_rfc3339(),
+            })
+            .collect(),
+        source: Some(source.to_string()),
+        ..Default::default()
+    };
+    Ok(render(&page))
+}
+
+fn rejection(error: Error) -> Rejection {
+    log::error!("Could not read backups: {}", error);
+    warp::reject::custom(error)
+}
+
+fn render(page: &BackupsPage) -> String {
+    PAGE_TEMPLATE
+        .render(BACKUPS_TEMPLATE, page)
+        .expect("Could not render backups template")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::users::tests::{test_viewer, with_test_users_scope};
+    use crate::integration::store::TestRoot;
+    use crate::server::auth::gen_token_for_path;
+    use chrono::{TimeZone, Utc};
+    use warp::http::header::COOKIE;
+    use warp::http::StatusCode;
+    use warp::test::request;
+
+    #[tokio::test]
+    async fn viewers_browse_sources_and_their_entities() {
+        let _scope = with_test_users_scope().await;
+        let root = TestRoot::new();
+        let store = root.store();
+        let at = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        store
+            .put(
+                "stub",
+                &Entity::new(at, "<b>task</b>".to_string(), "id", at),
+            )
+            .await
+            .unwrap();
+        let token = gen_token_for_path("/", &test_viewer()).await;
+        let filter = pages(store);
+
+        let res = request()
+            .method("GET")
+            .header(COOKIE, token.to_cookie_string())
+            .path("/backups")
+            .reply(&filter)
+            .await;
+
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = String::from_utf8(res.body().to_vec()).unwrap();
+        assert!(body.contains(r#"<a href="/backups/stub">stub</a>"#));
+
+        let res = request()
+            .method("GET")
+            .header(COOKIE, token.to_cookie_string())
+            .path("/backups/stub")
+            .reply(&filter)
+            .await;
+
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = String::from_utf8(res.body().to_vec()).unwrap();
+        assert!(body.contains("<td>2024-06-01T00:00:00+00:00</td>"));
+        assert!(body.contains("<pre>&lt;b&gt;task&lt;/b&gt;</pre>"));
+    }
+
+    #[tokio::test]
+    async fn unknown_sources_are_not_found() {
+        let _scope = with_test_users_scope().await;
+        let root = TestRoot::new();
+        let token = gen_token_for_path("/", &test_viewer()).await;
+
+        let res = request()
+            .method("GET")
+            .header(COOKIE, token.to_cookie_string())
+            .path("/backups/..")
+            .reply(&pages(root.store()))
+            .await;
+
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
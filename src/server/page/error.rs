@@ -0,0 +1,28 @@
+use handlebars::Handlebars;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+
+const ERROR_TEMPLATE: &str = "error";
+
+lazy_static! {
+    pub static ref PAGE_TEMPLATE: Handlebars<'static> = {
+        let mut handlebars = Handlebars::new();
+        handlebars
+            .register_template_string(
+                ERROR_TEMPLATE,
+                include_str!("../../../resources/html/error.html"),
+            )
+            .expect("Could not register error template");
+        handlebars
+    };
+}
+
+pub fn format_error_html(title: &str, message: &str) -> String {
+    let mut page_data = HashMap::new();
+    page_data.insert("title", title);
+    page_data.insert("message", message);
+
+    PAGE_TEMPLATE
+        .render(ERROR_TEMPLATE, &page_data)
+        .expect("Could not render error template")
+}
@@ -1,9 +1,16 @@
-use crate::core::root_password::check_root_password;
+use crate::core::metrics;
+use crate::core::metrics::Metric;
+use crate::core::node_handles::NodeHandles;
+use crate::core::users::{check_login, Identity};
 use crate::server::auth::auth_validation;
+use crate::server::csrf::{csrf_form, csrf_token, with_csrf_cookie};
+use crate::server::login_throttle::LoginThrottle;
 use handlebars::Handlebars;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
 use warp::http::header::LOCATION;
 use warp::{reply, Filter};
 
@@ -36,26 +43,44 @@ impl LoginQuery {
     }
 }
 
-pub fn login() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+pub fn login(
+    handles: &NodeHandles,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let throttle = Arc::new(LoginThrottle::new(
+        handles.lifecycle_manager().core_config().login_throttling(),
+    ));
+    let handles = handles.clone();
     warp::path(LOGIN)
         .and(warp::path::end())
         .and(warp::get())
         .and(auth_validation())
-        .map(|| warp::redirect::found(warp::http::Uri::from_static("/")))
+        .map(|_user: Identity| warp::redirect::found(warp::http::Uri::from_static("/")))
         .or(warp::path(LOGIN)
             .and(warp::path::end())
             .and(warp::get())
             .and(warp::query::<LoginQuery>())
-            .map(move |query: LoginQuery| reply::html(format_login_html(query.failed()))))
+            .and(csrf_token())
+            .map(move |query: LoginQuery, csrf_token: String| {
+                with_csrf_cookie(
+                    reply::html(format_login_html(query.failed(), &csrf_token)),
+                    &csrf_token,
+                )
+            }))
         .or(warp::path(LOGIN)
             .and(warp::path::end())
             .and(warp::post())
-            .and(warp::body::form())
-            .and_then(handlers::check_root_password)
-            .map(handlers::issue_token_and_redirect))
+            .and(warp::addr::remote())
+            .and(csrf_form())
+            .map(move |remote: Option<SocketAddr>, form_map| {
+                // Without a remote address, attempts share one unknown one.
+                let ip = remote.map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |addr| addr.ip());
+                handlers::check_login(handles.clone(), throttle.clone(), ip, form_map)
+            })
+            .and_then(|future| future)
+            .and_then(handlers::issue_token_and_redirect))
 }
 
-pub fn format_login_html(failed: bool) -> String {
+pub fn format_login_html(failed: bool, csrf_token: &str) -> String {
     let mut page_data = HashMap::new();
     page_data.insert(
         "error_html",
@@ -65,55 +90,110 @@ pub fn format_login_html(failed: bool) -> String {
             r""
         },
     );
+    page_data.insert("csrf_token", csrf_token);
 
     PAGE_TEMPLATE
         .render(LOGIN_TEMPLATE, &page_data)
         .expect("Could not render login template")
 }
 
-async fn root_password_is_good(map: HashMap<String, String>) -> bool {
-    match map.get("password") {
-        Some(password) => check_root_password(password).await.unwrap_or(false),
-        None => false,
+async fn logged_in_user(handles: &NodeHandles, map: HashMap<String, String>) -> Option<Identity> {
+    let hashing = handles.lifecycle_manager().core_config().password_hashing();
+    match (map.get("username"), map.get("password")) {
+        (Some(name), Some(password)) => {
+            check_login(name, password, &hashing)
+                .await
+                .unwrap_or_else(|e| {
+                    log::error!("Could not check the login: {}", e);
+                    None
+                })
+        }
+        _ => None,
     }
 }
 
 pub mod handlers {
     use super::*;
-    use crate::server::auth::{gen_token_for_path, Unauthorized};
-    use warp::http::header::SET_COOKIE;
+    use crate::server::auth::{gen_token_for_path, Forbidden, Unauthorized};
+    use crate::server::csrf::{format_forbidden_html, CsrfMismatch};
+    use crate::server::login_throttle::LoginThrottled;
+    use crate::server::page::format_error_html;
+    use warp::http::header::{RETRY_AFTER, SET_COOKIE};
     use warp::http::StatusCode;
     use warp::reject::InvalidHeader;
     use warp::Rejection;
 
-    pub async fn check_root_password(
+    pub async fn check_login(
+        handles: NodeHandles,
+        throttle: Arc<LoginThrottle>,
+        ip: IpAddr,
         form_map: HashMap<String, String>,
-    ) -> Result<impl warp::Reply, Rejection> {
-        if root_password_is_good(form_map).await {
-            log::info!("Successfully logged in.");
-            Ok(reply::html(""))
+    ) -> Result<Identity, Rejection> {
+        let attempt = match throttle.attempt(ip) {
+            Ok(attempt) => attempt,
+            Err(retry_after) => {
+                log::warn!("Refused a login from {} for another {:?}.", ip, retry_after);
+                return Err(LoginThrottled::rejection(retry_after));
+            }
+        };
+
+        if let Some(user) = logged_in_user(&handles, form_map).await {
+            log::info!("{} successfully logged in.", user.name());
+            attempt.succeeded();
+            Ok(user)
         } else {
-            log::warn!("Failed to login because of bad password.");
+            log::warn!("Failed to login from {} because of bad credentials.", ip);
+            metrics::increment(Metric::LoginFailures, &[]);
+            attempt.failed();
             Err(Unauthorized::rejection())
         }
     }
 
-    pub fn issue_token_and_redirect(reply: impl warp::Reply + Sized) -> impl warp::Reply {
-        let token = gen_token_for_path("/");
-        reply::with_status(
+    pub async fn issue_token_and_redirect(user: Identity) -> Result<impl warp::Reply, Rejection> {
+        let token = gen_token_for_path("/", &user).await;
+        Ok(reply::with_status(
             reply::with_header(
-                reply::with_header(reply, SET_COOKIE, token.to_cookie_string()),
+                reply::with_header(reply::html(""), SET_COOKIE, token.to_cookie_string()),
                 LOCATION,
                 "/",
             ),
             StatusCode::FOUND,
-        )
+        ))
     }
 
     pub async fn handle_rejection(rejection: Rejection) -> Result<impl warp::Reply, Rejection> {
         let mut redirection: Option<Box<dyn warp::Reply>> = None;
 
-        if let Some(invalid_header) = rejection.find::<InvalidHeader>() {
+        if let Some(throttled) = rejection.find::<LoginThrottled>() {
+            let retry_after = throttled.retry_after_seconds();
+            redirection = Some(Box::new(reply::with_status(
+                reply::with_header(
+                    reply::html(format_error_html(
+                        "Too Many Logins",
+                        &format!(
+                            "Too many logins have failed. Try again in {} seconds.",
+                            retry_after
+                        ),
+                    )),
+                    RETRY_AFTER,
+                    retry_after.to_string(),
+                ),
+                StatusCode::TOO_MANY_REQUESTS,
+            )))
+        } else if let Some(_forbidden) = rejection.find::<Forbidden>() {
+            redirection = Some(Box::new(reply::with_status(
+                reply::html(format_error_html(
+                    "Forbidden",
+                    "Only admins can do that. Ask one to do it for you.",
+                )),
+                StatusCode::FORBIDDEN,
+            )))
+        } else if let Some(_csrf_mismatch) = rejection.find::<CsrfMismatch>() {
+            redirection = Some(Box::new(reply::with_status(
+                reply::html(format_forbidden_html()),
+                StatusCode::FORBIDDEN,
+            )))
+        } else if let Some(invalid_header) = rejection.find::<InvalidHeader>() {
             if invalid_header.name() == "cookie" {
                 redirection = Some(Box::new(warp::redirect::found(
                     warp::http::Uri::from_static(LOGIN_PATH),
@@ -136,6 +216,9 @@ pub mod handlers {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::node_handles::tests::get_test_node_handles;
+    use crate::server::csrf::tests::{csrf_cookie, csrf_field, TEST_CSRF_TOKEN};
+    use warp::http::header::{COOKIE, SET_COOKIE};
     use warp::http::StatusCode;
     use warp::test::request;
 
@@ -144,32 +227,50 @@ mod tests {
 
         #[test]
         fn failed() {
-            let html = format_login_html(true);
+            let html = format_login_html(true, TEST_CSRF_TOKEN);
             assert!(html.contains("<p><b>Failed to login.</b></p>"));
         }
 
         #[test]
         fn not_failed() {
-            let html = format_login_html(false);
+            let html = format_login_html(false, TEST_CSRF_TOKEN);
             assert!(!html.contains("<p><b>Failed to login.</b></p>"));
         }
+
+        #[test]
+        fn embeds_the_csrf_token() {
+            let html = format_login_html(false, TEST_CSRF_TOKEN);
+            assert!(html.contains(&format!(r#"name="csrf_token" value="{}""#, TEST_CSRF_TOKEN)));
+        }
     }
 
     mod get_login {
         use super::*;
+        use crate::core::users::tests::{test_viewer, with_test_users_scope};
         use crate::server::auth::gen_token_for_path;
-        use warp::http::header::COOKIE;
 
         #[tokio::test]
         async fn shows_the_page() {
-            let filter = login();
-            let res = request().method("GET").path("/login").reply(&filter).await;
+            let filter = login(&get_test_node_handles());
+            let res = request()
+                .method("GET")
+                .header(COOKIE, csrf_cookie())
+                .path("/login")
+                .reply(&filter)
+                .await;
 
             assert_eq!(res.status(), StatusCode::OK);
             assert_eq!(
                 String::from_utf8(res.body().to_vec()).unwrap(),
-                format_login_html(false)
+                format_login_html(false, TEST_CSRF_TOKEN)
             );
+            assert!(res
+                .headers()
+                .get(SET_COOKIE)
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .starts_with(&csrf_cookie()));
             assert_eq!(
                 res.headers().get("content-type").unwrap(),
                 "text/html; charset=utf-8"
@@ -178,8 +279,9 @@ mod tests {
 
         #[tokio::test]
         async fn with_auth_cookie_redirects_to_root() {
-            let token = gen_token_for_path("/");
-            let filter = login();
+            let _scope = with_test_users_scope().await;
+            let token = gen_token_for_path("/", &test_viewer()).await;
+            let filter = login(&get_test_node_handles());
             let res = request()
                 .method("GET")
                 .header(COOKIE, token.to_cookie_string())
@@ -197,59 +299,158 @@ mod tests {
 
     mod post_login {
         use super::*;
-        use crate::core::root_password::tests::{with_test_root_password_scope, TEST_PASSWORD};
+        use crate::core::users::tests::{with_test_users_scope, TEST_PASSWORD};
+        use tokio::task::JoinSet;
 
         #[tokio::test]
         async fn correct_password_redirects_to_root() {
-            let _scope = with_test_root_password_scope().await;
-            let filter = login();
+            let _scope = with_test_users_scope().await;
+            let filter = login(&get_test_node_handles());
             let res = request()
                 .method("POST")
+                .header(COOKIE, csrf_cookie())
                 .path("/login")
-                .body(format!("password={}", TEST_PASSWORD))
+                .body(format!(
+                    "username=root&password={}&{}",
+                    TEST_PASSWORD,
+                    csrf_field()
+                ))
                 .reply(&filter)
                 .await;
 
             assert_eq!(res.status(), StatusCode::FOUND);
             assert_eq!(res.headers().get("location").unwrap(), "/");
         }
+
+        #[tokio::test]
+        async fn retrying_too_soon_is_throttled() {
+            let _scope = with_test_users_scope().await;
+            let filter = login(&get_test_node_handles()).recover(handlers::handle_rejection);
+            let post = |password: &str| {
+                request()
+                    .method("POST")
+                    .header(COOKIE, csrf_cookie())
+                    .path("/login")
+                    .body(format!(
+                        "username=root&password={}&{}",
+                        password,
+                        csrf_field()
+                    ))
+            };
+
+            let res = post("wrong").reply(&filter).await;
+            assert_eq!(res.status(), StatusCode::FOUND);
+            assert_eq!(res.headers().get("location").unwrap(), LOGIN_FAILED);
+
+            let res = post(TEST_PASSWORD).reply(&filter).await;
+            assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+            assert_eq!(res.headers().get("retry-after").unwrap(), "1");
+            assert!(res.headers().get(SET_COOKIE).is_none());
+        }
+
+        #[tokio::test(flavor = "multi_thread", worker_threads = 5)]
+        async fn concurrent_bad_logins_get_one_at_a_time_past_the_throttle() {
+            let _scope = with_test_users_scope().await;
+            let filter = login(&get_test_node_handles()).recover(handlers::handle_rejection);
+            let mut posts = JoinSet::new();
+            for _ in 0..5 {
+                let filter = filter.clone();
+                posts.spawn(async move {
+                    request()
+                        .method("POST")
+                        .header(COOKIE, csrf_cookie())
+                        .path("/login")
+                        .body(format!("username=root&password=wrong&{}", csrf_field()))
+                        .reply(&filter)
+                        .await
+                        .status()
+                });
+            }
+
+            let statuses = posts.join_all().await;
+
+            assert_eq!(
+                statuses
+                    .iter()
+                    .filter(|status| **status == StatusCode::FOUND)
+                    .count(),
+                1
+            );
+            assert!(statuses.iter().all(|status| [
+                StatusCode::FOUND,
+                StatusCode::TOO_MANY_REQUESTS
+            ]
+            .contains(status)));
+        }
+
+        #[tokio::test]
+        async fn without_csrf_token_is_rejected() {
+            let _scope = with_test_users_scope().await;
+            let filter = login(&get_test_node_handles());
+            let res = request()
+                .method("POST")
+                .path("/login")
+                .body(format!("username=root&password={}", TEST_PASSWORD))
+                .reply(&filter)
+                .await;
+
+            assert_ne!(res.status(), StatusCode::FOUND);
+            assert!(res.headers().get(SET_COOKIE).is_none());
+        }
     }
 
-    mod root_password_is_good {
+    mod logged_in_user {
         use super::*;
-        use crate::core::root_password::tests::{with_test_root_password_scope, TEST_PASSWORD};
+        use crate::core::users::tests::{test_viewer, with_test_users_scope, TEST_PASSWORD};
+
+        fn form(username: &str, password: &str) -> HashMap<String, String> {
+            let mut map = HashMap::new();
+            map.insert("username".to_string(), username.to_string());
+            map.insert("password".to_string(), password.to_string());
+            map
+        }
 
         #[tokio::test]
         async fn success() {
-            let _scope = with_test_root_password_scope().await;
+            let _scope = with_test_users_scope().await;
 
-            let mut map = HashMap::new();
-            map.insert("password".to_string(), TEST_PASSWORD.to_string());
-            assert!(root_password_is_good(map).await);
+            assert_eq!(
+                logged_in_user(&get_test_node_handles(), form("viewer", TEST_PASSWORD)).await,
+                Some(test_viewer())
+            );
         }
 
         #[tokio::test]
         async fn bad_password_fails() {
-            let _scope = with_test_root_password_scope().await;
+            let _scope = with_test_users_scope().await;
 
-            let mut map = HashMap::new();
-            map.insert("password".to_string(), "bad".to_string());
-            assert!(!root_password_is_good(map).await);
+            assert!(
+                logged_in_user(&get_test_node_handles(), form("viewer", "bad"))
+                    .await
+                    .is_none()
+            );
         }
 
         #[tokio::test]
-        async fn no_password_saved_fails() {
-            let mut map = HashMap::new();
-            map.insert("password".to_string(), "missing".to_string());
-            assert!(!root_password_is_good(map).await);
+        async fn unknown_user_fails() {
+            let _scope = with_test_users_scope().await;
+
+            assert!(
+                logged_in_user(&get_test_node_handles(), form("nobody", TEST_PASSWORD))
+                    .await
+                    .is_none()
+            );
         }
 
         #[tokio::test]
         async fn no_password_fails() {
-            let _scope = with_test_root_password_scope().await;
+            let _scope = with_test_users_scope().await;
 
-            let map = HashMap::new();
-            assert!(!root_password_is_good(map).await);
+            let mut map = HashMap::new();
+            map.insert("username".to_string(), "viewer".to_string());
+            assert!(logged_in_user(&get_test_node_handles(), map)
+                .await
+                .is_none());
         }
     }
 }
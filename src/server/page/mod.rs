@@ -1,7 +1,13 @@
+mod backups;
+mod error;
 mod login;
+mod sessions;
 
+pub use backups::backups_pages;
+pub use error::format_error_html;
 pub use login::handlers;
 pub use login::login;
+pub use sessions::sessions_pages;
 
 #[cfg(test)]
 pub use login::LOGIN_FAILED;
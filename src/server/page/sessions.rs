@@ -0,0 +1,265 @@
+use crate::core::users::Identity;
+use crate::server::auth::{
+    auth_validation, revoke_session, revoke_token, session_for_token, sessions, Session, Token,
+};
+use crate::server::csrf::{csrf_form, csrf_token, with_csrf_cookie};
+use crate::server::page::login::LOGIN_PATH;
+use handlebars::Handlebars;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::collections::HashMap;
+use warp::http::header::{LOCATION, SET_COOKIE};
+use warp::http::StatusCode;
+use warp::{reply, Filter, Rejection};
+
+const SESSIONS_TEMPLATE: &str = "sessions";
+const SESSIONS: &str = "sessions";
+const LOGOUT: &str = "logout";
+const REVOKE: &str = "revoke";
+const SESSIONS_PATH: &str = "/sessions";
+
+lazy_static! {
+    pub static ref PAGE_TEMPLATE: Handlebars<'static> = {
+        let mut handlebars = Handlebars::new();
+        handlebars
+            .register_template_string(
+                SESSIONS_TEMPLATE,
+                include_str!("../../../resources/html/sessions.html"),
+            )
+            .expect("Could not register sessions template");
+        handlebars
+    };
+}
+
+#[derive(Serialize)]
+struct SessionRow {
+    created_at: String,
+    current: bool,
+    expires_at: String,
+    id: String,
+    user: String,
+}
+
+#[derive(Serialize)]
+struct SessionsPage {
+    csrf_token: String,
+    sessions: Vec<SessionRow>,
+}
+
+pub fn sessions_pages() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone
+{
+    warp::path(LOGOUT)
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(csrf_form())
+        .and(warp::cookie::optional::<String>("token"))
+        .and_then(
+            |_form: HashMap<String, String>, token: Option<String>| async move {
+                if let Some(token) = token {
+                    if revoke_token(&token).await {
+                        log::info!("Logged out.");
+                    }
+                }
+                Ok::<_, Rejection>(redirect(LOGIN_PATH, Some(Token::removal_cookie_string())))
+            },
+        )
+        .or(warp::path(SESSIONS)
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(auth_validation())
+            .and(warp::cookie::<String>("token"))
+            .and(csrf_token())
+            .map(|user: Identity, token: String, csrf_token: String| {
+                with_csrf_cookie(
+                    reply::html(format_sessions_html(&user, &token, &csrf_token)),
+                    &csrf_token,
+                )
+            }))
+        .or(warp::path(SESSIONS)
+            .and(warp::path(REVOKE))
+            .and(warp::path::end())
+            .and(warp::post())
+            .and(auth_validation())
+            .and(csrf_form())
+            .and_then(|user: Identity, form: HashMap<String, String>| async move {
+                let id = form.get("id");
+                let visible = sessions()
+                    .iter()
+                    .any(|session| Some(session.id()) == id && is_visible_to(session, &user));
+                if let (true, Some(id)) = (visible, id) {
+                    if revoke_session(id).await {
+                        log::info!("{} revoked session {}.", user.name(), id);
+                    }
+                }
+                Ok::<_, Rejection>(redirect(SESSIONS_PATH, None))
+            }))
+}
+
+fn redirect(location: &'static str, cookie: Option<String>) -> impl warp::Reply {
+    let reply = reply::with_status(
+        reply::with_header(reply::html(""), LOCATION, location),
+        StatusCode::FOUND,
+    );
+    match cookie {
+        Some(cookie) => {
+            Box::new(reply::with_header(reply, SET_COOKIE, cookie)) as Box<dyn warp::Reply>
+        }
+        None => Box::new(reply),
+    }
+}
+
+/// Admins see everyone's sessions. Everyone else sees only their own.
+fn is_visible_to(session: &Session, user: &Identity) -> bool {
+    user.is_admin() || session.user().name() == user.name()
+}
+
+fn format_sessions_html(user: &Identity, token: &str, csrf_token: &str) -> String {
+    let current = session_for_token(token).map(|session| session.id().clone());
+    let page = SessionsPage {
+        csrf_token: csrf_token.to_string(),
+        sessions: sessions()
+            .into_iter()
+            .filter(|session| is_visible_to(session, user))
+            .map(|session| SessionRow {
+                created_at: session.created_at().to_rfc3339(),
+                current: current.as_ref() == Some(session.id()),
+                expires_at: session.expires_at().to_rfc3339(),
+                id: session.id().clone(),
+                user: session.user().name().clone(),
+            })
+            .collect(),
+    };
+
+    PAGE_TEMPLATE
+        .render(SESSIONS_TEMPLATE, &page)
+        .expect("Could not render sessions template")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::users::tests::{test_admin, test_viewer, with_test_users_scope};
+    use crate::server::auth::gen_token_for_path;
+    use crate::server::csrf::tests::{csrf_cookie, csrf_field};
+    use warp::http::header::COOKIE;
+    use warp::test::request;
+
+    fn id_for(token: &Token) -> String {
+        session_for_token(token.value()).unwrap().id().clone()
+    }
+
+    mod logout {
+        use super::*;
+
+        #[tokio::test]
+        async fn revokes_the_token_and_clears_the_cookie() {
+            let token = gen_token_for_path("/", &test_admin()).await;
+            let res = request()
+                .method("POST")
+                .header(
+                    COOKIE,
+                    format!("{}; {}", token.to_cookie_string(), csrf_cookie()),
+                )
+                .path("/logout")
+                .body(csrf_field())
+                .reply(&sessions_pages())
+                .await;
+
+            assert_eq!(res.status(), StatusCode::FOUND);
+            assert_eq!(res.headers().get(LOCATION).unwrap(), LOGIN_PATH);
+            assert_eq!(
+                res.headers().get(SET_COOKIE).unwrap().to_str().unwrap(),
+                Token::removal_cookie_string()
+            );
+            assert!(session_for_token(token.value()).is_none());
+        }
+
+        #[tokio::test]
+        async fn without_a_token_redirects_to_login() {
+            let res = request()
+                .method("POST")
+                .header(COOKIE, csrf_cookie())
+                .path("/logout")
+                .body(csrf_field())
+                .reply(&sessions_pages())
+                .await;
+
+            assert_eq!(res.status(), StatusCode::FOUND);
+            assert_eq!(res.headers().get(LOCATION).unwrap(), LOGIN_PATH);
+        }
+    }
+
+    mod sessions {
+        use super::*;
+
+        #[tokio::test]
+        async fn lists_sessions_and_marks_the_current_one() {
+            let _scope = with_test_users_scope().await;
+            let token = gen_token_for_path("/", &test_admin()).await;
+            let other = gen_token_for_path("/", &test_admin()).await;
+            let res = request()
+                .method("GET")
+                .header(COOKIE, token.to_cookie_string())
+                .path("/sessions")
+                .reply(&sessions_pages())
+                .await;
+
+            assert_eq!(res.status(), StatusCode::OK);
+            let body = String::from_utf8(res.body().to_vec()).unwrap();
+            assert!(body.contains(&id_for(&token)));
+            assert!(body.contains(&id_for(&other)));
+            assert_eq!(body.matches("This session").count(), 1);
+        }
+
+        #[tokio::test]
+        async fn viewers_see_and_revoke_only_their_own() {
+            let _scope = with_test_users_scope().await;
+            let viewer = gen_token_for_path("/", &test_viewer()).await;
+            let admin = gen_token_for_path("/", &test_admin()).await;
+            let cookie = format!("{}; {}", viewer.to_cookie_string(), csrf_cookie());
+            let res = request()
+                .method("GET")
+                .header(COOKIE, &cookie)
+                .path("/sessions")
+                .reply(&sessions_pages())
+                .await;
+
+            let body = String::from_utf8(res.body().to_vec()).unwrap();
+            assert!(body.contains(&id_for(&viewer)));
+            assert!(!body.contains(&id_for(&admin)));
+
+            let res = request()
+                .method("POST")
+                .header(COOKIE, &cookie)
+                .path("/sessions/revoke")
+                .body(format!("id={}&{}", id_for(&admin), csrf_field()))
+                .reply(&sessions_pages())
+                .await;
+
+            assert_eq!(res.status(), StatusCode::FOUND);
+            assert!(session_for_token(admin.value()).is_some());
+        }
+
+        #[tokio::test]
+        async fn revoke_removes_the_session() {
+            let _scope = with_test_users_scope().await;
+            let token = gen_token_for_path("/", &test_admin()).await;
+            let other = gen_token_for_path("/", &test_admin()).await;
+            let res = request()
+                .method("POST")
+                .header(
+                    COOKIE,
+                    format!("{}; {}", token.to_cookie_string(), csrf_cookie()),
+                )
+                .path("/sessions/revoke")
+                .body(format!("id={}&{}", id_for(&other), csrf_field()))
+                .reply(&sessions_pages())
+                .await;
+
+            assert_eq!(res.status(), StatusCode::FOUND);
+            assert_eq!(res.headers().get(LOCATION).unwrap(), SESSIONS_PATH);
+            assert!(session_for_token(token.value()).is_some());
+            assert!(session_for_token(other.value()).is_none());
+        }
+    }
+}
@@ -1,8 +1,11 @@
 use crate::core::node_handles::NodeHandles;
+use crate::core::users::Identity;
 use crate::server::auth::auth_validation;
+use crate::server::csrf::{csrf_token, with_csrf_cookie};
 use crate::server::javascript::WithRedirect;
 use handlebars::Handlebars;
 use lazy_static::lazy_static;
+use serde_json::json;
 use std::collections::HashMap;
 use warp::{reply, Filter, Rejection, Reply};
 
@@ -25,19 +28,37 @@ pub fn root(handles: &NodeHandles) -> impl Filter<Extract = impl Reply, Error =
     let handles = handles.clone();
     warp::path::end()
         .and(auth_validation())
-        .map(move || {
+        .and(csrf_token())
+        .map(move |user: Identity, csrf_token| {
             let handles = handles.clone();
-            render_root(handles)
+            render_root(handles, user, csrf_token)
         })
         .and_then(|future| future)
 }
 
-async fn render_root(handles: NodeHandles) -> Result<impl Reply, Rejection> {
-    Ok(reply::html(format_root_html(&handles)))
+async fn render_root(
+    handles: NodeHandles,
+    user: Identity,
+    csrf_token: String,
+) -> Result<impl Reply, Rejection> {
+    Ok(with_csrf_cookie(
+        reply::html(format_root_html(&handles, &user, &csrf_token)),
+        &csrf_token,
+    ))
 }
 
-pub fn format_root_html(handles: &NodeHandles) -> String {
-    let page_data = HashMap::new().with_redirect_script(handles);
+pub fn format_root_html(handles: &NodeHandles, user: &Identity, csrf_token: &str) -> String {
+    let mut page_data = HashMap::new();
+    page_data.insert("csrf_token", csrf_token.to_string());
+    page_data.insert("user", user.name().clone());
+    if user.is_admin() {
+        page_data.insert("admin", "true".to_string());
+    }
+    let page_data = page_data.with_redirect_script(handles);
+    let mut page_data = json!(page_data);
+    if user.is_admin() {
+        page_data["nodes"] = json!(handles.lifecycle_manager().node_names());
+    }
     PAGE_TEMPLATE
         .render(ROOT_TEMPLATE, &page_data)
         .expect("Could not render root template")
@@ -1,8 +1,11 @@
 use crate::core::node_handles::NodeHandles;
 use crate::integration::google::auth::web::config_google;
+use crate::server::metrics::metrics;
+use crate::server::nodes::nodes;
 use crate::server::oauth2::oauth2_callback;
-use crate::server::page::{handlers, login};
+use crate::server::page::{backups_pages, handlers, login, sessions_pages};
 use crate::server::root::root;
+use crate::server::status::status;
 use crate::server::websocket::websocket;
 use warp::{Filter, Rejection};
 
@@ -10,8 +13,13 @@ pub fn router(
     handles: &NodeHandles,
 ) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
     root(handles)
-        .or(login())
+        .or(login(handles))
+        .or(sessions_pages())
+        .or(backups_pages(handles))
         .or(config_google(handles))
+        .or(nodes(handles))
+        .or(status(handles))
+        .or(metrics())
         .or(websocket(handles))
         .or(oauth2_callback!(handles, "auth" / "google"))
         .recover(handlers::handle_rejection)
@@ -21,34 +29,62 @@ pub fn router(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::node_handles::tests::get_test_node_handles;
     use warp::http::StatusCode;
     use warp::test::request;
 
     mod router {
         use super::*;
 
+        #[tokio::test]
+        async fn metrics_are_served_without_a_token() {
+            let node_handles = get_test_node_handles();
+            let filter = router(&node_handles);
+            let res = request()
+                .method("GET")
+                .path("/metrics")
+                .reply(&filter)
+                .await;
+
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(
+                res.headers().get("content-type").unwrap(),
+                "text/plain; version=0.0.4"
+            );
+            let body = String::from_utf8(res.body().to_vec()).unwrap();
+            assert!(body.contains("# TYPE cloud_scraper_login_failures_total counter\n"));
+        }
+
         mod auth {
             use super::*;
-            use crate::core::node_handles::tests::get_test_node_handles;
-            use crate::core::root_password::tests::with_test_root_password_scope;
-            use crate::server::auth::gen_token_for_path;
+            use crate::core::users::tests::{
+                save_test_user, test_admin, test_viewer, with_test_users_scope, TEST_VIEWER,
+            };
+            use crate::core::users::{remove_user, Role, ROOT_USER};
+            use crate::domain::node::{NodeKind, NodeState};
+            use crate::server::auth::{gen_token_for_path, session_for_token};
+            use crate::server::csrf::tests::{csrf_cookie, TEST_CSRF_TOKEN};
             use crate::server::format_root_html;
             use crate::server::page::{LOGIN_FAILED, LOGIN_PATH};
             use warp::http::header::COOKIE;
 
             #[tokio::test]
             async fn authorized_root_serves_page() {
+                let _scope = with_test_users_scope().await;
                 let node_handles = get_test_node_handles();
                 let filter = router(&node_handles);
-                let token = gen_token_for_path("/");
+                let token = gen_token_for_path("/", &test_admin()).await;
                 let res = request()
                     .method("GET")
-                    .header(COOKIE, token.to_cookie_string())
+                    .header(
+                        COOKIE,
+                        format!("{}; {}", token.to_cookie_string(), csrf_cookie()),
+                    )
                     .path("/")
                     .reply(&filter)
                     .await;
 
-                let expected = format_root_html(&node_handles);
+                let expected = format_root_html(&node_handles, &test_admin(), TEST_CSRF_TOKEN);
                 assert_eq!(res.status(), StatusCode::OK);
                 assert_eq!(res.body(), expected.as_bytes());
                 assert_eq!(
@@ -57,6 +93,120 @@ mod tests {
                 );
             }
 
+            #[tokio::test]
+            async fn viewers_cannot_configure_google() {
+                let _scope = with_test_users_scope().await;
+                let node_handles = get_test_node_handles();
+                let filter = router(&node_handles);
+                let token = gen_token_for_path("/", &test_viewer()).await;
+                let res = request()
+                    .method("GET")
+                    .header(COOKIE, token.to_cookie_string())
+                    .path("/config/google")
+                    .reply(&filter)
+                    .await;
+
+                assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+                let res = request()
+                    .method("GET")
+                    .header(COOKIE, token.to_cookie_string())
+                    .path("/")
+                    .reply(&filter)
+                    .await;
+
+                let body = String::from_utf8(res.body().to_vec()).unwrap();
+                assert!(body.contains("Logged in as viewer."));
+                assert!(!body.contains("/config/google"));
+            }
+
+            #[tokio::test]
+            async fn demoted_admins_cannot_configure_google() {
+                let _scope = with_test_users_scope().await;
+                let node_handles = get_test_node_handles();
+                let filter = router(&node_handles);
+                let token = gen_token_for_path("/", &test_admin()).await;
+                save_test_user(ROOT_USER, Role::Viewer).await;
+                let res = request()
+                    .method("GET")
+                    .header(COOKIE, token.to_cookie_string())
+                    .path("/config/google")
+                    .reply(&filter)
+                    .await;
+
+                assert_eq!(res.status(), StatusCode::FORBIDDEN);
+            }
+
+            #[tokio::test]
+            async fn removed_users_are_logged_out() {
+                let _scope = with_test_users_scope().await;
+                let node_handles = get_test_node_handles();
+                let filter = router(&node_handles);
+                let token = gen_token_for_path("/", &test_viewer()).await;
+                remove_user(TEST_VIEWER).await.unwrap();
+                let res = request()
+                    .method("GET")
+                    .header(COOKIE, token.to_cookie_string())
+                    .path("/")
+                    .reply(&filter)
+                    .await;
+
+                assert_eq!(res.status(), StatusCode::FOUND);
+                assert_eq!(
+                    res.headers().get("location").unwrap().to_str().unwrap(),
+                    LOGIN_FAILED
+                );
+                assert!(session_for_token(token.value()).is_none());
+            }
+
+            #[tokio::test]
+            async fn authorized_status_serves_json() {
+                let _scope = with_test_users_scope().await;
+                let node_handles = get_test_node_handles();
+                node_handles.lifecycle_manager().status().set(
+                    "stub",
+                    NodeKind::Source,
+                    NodeState::Syncing,
+                );
+                let filter = router(&node_handles);
+                let token = gen_token_for_path("/api/status", &test_viewer()).await;
+                let res = request()
+                    .method("GET")
+                    .header(COOKIE, token.to_cookie_string())
+                    .path("/api/status")
+                    .reply(&filter)
+                    .await;
+
+                assert_eq!(res.status(), StatusCode::OK);
+                assert_eq!(
+                    res.headers().get("content-type").unwrap(),
+                    "application/json"
+                );
+                let body = String::from_utf8(res.body().to_vec()).unwrap();
+                assert!(body.contains(
+                    r#""stub":{"error":null,"kind":"source","last_sync":null,"state":"syncing"}"#
+                ));
+                assert!(body.contains(r#""tls_certificate_expiry":null"#));
+                assert!(body.contains(r#""uptime_seconds":"#));
+            }
+
+            #[tokio::test]
+            async fn status_without_token_redirects_to_login() {
+                let node_handles = get_test_node_handles();
+                let filter = router(&node_handles);
+                let res = request()
+                    .method("GET")
+                    .path("/api/status")
+                    .reply(&filter)
+                    .await;
+
+                assert_eq!(res.status(), StatusCode::FOUND);
+                assert_eq!(
+                    res.headers().get("location").unwrap().to_str().unwrap(),
+                    LOGIN_PATH
+                );
+            }
+
             #[tokio::test]
             async fn missing_token_redirects_to_login() {
                 let node_handles = get_test_node_handles();
@@ -90,13 +240,17 @@ mod tests {
 
             #[tokio::test]
             async fn incorrect_password_redirects_to_login() {
-                let _scope = with_test_root_password_scope().await;
+                let _scope = with_test_users_scope().await;
                 let node_handles = get_test_node_handles();
                 let filter = router(&node_handles);
                 let res = request()
                     .method("POST")
+                    .header(COOKIE, csrf_cookie())
                     .path("/login")
-                    .body("password=wrong")
+                    .body(format!(
+                        "username=root&password=wrong&csrf_token={}",
+                        TEST_CSRF_TOKEN
+                    ))
                     .reply(&filter)
                     .await;
 
@@ -104,12 +258,36 @@ mod tests {
                 let location = res.headers().get("location").unwrap().to_str().unwrap();
                 assert_eq!(location, "/login?failed=true");
 
-                let res = request().method("GET").path(location).reply(&filter).await;
+                let res = request()
+                    .method("GET")
+                    .header(COOKIE, csrf_cookie())
+                    .path(location)
+                    .reply(&filter)
+                    .await;
 
                 assert_eq!(res.status(), StatusCode::OK);
                 assert_eq!(
                     String::from_utf8(res.body().to_vec()).unwrap(),
-                    crate::server::page::format_login_html(true)
+                    crate::server::page::format_login_html(true, TEST_CSRF_TOKEN)
+                );
+            }
+
+            #[tokio::test]
+            async fn post_without_csrf_token_is_forbidden() {
+                let _scope = with_test_users_scope().await;
+                let node_handles = get_test_node_handles();
+                let filter = router(&node_handles);
+                let res = request()
+                    .method("POST")
+                    .path("/login")
+                    .body("username=root&password=wrong")
+                    .reply(&filter)
+                    .await;
+
+                assert_eq!(res.status(), StatusCode::FORBIDDEN);
+                assert_eq!(
+                    String::from_utf8(res.body().to_vec()).unwrap(),
+                    crate::server::csrf::format_forbidden_html()
                 );
             }
         }
@@ -0,0 +1,43 @@
+use crate::core::node_handles::NodeHandles;
+use crate::core::users::Identity;
+use crate::domain::node::NodeStatus;
+use crate::server::acme::cert_expiry;
+use crate::server::auth::auth_validation;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use warp::{reply, Filter, Rejection, Reply};
+
+#[derive(Debug, Serialize)]
+struct Status {
+    nodes: BTreeMap<String, NodeStatus>,
+    started_at: DateTime<Utc>,
+    tls_certificate_expiry: Option<DateTime<Utc>>,
+    uptime_seconds: i64,
+}
+
+pub fn status(
+    handles: &NodeHandles,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let handles = handles.clone();
+    warp::path!("api" / "status")
+        .and(warp::get())
+        .and(auth_validation())
+        .map(move |_user: Identity| {
+            let handles = handles.clone();
+            render_status(handles)
+        })
+        .and_then(|future| future)
+}
+
+async fn render_status(handles: NodeHandles) -> Result<impl Reply, Rejection> {
+    let manager = handles.lifecycle_manager();
+    let board = manager.status();
+    let started_at = board.started_at();
+    Ok(reply::json(&Status {
+        nodes: board.nodes(),
+        started_at,
+        tls_certificate_expiry: cert_expiry(&manager.core_config()).await,
+        uptime_seconds: (Utc::now() - started_at).num_seconds(),
+    }))
+}
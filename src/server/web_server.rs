@@ -4,6 +4,7 @@ use crate::core::node_handles::NodeHandles;
 use crate::domain::config::Config;
 use crate::domain::node::LifecycleAware;
 use crate::server::acme::Acme;
+use crate::server::auth::{load_sessions, purge_expired_sessions_periodically};
 use crate::server::routes::router;
 use async_trait::async_trait;
 #[cfg(test)]
@@ -15,7 +16,7 @@ pub fn new(config: Arc<Config>) -> impl WebServer {
     WebServerImpl {
         acme: Arc::new(Acme::new(&config)),
         config: config.clone(),
-        web_channel_handle: WebEventChannelHandle::new(),
+        web_channel_handle: WebEventChannelHandle::with_capacity(config.web_channel_capacity()),
     }
 }
 
@@ -71,6 +72,9 @@ impl WebServer for WebServerImpl {
             self.acme.ensure_certs().await?;
         }
 
+        load_sessions(&self.config);
+        let purge = tokio::spawn(purge_expired_sessions_periodically());
+
         let routes = router(node_handles);
         let mut lifecycle_rx = node_handles.lifecycle_manager().readonly().get_receiver();
         let shutdown_binding = async move {
@@ -101,6 +105,7 @@ impl WebServer for WebServerImpl {
             fut.await;
         };
 
+        purge.abort();
         Ok(())
     }
 
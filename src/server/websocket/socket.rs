@@ -40,6 +40,7 @@ mod tests {
         use crate::core::node_handles::tests::get_test_node_handles;
 
         #[tokio::test]
+        #[allow(static_mut_refs)]
         async fn calls_handler() {
             unsafe {
                 HANDLES = None;
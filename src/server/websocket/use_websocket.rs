@@ -1,3 +1,5 @@
+use crate::core::metrics;
+use crate::core::metrics::Metric;
 use crate::core::node_handles::NodeHandles;
 use crate::domain::node::InitReplier;
 use crate::server::Event::Redirect;
@@ -56,6 +58,7 @@ async fn break_on_web_event_result(
                 return true;
             }
             RecvError::Lagged(amount) => {
+                metrics::add(Metric::ChannelLagged, &[("channel", "web")], amount);
                 info!("Websocket lagged by {} messages.", amount);
             }
         },
@@ -272,7 +275,7 @@ mod tests {
         }
     }
 
-    mod BreakInstructionTranslator {
+    mod break_instruction_translator {
         use super::*;
         use crate::server::websocket::use_websocket::BreakInstructionTranslator;
         mod none_or_break_parent {
@@ -3,7 +3,6 @@ use core::error::Error as CoreError;
 use log::{debug, error};
 use oauth2::basic::BasicErrorResponse;
 use oauth2::RequestTokenError;
-use serde_yaml::Value;
 use std::error;
 use std::error::Error as StdError;
 use std::fmt::{Display, Formatter};
@@ -19,8 +18,7 @@ pub enum Error {
     Connection(String),
     FailedAfterRetries,
     Io(String),
-    KeyNotFound(Value),
-    NotAMapping(Value),
+    JsonSerialization(String),
     Oauth2CodeMissing,
     Oauth2CsrfMismatch,
     Oauth2TokenAbsent,
@@ -40,8 +38,7 @@ impl Display for Error {
             Error::Connection(e) => write!(f, "Connection error: {}", e),
             Error::FailedAfterRetries => write!(f, "Failed after retries"),
             Io(e) => write!(f, "IO error: {}", e),
-            Error::KeyNotFound(v) => write!(f, "Key not found: {:?}", v),
-            Error::NotAMapping(v) => write!(f, "Not a mapping: {:?}", v),
+            Error::JsonSerialization(e) => write!(f, "JSON serialization error: {}", e),
             Error::Oauth2CodeMissing => write!(f, "Oauth2 code missing"),
             Error::Oauth2CsrfMismatch => write!(f, "Oauth2 CSRF mismatch"),
             Error::Oauth2TokenAbsent => write!(f, "Oauth2 token absent"),
@@ -118,6 +115,16 @@ impl SerdeErrorExt for serde_yaml::Error {
     }
 }
 
+pub trait JsonErrorExt {
+    fn to_json_serialization_error(&self) -> Error;
+}
+
+impl JsonErrorExt for serde_json::Error {
+    fn to_json_serialization_error(&self) -> Error {
+        Error::JsonSerialization(self.to_string())
+    }
+}
+
 pub trait IoErrorExt {
     fn to_source_creation_builder_error(&self) -> Error;
     fn to_error(&self) -> Error;
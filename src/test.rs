@@ -44,6 +44,7 @@ pub(crate) mod tests {
     }
 
     impl Logger {
+        #[allow(static_mut_refs)]
         fn get() -> &'static mut Logger {
             unsafe {
                 static mut LOGGER_INIT: Mutex<()> = Mutex::new(());
@@ -69,7 +70,7 @@ pub(crate) mod tests {
 
         pub(crate) fn use_in<T>(log_use: T)
         where
-            T: FnOnce(&mut Logger) -> (),
+            T: FnOnce(&mut Logger),
         {
             let mutex = Logger::get().records.clone();
             let _lock = mutex.lock();
@@ -83,12 +84,7 @@ pub(crate) mod tests {
         }
 
         pub(crate) fn log_entries(&mut self) -> Vec<LogEntry> {
-            self.records
-                .lock()
-                .borrow()
-                .iter()
-                .map(|r| r.clone())
-                .collect()
+            self.records.lock().borrow().iter().cloned().collect()
         }
 
         pub(crate) fn log_entry_exists(&self, entry: &LogEntry) -> bool {
@@ -128,6 +124,7 @@ pub(crate) mod tests {
     }
 
     impl<'a> CleanableTestFile<'a> {
+        #[allow(clippy::await_holding_lock)]
         pub(crate) async fn new<ErrorType, ResponseFuture, SaveFunctionType>(
             guard: MutexGuard<'a, ()>,
             path: String,
@@ -140,7 +137,7 @@ pub(crate) mod tests {
         {
             save_function(path.clone())
                 .await
-                .expect(&format!("Could not create {:?}", path));
+                .unwrap_or_else(|_| panic!("Could not create {:?}", path));
             Self {
                 _guard: guard,
                 path: path.to_string(),
@@ -5,10 +5,11 @@ use derive_getters::Getters;
 use regex::RegexBuilder;
 use std::cmp::PartialEq;
 use std::collections::HashMap;
+use std::env::temp_dir;
 use std::fmt::Debug;
 use std::future::Future;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::process::Output;
 use std::time::Duration;
@@ -17,6 +18,7 @@ use tokio::io::AsyncWriteExt;
 use tokio::net::TcpListener;
 use tokio::process::Command;
 use tokio_test::assert_ok;
+use uuid::Uuid;
 
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) enum InputType {
@@ -45,7 +47,7 @@ impl Debug for PinnedBoxedFutureWrapper {
 }
 
 pub(crate) trait ResponseExpecter {
-    fn expect_response<'a, 'b>(&'a self, world: &'b CliWorld) -> &'b HttpResponse;
+    fn expect_response<'b>(&self, world: &'b CliWorld) -> &'b HttpResponse;
 }
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
@@ -55,7 +57,7 @@ struct RequestMethodAndUrl {
 }
 
 impl ResponseExpecter for RequestMethodAndUrl {
-    fn expect_response<'a, 'b>(&'a self, world: &'b CliWorld) -> &'b HttpResponse {
+    fn expect_response<'b>(&self, world: &'b CliWorld) -> &'b HttpResponse {
         world
             .http_request_shortcuts
             .get(self)
@@ -77,12 +79,12 @@ pub(crate) struct PostRequest {
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub(crate) enum HttpRequest {
-    GET(GetRequest),
-    POST(PostRequest),
+    Get(GetRequest),
+    Post(PostRequest),
 }
 
 impl ResponseExpecter for HttpRequest {
-    fn expect_response<'a, 'b>(&'a self, world: &'b CliWorld) -> &'b HttpResponse {
+    fn expect_response<'b>(&self, world: &'b CliWorld) -> &'b HttpResponse {
         world.http_transactions.expect(self)
     }
 }
@@ -110,7 +112,7 @@ impl RequestResponseMap {
 
     pub(crate) fn expect(&self, request: &HttpRequest) -> &HttpResponse {
         self.get(request)
-            .expect(&format!("No response for request {:?}", request))
+            .unwrap_or_else(|| panic!("No response for request {:?}", request))
             .as_ref()
             .unwrap_or_else(|e| panic!("Error getting response for request {:?}: {}", request, e))
     }
@@ -134,6 +136,7 @@ impl Debug for RequestResponseMap {
 pub(crate) struct CliWorld {
     args: Vec<String>,
     command: Option<String>,
+    cookies: HashMap<String, String>,
     environment_variables: Vec<(String, String)>,
     http_requests: Vec<HttpRequest>,
     http_request_shortcuts: HashMap<RequestMethodAndUrl, HttpRequest>,
@@ -142,6 +145,8 @@ pub(crate) struct CliWorld {
     output: Option<Output>,
     output_future: Option<PinnedBoxedFutureWrapper>,
     wait_for_process_to_terminate: bool,
+    /// Where the scenario runs, so the files it writes stay out of the working copy.
+    working_dir: PathBuf,
 }
 
 impl CliWorld {
@@ -149,6 +154,7 @@ impl CliWorld {
         Self {
             args: Vec::new(),
             command: None,
+            cookies: Default::default(),
             environment_variables: Default::default(),
             http_requests: Default::default(),
             http_request_shortcuts: Default::default(),
@@ -157,6 +163,7 @@ impl CliWorld {
             output: None,
             output_future: None,
             wait_for_process_to_terminate: false,
+            working_dir: make_working_dir(),
         }
     }
 
@@ -196,11 +203,12 @@ impl CliWorld {
         }
 
         let command = self.command.clone().expect("Command not set");
-        let command = PathBuf::new().join("target/debug").join(command);
+        assert_eq!(command, "cloud_scraper", "Unknown command {}", command);
 
-        let cmd = command.clone();
-        let mut command = Command::new(cmd);
+        let mut command = Command::new(env!("CARGO_BIN_EXE_cloud_scraper"));
         let mut command = command
+            .current_dir(&self.working_dir)
+            .kill_on_drop(true)
             .stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped());
@@ -222,7 +230,7 @@ impl CliWorld {
                 }
                 InputType::String(string) => {
                     stdin
-                        .write((string + "\n").as_bytes())
+                        .write_all((string + "\n").as_bytes())
                         .await
                         .expect("Error writing to stdin");
                 }
@@ -239,11 +247,11 @@ impl CliWorld {
         self.http_requests.push(request.clone());
 
         let request_method_and_url = match request {
-            HttpRequest::GET(get) => RequestMethodAndUrl {
+            HttpRequest::Get(get) => RequestMethodAndUrl {
                 method: "GET".to_string(),
                 url: get.url.clone(),
             },
-            HttpRequest::POST(post) => RequestMethodAndUrl {
+            HttpRequest::Post(post) => RequestMethodAndUrl {
                 method: "POST".to_string(),
                 url: post.url.clone(),
             },
@@ -257,21 +265,21 @@ impl CliWorld {
             .build()
             .expect("Error building reqwest client");
 
-        let result = match request {
-            HttpRequest::GET(ref get_request) => client
-                .get(&get_request.url)
-                .send()
-                .await
-                .map_err(|e| e.to_string()),
-            HttpRequest::POST(ref post_request) => client
+        let builder = match request {
+            HttpRequest::Get(ref get_request) => client.get(&get_request.url),
+            HttpRequest::Post(ref post_request) => client
                 .post(&post_request.url)
-                .body(post_request.body.clone())
-                .send()
-                .await
-                .map_err(|e| e.to_string()),
+                .body(self.with_cookie_values(&post_request.body)),
+        };
+        let builder = if self.cookies.is_empty() {
+            builder
+        } else {
+            builder.header(reqwest::header::COOKIE, self.cookie_header())
         };
+        let result = builder.send().await.map_err(|e| e.to_string());
         let result = match result {
             Ok(response) => {
+                self.keep_cookies(&response);
                 let status_code = response.status().as_u16();
                 let headers = response
                     .headers()
@@ -289,7 +297,41 @@ impl CliWorld {
             Err(e) => Err(e),
         };
 
-        self.http_transactions.insert(&request, &Some(result));
+        self.http_transactions.insert(request, &Some(result));
+    }
+
+    fn cookie_header(&self) -> String {
+        self.cookies
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// Keeps cookies the server sets, like a browser would.
+    fn keep_cookies(&mut self, response: &reqwest::Response) {
+        for set_cookie in response.headers().get_all(reqwest::header::SET_COOKIE) {
+            let set_cookie = set_cookie.to_str().unwrap();
+            let pair = set_cookie.split(';').next().unwrap_or_default();
+            if let Some((name, value)) = pair.split_once('=') {
+                self.cookies
+                    .insert(name.trim().to_string(), value.trim().to_string());
+            }
+        }
+    }
+
+    /// Replaces `{cookie:name}` with the value of that cookie, as a page's form would embed it.
+    fn with_cookie_values(&self, body: &str) -> String {
+        self.cookies
+            .iter()
+            .fold(body.to_string(), |body, (name, value)| {
+                body.replace(&format!("{{cookie:{}}}", name), value)
+            })
+    }
+
+    /// Resolves a path in a step against the scenario's working directory.
+    fn path(&self, path: &str) -> PathBuf {
+        self.working_dir.join(path)
     }
 
     pub(crate) async fn trigger(&mut self) {
@@ -301,38 +343,60 @@ impl CliWorld {
     }
 }
 
+impl Drop for CliWorld {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.working_dir);
+    }
+}
+
+/// Makes an empty directory for a scenario to run in, with the fixtures its steps refer to.
+fn make_working_dir() -> PathBuf {
+    let working_dir = temp_dir().join(format!("cloud-scraper-cucumber-{}", Uuid::new_v4()));
+    let fixtures = Path::new("tests/fixtures");
+    let working_fixtures = working_dir.join(fixtures);
+    std::fs::create_dir_all(&working_fixtures).expect("Error creating working directory");
+    for entry in std::fs::read_dir(fixtures).expect("Error reading fixtures") {
+        let entry = entry.expect("Error reading fixture");
+        std::fs::copy(entry.path(), working_fixtures.join(entry.file_name()))
+            .expect("Error copying fixture");
+    }
+    working_dir
+}
+
 #[given(regex = r#"no file named "([\S ]+)""#)]
-async fn no_file(_cli_world: &mut CliWorld, path: String) {
+async fn no_file(cli_world: &mut CliWorld, path: String) {
+    let path = cli_world.path(&path);
     if fs::try_exists(&path)
         .await
         .expect("Error checking file existence")
     {
         fs::remove_file(&path)
             .await
-            .expect(&format!("Error removing {}", path));
+            .unwrap_or_else(|_| panic!("Error removing {}", path.display()));
     }
 }
 
 #[given(regex = r#"a file named "([\S ]+)" containing:"#)]
-async fn a_file_containing(_cli_world: &mut CliWorld, step: &Step, path: String) {
+async fn a_file_containing(cli_world: &mut CliWorld, step: &Step, path: String) {
+    let path = cli_world.path(&path);
     if fs::try_exists(&path)
         .await
         .expect("Error checking file existence")
     {
         fs::remove_file(&path)
             .await
-            .expect(&format!("Error removing {}", path));
+            .unwrap_or_else(|_| panic!("Error removing {}", path.display()));
     }
 
     fs::write(&path, step.docstring.as_ref().unwrap().as_bytes())
         .await
-        .expect(&format!("Error writing to {}", path));
+        .unwrap_or_else(|_| panic!("Error writing to {}", path.display()));
 }
 
 #[given("a test config")]
-async fn a_config_file(_cli_world: &mut CliWorld) {
+async fn a_config_file(cli_world: &mut CliWorld) {
     fs::write(
-        "config.yaml",
+        cli_world.path("config.yaml"),
         serde_yaml::to_string(&test_config()).unwrap(),
     )
     .await
@@ -382,14 +446,14 @@ pub(crate) async fn i_kill_the_process(cli_world: &mut CliWorld) {
 #[when(regex = r#"^I request "GET" "([a-zA-Z0-9.-/:]+)"$"#)]
 pub(crate) async fn i_request_get(cli_world: &mut CliWorld, url: String) {
     cli_world
-        .send_request(&HttpRequest::GET(GetRequest { url }))
+        .send_request(&HttpRequest::Get(GetRequest { url }))
         .await;
 }
 
 #[when(regex = r#"^I request "POST" "([a-zA-Z0-9.-/:]+)" with body:$"#)]
 pub(crate) async fn i_request_post_with_body(cli_world: &mut CliWorld, url: String, step: &Step) {
     cli_world
-        .send_request(&HttpRequest::POST(PostRequest {
+        .send_request(&HttpRequest::Post(PostRequest {
             body: step.docstring.as_ref().expect("no docstring found").clone(),
             url,
         }))
@@ -400,7 +464,8 @@ pub(crate) async fn i_request_post_with_body(cli_world: &mut CliWorld, url: Stri
 pub(crate) async fn the_file_should_not_exist(cli_world: &mut CliWorld, path: String) {
     cli_world.trigger().await;
     assert!(
-        !std::fs::exists(&path).expect(&format!("Error checking {} existence", &path)),
+        !std::fs::exists(cli_world.path(&path))
+            .unwrap_or_else(|_| panic!("Error checking {} existence", &path)),
         "File {} exists",
         &path
     );
@@ -410,7 +475,8 @@ pub(crate) async fn the_file_should_not_exist(cli_world: &mut CliWorld, path: St
 pub(crate) async fn the_file_should_exist(cli_world: &mut CliWorld, path: String) {
     cli_world.trigger().await;
     assert!(
-        std::fs::exists(&path).expect(&format!("Error checking {} existence", &path)),
+        std::fs::exists(cli_world.path(&path))
+            .unwrap_or_else(|_| panic!("Error checking {} existence", &path)),
         "File {} does not exist",
         &path
     );
@@ -446,7 +512,7 @@ pub(crate) async fn the_exit_code_should_not_be(cli_world: &mut CliWorld, expect
 #[then(regex = r#"^the file "([\S "]+)" should be a valid config$"#)]
 pub(crate) async fn the_file_should_be_a_valid_config(cli_world: &mut CliWorld, path: String) {
     cli_world.trigger().await;
-    let config = tokio::fs::read_to_string(&path)
+    let config = tokio::fs::read_to_string(cli_world.path(&path))
         .await
         .expect("Error reading config file");
     let config = serde_yaml::from_str::<Config>(&config).expect("Error parsing config");
@@ -456,7 +522,7 @@ pub(crate) async fn the_file_should_be_a_valid_config(cli_world: &mut CliWorld,
 #[then(regex = r#"^the file "([\S "]+)" should contain:$"#)]
 pub(crate) async fn the_file_should_contain(cli_world: &mut CliWorld, step: &Step, path: String) {
     cli_world.trigger().await;
-    let config = tokio::fs::read_to_string(&path)
+    let config = tokio::fs::read_to_string(cli_world.path(&path))
         .await
         .expect("Error reading config file");
 
@@ -492,7 +558,7 @@ fn regex_from_step(step: &Step) -> (regex::Regex, String) {
             .multi_line(true)
             .dot_matches_new_line(true)
             .build()
-            .expect(&format!("Error parsing regex {}", regex_string)),
+            .unwrap_or_else(|_| panic!("Error parsing regex {}", regex_string)),
         regex_string,
     )
 }
@@ -519,7 +585,7 @@ pub(crate) async fn the_stderr_should_have_matched(cli_world: &mut CliWorld, ste
 pub(crate) async fn the_test_config_should_be_unchanged(cli_world: &mut CliWorld) {
     cli_world.trigger().await;
     let config = test_config();
-    let actual = tokio::fs::read_to_string("config.yaml")
+    let actual = tokio::fs::read_to_string(cli_world.path("config.yaml"))
         .await
         .expect("Error reading config file");
     let actual = serde_yaml::from_str::<Config>(&actual).expect("Error parsing config");
@@ -602,8 +668,5 @@ pub(crate) async fn the_request_should_return_a_header_value_of(
 }
 
 async fn is_port_open(addr: &str) -> bool {
-    match TcpListener::bind(addr).await {
-        Ok(_) => false,
-        _ => true,
-    }
+    TcpListener::bind(addr).await.is_err()
 }
@@ -0,0 +1,21 @@
+@serial
+Feature: Export subcommand
+
+  Scenario: Export needs a source
+    When I run "cloud_scraper export"
+    Then the stderr should have matched:
+    """--source <SOURCE>"""
+    And the exit code should not be 0
+
+  Scenario: Export rejects a bad date
+    When I run "cloud_scraper export --source stub --since yesterday"
+    Then the stderr should have matched:
+    """yesterday is not a date like 2024-06-01 or an RFC 3339 time"""
+    And the exit code should not be 0
+
+  Scenario: Export an unknown source
+    Given no file named "config.yaml"
+    When I run "cloud_scraper export --source nothing --format ndjson"
+    Then the stdout should have been:
+    """"""
+    And the exit code should be 0
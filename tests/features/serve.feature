@@ -1,23 +1,26 @@
 @serial
 Feature: Serve subcommand
 
-  Scenario: Serve without root password
+  Scenario: Serve without users
     Given no file named "root_password.yaml"
+    Given no file named "users.yaml"
     When I run "cloud_scraper serve --port=8080"
     Then the stderr should have matched:
-    """Root password not set"""
+    """No users set"""
     And the exit code should not be 0
 
   Scenario: Serve env debug
     Given an environment variable "RUST_LOG" with the value "debug"
     Given no file named "config.yaml"
     Given no file named "state/google/config.yaml"
+    Given no file named "state/google/since.yaml"
+    Given no file named "users.yaml"
     Given a file named "root_password.yaml" containing:
     """"""
     When I run "cloud_scraper serve --exit-after=1 --port=8080"
     Then the stderr should have matched:
     """\s*\[[\d]{4}-[\d]{2}-[\d]{2}T[\d]{2}:[\d]{2}:[\d]{2}Z DEBUG cloud_scraper::main_impl\] Reading cli input\.\.\.
-\s*\[[\d]{4}-[\d]{2}-[\d]{2}T[\d]{2}:[\d]{2}:[\d]{2}Z DEBUG cloud_scraper::main_impl\] Checking root password\.\.\.
+\s*\[[\d]{4}-[\d]{2}-[\d]{2}T[\d]{2}:[\d]{2}:[\d]{2}Z DEBUG cloud_scraper::main_impl\] Checking users\.\.\.
 \s*\[[\d]{4}-[\d]{2}-[\d]{2}T[\d]{2}:[\d]{2}:[\d]{2}Z DEBUG cloud_scraper::main_impl\] Reading config\.\.\.
 \s*\[[\d]{4}-[\d]{2}-[\d]{2}T[\d]{2}:[\d]{2}:[\d]{2}Z DEBUG cloud_scraper::main_impl\] Checking config\.\.\.
 \s*\[[\d]{4}-[\d]{2}-[\d]{2}T[\d]{2}:[\d]{2}:[\d]{2}Z DEBUG cloud_scraper::main_impl\] Constructing server\.\.\.
@@ -41,6 +44,7 @@ Feature: Serve subcommand
     Given an environment variable "RUST_LOG" with the value "debug"
     Given no file named "config.yaml"
     Given no file named "state/google/config.yaml"
+    Given no file named "users.yaml"
     Given a file named "root_password.yaml" containing:
     """"""
     When I run "cloud_scraper serve --config=tests/fixtures/empty_config.yaml --port=8080"
@@ -56,6 +60,7 @@ Feature: Serve subcommand
     Given an environment variable "RUST_LOG" with the value "debug"
     Given no file named "config.yaml"
     Given no file named "state/google/config.yaml"
+    Given no file named "users.yaml"
     Given a file named "root_password.yaml" containing:
     """"""
     When I run "cloud_scraper serve --config=tests/fixtures/empty_config.yaml --exit-after=0"
@@ -69,6 +74,7 @@ Feature: Serve subcommand
   Scenario: Start the service without HTTPS, opens the configured port and serves the API
     Given no file named "config.yaml"
     Given no file named "root_password.yaml"
+    Given no file named "users.yaml"
     Given a file named "root_password.yaml" containing:
     """hash: b4f27c30d7530f6f8d9edca87a86c867d9a1d537
 salt: eyF8Ak6G48ZrRBs0
@@ -86,6 +92,7 @@ email: email@test.scenario.domain
     And the exit code should be 0
 
   Scenario: Login page is served
+    Given no file named "users.yaml"
     Given a file named "root_password.yaml" containing:
     """hash: b4f27c30d7530f6f8d9edca87a86c867d9a1d537
 salt: eyF8Ak6G48ZrRBs0
@@ -99,10 +106,11 @@ email: email@test.scenario.domain
     When I request "GET" "http://localhost:4321/login"
     Then the request "GET" "http://localhost:4321/login" should return a status code of 200
     And the request "GET" "http://localhost:4321/login" should return a response matching:
-    """.*Admin Login.*
+    """.*<h1>Login</h1>.*
     """
 
   Scenario: Login redirects to index
+    Given no file named "users.yaml"
     Given a file named "root_password.yaml" containing:
     """hash: b4f27c30d7530f6f8d9edca87a86c867d9a1d537
 salt: eyF8Ak6G48ZrRBs0
@@ -115,8 +123,27 @@ email: email@test.scenario.domain
     When I start "cloud_scraper serve --exit-after=2"
     When I request "GET" "http://localhost:4321/login"
     When I request "POST" "http://localhost:4321/login" with body:
-    """password=test"""
+    """username=root&password=test&csrf_token={cookie:csrf}"""
     Then the request "POST" "http://localhost:4321/login" should return a status code of 302
     And the request "POST" "http://localhost:4321/login" should return a response matching:
     """"""
     And the request "POST" "http://localhost:4321/login" should return a header "location" with "/"
+
+  Scenario: Login without a CSRF token is forbidden
+    Given no file named "users.yaml"
+    Given a file named "root_password.yaml" containing:
+    """hash: b4f27c30d7530f6f8d9edca87a86c867d9a1d537
+salt: eyF8Ak6G48ZrRBs0
+"""
+    Given a file named "config.yaml" containing:
+    """domain_config:
+  url: http://localhost:4321/
+email: email@test.scenario.domain
+"""
+    When I start "cloud_scraper serve --exit-after=2"
+    When I request "POST" "http://localhost:4321/login" with body:
+    """username=root&password=test"""
+    Then the request "POST" "http://localhost:4321/login" should return a status code of 403
+    And the request "POST" "http://localhost:4321/login" should return a response matching:
+    """.*Forbidden.*
+    """
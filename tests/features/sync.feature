@@ -0,0 +1,33 @@
+@serial
+Feature: Sync subcommand
+
+  Scenario: Sync runs each source once and exits
+    Given an environment variable "RUST_LOG" with the value "debug"
+    Given no file named "root_password.yaml"
+    Given a file named "config.yaml" containing:
+    """sinks:
+  log: {}
+sources:
+  stub: {}
+"""
+    When I run "cloud_scraper sync"
+    Then the stderr should have matched:
+      """Reading config\.\.\.
+.*Checking config\.\.\.
+.*Constructing engine\.\.\.
+.*Syncing
+      """
+    And the exit code should be 0
+
+  Scenario: Sync fails without a stored token
+    Given no file named "state/google/config.yaml"
+    Given a file named "config.yaml" containing:
+    """sinks:
+  log: {}
+sources:
+  google: {}
+"""
+    When I run "cloud_scraper sync"
+    Then the stderr should have matched:
+    """google failed: Not authorized"""
+    And the exit code should not be 0